@@ -1,15 +1,32 @@
 use clap::Parser;
-use fingerprinting_cli::config::{FingerprintServiceConfig, GrpcConfig};
+use fingerprinting_cli::config::{
+    load_secret_shards, member_identities, FingerprintSchemaConfig, FingerprintServiceConfig,
+    FingerprintStoreConfig, GrpcConfig, TenantAllowlistConfig,
+};
 use fingerprinting_cli::HealthRegistryService;
-use fingerprinting_core::{CollaborativeProtocol, Compact, NaiveProtocol};
-use fingerprinting_grpc::{net as fp, FingerprintService};
-use fingerprinting_grpc_agent::{net as fp_agent, CooperationAgentService, GrpcAgentsTopology};
+use fingerprinting_core::curves::{Curve, FingerprintCurve};
+use fingerprinting_core::schema::SchemaRegistry;
+use fingerprinting_core::secret_sharing::ShareCommitments;
+use fingerprinting_core::tenant::TenantKey;
+use fingerprinting_core::verification::{derive_group_key, verify_public_share};
+use fingerprinting_core::{
+    CollaborativeProtocol, Compact, DomainSeparationTag, FingerprintProtocol, NaiveProtocol,
+};
+use fingerprinting_grpc::{net as fp, FingerprintService, GroupKey, TenantAllowlist};
+use fingerprinting_grpc_agent::{
+    net as fp_agent, AgentParameters, CooperationAgentService, GrpcAgentsTopology,
+};
+use fingerprinting_store::FingerprintStore;
 use grpc_health_checking::grpc::health::v1::HealthServer;
 use grpc_health_checking::HealthRegistry;
-use halo2_axiom::halo2curves::bn256::Fr;
+use halo2_axiom::halo2curves::bls12_381;
+use halo2_axiom::halo2curves::bn256::{self, Fr};
+use halo2_axiom::halo2curves::group::Group;
 use hocon::HoconLoader;
 use serde_derive::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use volo::net::Address;
 use volo_grpc::codegen::futures;
 use volo_grpc::server::{Server, ServiceBuilder};
@@ -32,6 +49,12 @@ struct FingerprintingServiceConfig {
     management_grpc: GrpcConfig,
     #[serde(rename = "fingerprint-service")]
     fingerprint_service: FingerprintServiceConfig,
+    #[serde(rename = "fingerprint-schema")]
+    fingerprint_schema: FingerprintSchemaConfig,
+    #[serde(rename = "fingerprint-store")]
+    fingerprint_store: Option<FingerprintStoreConfig>,
+    #[serde(rename = "tenant-allowlist")]
+    tenant_allowlist: Option<TenantAllowlistConfig>,
 }
 #[volo::main]
 async fn main() -> Result<(), anyhow::Error> {
@@ -50,37 +73,229 @@ async fn main() -> Result<(), anyhow::Error> {
         .load_file(args.config)?
         .resolve()?;
 
+    match conf.fingerprint_service.curve()? {
+        Curve::Bn256 => serve::<bn256::G1>(conf).await,
+        Curve::Bls12_381 => serve::<bls12_381::G1>(conf).await,
+    }
+}
+
+/// Serve the fingerprint service (and the cooperation service in Cooperative mode) on the curve `G`
+async fn serve<G: FingerprintCurve>(
+    conf: FingerprintingServiceConfig,
+) -> Result<(), anyhow::Error> {
+    let dst = conf.fingerprint_service.dst()?;
+    let schemas: SchemaRegistry = conf.fingerprint_schema.try_into()?;
+    let schemas = schemas.with_dst(dst.clone());
+    log::info!(
+        "== Fingerprint schema versions {:?}, computing with version {}, domain separation tag {}, curve {}",
+        schemas.versions().collect::<Vec<_>>(),
+        schemas.current().version(),
+        dst,
+        G::CURVE.name()
+    );
+
+    let store: Option<Arc<dyn FingerprintStore>> = match conf.fingerprint_store {
+        Some(store_config) => {
+            log::info!("== Recording fingerprints in {:?}", store_config);
+            let store: Arc<dyn FingerprintStore> = store_config.try_into()?;
+            tokio::spawn(purge_fingerprints(Arc::clone(&store)));
+
+            Some(store)
+        }
+        None => None,
+    };
+
+    let tenant_key = conf.fingerprint_service.tenant_key()?;
+    let allowlist: Option<TenantAllowlist> = match conf.tenant_allowlist {
+        Some(allowlist_config) => {
+            log::info!(
+                "== Tenants allowed to callers {:?}",
+                allowlist_config.callers
+            );
+            log::warn!("== Bearer tokens are received over plaintext HTTP/2, expose the fingerprint service only through a TLS-terminating proxy");
+            Some(allowlist_config.try_into()?)
+        }
+        None => None,
+    };
+
+    let mut parameters_check = None;
     let (fingerprint_server, agent_server): (Server, Option<Server>) = match conf
         .fingerprint_service
     {
         FingerprintServiceConfig::Cooperative(topology_config) => {
             log::info!("== Starting SRA Fingerprint agent in Cooperative mode with {} agents and {} threshold", topology_config.agents, topology_config.threshold);
-            let topology = GrpcAgentsTopology::new(
-                topology_config.agents,
-                topology_config.threshold,
-                topology_config
-                    .members
-                    .iter()
-                    .map(|agent| (agent.agent_id, agent.address.to_string()))
-                    .collect(),
+            let shards = load_secret_shards::<G>(
+                topology_config.agent_id,
+                topology_config.generation,
+                &topology_config.secret_shard,
+                topology_config.commitments.as_ref(),
+                &topology_config.retained_generations,
+            )?;
+
+            for (generation, commitments) in &shards.commitments {
+                if commitments.threshold() != topology_config.threshold {
+                    return Err(anyhow::anyhow!(
+                        "Published commitments of generation {} are for threshold {}, but configured threshold is {}",
+                        generation,
+                        commitments.threshold(),
+                        topology_config.threshold
+                    ));
+                }
+            }
+
+            // Derive public shares of every generation from its published commitments
+            let mut public_shares: HashMap<u64, HashMap<usize, G>> = shards
+                .commitments
+                .iter()
+                .map(|(generation, commitments)| {
+                    let generation_shares = topology_config
+                        .members
+                        .iter()
+                        .map(|agent| (agent.agent_id, commitments.public_share(agent.agent_id)))
+                        .collect();
+                    (*generation, generation_shares)
+                })
+                .collect();
+
+            // Explicitly configured public shares are for the current generation
+            for agent in &topology_config.members {
+                if let Some(share) = &agent.public_share {
+                    public_shares
+                        .entry(topology_config.generation)
+                        .or_default()
+                        .insert(agent.agent_id, Compact::unwrap(share)?);
+                }
+            }
+
+            let members_topology = || {
+                GrpcAgentsTopology::<G>::new(
+                    topology_config.agents,
+                    topology_config.threshold,
+                    topology_config
+                        .members
+                        .iter()
+                        .map(|agent| (agent.agent_id, agent.address.to_string()))
+                        .collect(),
+                )
+            };
+
+            let mut topology = public_shares.into_iter().fold(
+                members_topology(),
+                |topology, (generation, generation_shares)| {
+                    topology.with_public_shares(generation, generation_shares)
+                },
             );
 
+            // Responses of members without a public share can't be verified and would be rejected
+            let unverifiable = topology.members_without_public_share(topology_config.generation);
+            if !unverifiable.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Members {:?} have no public share of generation {}, configure their `public_share` or the `commitments`",
+                    unverifiable,
+                    topology_config.generation
+                ));
+            }
+
+            if topology_config.hedge_delay_ms > 0 {
+                topology = topology
+                    .with_hedge_delay(Duration::from_millis(topology_config.hedge_delay_ms));
+            }
+
+            if topology_config.redundancy > 0 {
+                if topology_config.threshold + topology_config.redundancy > topology_config.agents {
+                    return Err(anyhow::anyhow!(
+                        "Redundancy {} with threshold {} exceeds the number of agents {}",
+                        topology_config.redundancy,
+                        topology_config.threshold,
+                        topology_config.agents
+                    ));
+                }
+
+                topology = topology.with_redundancy(topology_config.redundancy);
+            }
+
             log::info!(
                 "== Built topology with members: {:?}",
                 topology_config.members
             );
 
-            let current_agent_secret = Compact::unwrap(&topology_config.secret_shard)?;
-            let cooperation_service = CooperationAgentService::new(current_agent_secret);
+            let mut cooperation_service =
+                CooperationAgentService::<G>::with_generations(shards.secret_shards.clone())
+                    .with_dst(dst.clone());
+            if let Some(tenant_key) = &tenant_key {
+                // Members authenticate their requests for tenants to each other
+                let (identity, members) = member_identities::<G>(
+                    topology_config.identity_key.as_deref(),
+                    topology_config
+                        .members
+                        .iter()
+                        .map(|member| (member.agent_id, member.identity.as_ref())),
+                )?;
+                topology =
+                    topology.with_identity(topology_config.agent_id, &identity, members.clone())?;
+                cooperation_service = cooperation_service
+                    .with_tenant_key(tenant_key.clone())
+                    .with_members(topology_config.agent_id, &identity, members)?;
+            }
+            for generation in shards.secret_shards.keys() {
+                if let Some(public_share) = cooperation_service.public_share(*generation) {
+                    log::info!(
+                        "== Agent public share of generation {}: {}",
+                        generation,
+                        public_share.compact()
+                    );
+                }
+            }
 
-            let protocol = CollaborativeProtocol::new(
-                (topology_config.agent_id, current_agent_secret),
+            // Publish the group key in G2 once enough members report their public shares
+            let group_key = GroupKey::default();
+            match (
+                shards.commitments.get(&topology_config.generation),
+                cooperation_service.public_share_g2(topology_config.generation),
+            ) {
+                (Some(commitments), Some(own_share_g2)) => {
+                    tokio::spawn(publish_group_key(
+                        members_topology(),
+                        topology_config.generation,
+                        commitments.clone(),
+                        (topology_config.agent_id, own_share_g2),
+                        Arc::clone(&group_key),
+                    ));
+                }
+                _ => log::warn!(
+                    "== No commitments of generation {}, group key will not be published",
+                    topology_config.generation
+                ),
+            }
+
+            let mut protocol = CollaborativeProtocol::with_generations(
+                topology_config.agent_id,
+                topology_config.generation,
+                shards.secret_shards,
                 topology,
-            );
+            )?
+            .with_dst(dst.clone());
+            if let Some(tenant_key) = &tenant_key {
+                protocol = protocol.with_tenant_key(tenant_key.clone());
+            }
+
+            // Members hashing with another tag or on another curve would compute unrelated fingerprints, the agent stops
+            parameters_check = Some(verify_members_parameters(
+                members_topology(),
+                topology_config.members.len(),
+                dst.clone(),
+                tenant_key.as_ref().map(TenantKey::id).unwrap_or_default(),
+            ));
 
             let fingerprint_server = Server::new().add_service(
                 ServiceBuilder::new(fp::pso::transaction_fingerprinting::fingerprint::v1::FingerprintServiceServer::new(
-                    FingerprintService::new(protocol),
+                    with_options(
+                        FingerprintService::new(protocol)
+                            .with_group_key(group_key)
+                            .with_schemas(schemas),
+                        store,
+                        allowlist,
+                    ),
                 ))
                 .build(),
             );
@@ -101,14 +316,25 @@ async fn main() -> Result<(), anyhow::Error> {
                 "== Starting SRA Fingerprint agent in Naive mode with predefined secret: {}",
                 naive.secret
             );
-            let secret: Fr = Compact::unwrap(&naive.secret)?;
+            let secret: G::ScalarExt = Compact::unwrap(&naive.secret)?;
 
-            let protocol = NaiveProtocol::new(secret);
+            let mut protocol = NaiveProtocol::<G>::new(secret).with_dst(dst.clone());
+            if let Some(tenant_key) = tenant_key {
+                protocol = protocol.with_tenant_key(tenant_key);
+            }
+            let group_key =
+                GroupKey::new((G::generator() * secret, G::G2::generator() * secret).into());
 
             (
                 Server::new().add_service(
                     ServiceBuilder::new(fp::pso::transaction_fingerprinting::fingerprint::v1::FingerprintServiceServer::new(
-                        FingerprintService::new(protocol),
+                        with_options(
+                            FingerprintService::new(protocol)
+                                .with_group_key(group_key)
+                                .with_schemas(schemas),
+                            store,
+                            allowlist,
+                        ),
                     ))
                     .build(),
                 ),
@@ -142,7 +368,7 @@ async fn main() -> Result<(), anyhow::Error> {
         .accept_http1(true)
         .run(management_address);
 
-    match agent_server {
+    match agent_server.zip(parameters_check) {
         None => {
             let fingerprint_server = fingerprint_server
                 .http2_adaptive_window(true)
@@ -154,7 +380,7 @@ async fn main() -> Result<(), anyhow::Error> {
                 .map(|_| ())
                 .map_err(|e| anyhow::anyhow!(e))
         }
-        Some(agent_server) => {
+        Some((agent_server, parameters_check)) => {
             let agent_grpc_address: Address = conf.agent_grpc.try_into()?;
             log::info!("== starting Agent GRPC server on {}", agent_grpc_address);
 
@@ -163,15 +389,188 @@ async fn main() -> Result<(), anyhow::Error> {
                 .accept_http1(true)
                 .run(agent_grpc_address);
 
-            let fingerprint_server = fingerprint_server
-                .http2_adaptive_window(true)
-                .accept_http1(true)
-                .run(fingerprint_grpc_address);
+            // Fingerprints are served only once all members confirmed their parameters,
+            // the agent server answers the checks of other members meanwhile
+            let fingerprint_server = async {
+                tokio::time::timeout(PARAMETERS_CHECK_TIMEOUT, parameters_check)
+                    .await
+                    .map_err(|_| {
+                        anyhow::anyhow!(
+                            "Members did not confirm their parameters within {:?}",
+                            PARAMETERS_CHECK_TIMEOUT
+                        )
+                    })??;
+
+                fingerprint_server
+                    .http2_adaptive_window(true)
+                    .accept_http1(true)
+                    .run(fingerprint_grpc_address)
+                    .await
+                    .map_err(|e| anyhow::anyhow!(e))
+            };
+            let agent_server = async { agent_server.await.map_err(|e| anyhow::anyhow!(e)) };
+            let heath_server = async { heath_server.await.map_err(|e| anyhow::anyhow!(e)) };
 
             futures::future::try_join3(agent_server, fingerprint_server, heath_server)
                 .await
                 .map(|_| ())
-                .map_err(|e| anyhow::anyhow!(e))
+        }
+    }
+}
+
+/// Interval between purges of fingerprint occurrences outside of the retention window
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Record fingerprints in the `store` and enforce the tenant `allowlist` when they are configured
+fn with_options<P: FingerprintProtocol<Fr, Point: FingerprintCurve> + Sync>(
+    service: FingerprintService<P>,
+    store: Option<Arc<dyn FingerprintStore>>,
+    allowlist: Option<TenantAllowlist>,
+) -> FingerprintService<P> {
+    let service = match store {
+        Some(store) => service.with_store(store),
+        None => service,
+    };
+
+    match allowlist {
+        Some(allowlist) => service.with_tenant_allowlist(allowlist),
+        None => service,
+    }
+}
+
+/// Periodically forget fingerprint occurrences outside of the retention window of the `store`
+async fn purge_fingerprints(store: Arc<dyn FingerprintStore>) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let store = Arc::clone(&store);
+        match tokio::task::spawn_blocking(move || store.purge(chrono::Utc::now())).await {
+            Ok(Ok(purged)) => log::info!("== Purged {} fingerprint occurrences", purged),
+            Ok(Err(e)) => log::error!("Failed to purge fingerprint occurrences: {}", e),
+            Err(e) => log::error!("Failed to purge fingerprint occurrences: {}", e),
+        }
+    }
+}
+
+/// Interval between attempts to collect public shares of members
+const GROUP_KEY_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Time the members have to confirm their parameters before the agent stops without serving fingerprints
+const PARAMETERS_CHECK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Check that all `members` of the topology hash to curve with the `dst` on the curve `G`
+/// and derive tenant scalars with the same tenant key, retrying until all of them respond
+async fn verify_members_parameters<G: FingerprintCurve>(
+    topology: GrpcAgentsTopology<G>,
+    members: usize,
+    dst: DomainSeparationTag,
+    tenant_key_id: String,
+) -> Result<(), anyhow::Error> {
+    loop {
+        let parameters = topology.obtain_parameters().await;
+
+        for (
+            agent,
+            AgentParameters {
+                dst: member_dst,
+                curve,
+                tenant_key_id: member_tenant_key_id,
+            },
+        ) in &parameters
+        {
+            if member_dst != dst.as_str() {
+                return Err(anyhow::anyhow!(
+                    "Agent {} uses the domain separation tag {}, this agent uses {}",
+                    agent,
+                    member_dst,
+                    dst
+                ));
+            }
+
+            if curve != G::CURVE.name() {
+                return Err(anyhow::anyhow!(
+                    "Agent {} computes on curve {}, this agent on {}",
+                    agent,
+                    curve,
+                    G::CURVE.name()
+                ));
+            }
+
+            // Members with another tenant key compute unrelated fingerprints of tenants
+            if *member_tenant_key_id != tenant_key_id {
+                return Err(anyhow::anyhow!(
+                    "Agent {} uses the tenant key {:?}, this agent uses {:?}",
+                    agent,
+                    member_tenant_key_id,
+                    tenant_key_id
+                ));
+            }
+        }
+
+        if parameters.len() == members {
+            log::info!(
+                "== All {} members use the domain separation tag {} and curve {}",
+                members,
+                dst,
+                G::CURVE.name()
+            );
+            return Ok(());
+        }
+
+        log::warn!(
+            "Parameters are confirmed by {} of {} members, retrying in {:?}",
+            parameters.len(),
+            members,
+            GROUP_KEY_RETRY_INTERVAL
+        );
+        tokio::time::sleep(GROUP_KEY_RETRY_INTERVAL).await;
+    }
+}
+
+/// Derive the group key `[k] G2` from the public shares of members checked against the `commitments`,
+/// retrying until enough members respond
+async fn publish_group_key<G: FingerprintCurve>(
+    topology: GrpcAgentsTopology<G>,
+    generation: u64,
+    commitments: ShareCommitments<G>,
+    own_share_g2: (usize, G::G2),
+    group_key: GroupKey<G>,
+) {
+    loop {
+        // Members not reachable (or not resolvable) yet are skipped and asked again in the next round
+        let mut public_shares = topology.obtain_public_shares_g2(generation).await;
+        public_shares.push(own_share_g2);
+        public_shares.retain(|(agent, public_share_g2)| {
+            let valid = verify_public_share(&commitments.public_share(*agent), public_share_g2);
+            if !valid {
+                log::error!(
+                    "Public share of agent {} does not match the commitments, share rejected",
+                    agent
+                );
+            }
+            valid
+        });
+
+        match derive_group_key(&commitments, &public_shares) {
+            Ok(group_key_g2) => {
+                log::info!("== Group key in G2: {}", group_key_g2.compact());
+                group_key.get_or_init(|| (commitments.group_key(), group_key_g2));
+                return;
+            }
+            Err(e) => {
+                log::warn!(
+                    "Group key is not derived yet from the public shares of agents {:?}, retrying in {:?}: {}",
+                    public_shares
+                        .iter()
+                        .map(|(agent, _)| *agent)
+                        .collect::<Vec<_>>(),
+                    GROUP_KEY_RETRY_INTERVAL,
+                    e
+                );
+                tokio::time::sleep(GROUP_KEY_RETRY_INTERVAL).await;
+            }
         }
     }
 }
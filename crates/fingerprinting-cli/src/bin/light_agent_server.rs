@@ -1,6 +1,5 @@
 use clap::Parser;
 use fingerprinting_grpc_agent::{net, CooperationAgentService};
-use halo2_axiom::halo2curves::bn256::Fr;
 use hocon::HoconLoader;
 use serde_derive::Deserialize;
 use std::sync::Arc;
@@ -9,11 +8,15 @@ use volo::net::Address;
 use volo_grpc::codegen::futures;
 use volo_grpc::server::{Server, ServiceBuilder};
 
-use fingerprinting_cli::config::{AgentConfig, GrpcConfig};
+use fingerprinting_cli::config::{
+    load_secret_shards, load_tenant_key, member_identities, AgentConfig, GrpcConfig,
+};
 use fingerprinting_cli::HealthRegistryService;
-use fingerprinting_core::Compact;
+use fingerprinting_core::curves::{Curve, FingerprintCurve};
+use fingerprinting_core::{Compact, DomainSeparationTag};
 use grpc_health_checking::grpc::health::v1::HealthServer;
 use grpc_health_checking::HealthRegistry;
+use halo2_axiom::halo2curves::{bls12_381, bn256};
 
 #[derive(Parser, Debug)]
 #[command(name = "fingerprinting-light-agent")]
@@ -48,6 +51,14 @@ async fn main() -> Result<(), anyhow::Error> {
         .load_file(args.config)?
         .resolve()?;
 
+    match conf.agent.curve.parse()? {
+        Curve::Bn256 => serve::<bn256::G1>(conf).await,
+        Curve::Bls12_381 => serve::<bls12_381::G1>(conf).await,
+    }
+}
+
+/// Serve the cooperation service with the secret shards on the curve `G`
+async fn serve<G: FingerprintCurve>(conf: LightAgentConfig) -> Result<(), anyhow::Error> {
     let fingerprint_agent_grpc_address: Address = conf.grpc.try_into()?;
     log::info!(
         "== starting GRPC server on {}",
@@ -68,10 +79,52 @@ async fn main() -> Result<(), anyhow::Error> {
     health_registry.register(health_service);
     let heath_registry_service = ServiceBuilder::new(HealthServer::new(health_registry)).build();
 
-    let secret_shard: Fr =
-        Compact::unwrap(&conf.agent.secret_shard).expect("Cannot parse secret shard");
+    let shards = load_secret_shards::<G>(
+        conf.agent.agent_id,
+        conf.agent.generation,
+        &conf.agent.secret_shard,
+        conf.agent.commitments.as_ref(),
+        &conf.agent.retained_generations,
+    )?;
+
+    let dst = DomainSeparationTag::new(&conf.agent.dst)?;
+    log::info!(
+        "== Agent domain separation tag {}, curve {}",
+        dst,
+        G::CURVE.name()
+    );
 
-    let service = CooperationAgentService::new(secret_shard);
+    let mut service =
+        CooperationAgentService::<G>::with_generations(shards.secret_shards).with_dst(dst);
+    if let Some(tenant_key) = load_tenant_key(conf.agent.tenant_key.as_deref())? {
+        // Tenants are served only to members authenticating their requests
+        let (identity, members) = member_identities::<G>(
+            conf.agent.identity_key.as_deref(),
+            conf.agent
+                .members
+                .iter()
+                .flatten()
+                .map(|member| (member.agent_id, Some(&member.identity))),
+        )?;
+        service = service.with_tenant_key(tenant_key).with_members(
+            conf.agent.agent_id,
+            &identity,
+            members,
+        )?;
+    }
+    for generation in [conf.agent.generation]
+        .into_iter()
+        .chain(conf.agent.retained_generations.iter().map(|r| r.generation))
+    {
+        if let Some(public_share) = service.public_share(generation) {
+            log::info!(
+                "== Agent {} public share of generation {}: {}",
+                conf.agent.agent_id,
+                generation,
+                public_share.compact()
+            );
+        }
+    }
 
     let fingerprint_server = Server::new()
         .http2_adaptive_window(true)
@@ -1,16 +1,63 @@
+use anyhow::anyhow;
+use fingerprinting_core::curves::{Curve, FingerprintCurve};
+use fingerprinting_core::identity::AgentIdentity;
+use fingerprinting_core::schema::{FingerprintSchema, SchemaRegistry};
+use fingerprinting_core::secret_sharing::{SecretSharing, ShareCommitments};
+use fingerprinting_core::tenant::{Tenant, TenantKey};
+use fingerprinting_core::{Compact, DomainSeparationTag};
+use fingerprinting_grpc::TenantAllowlist;
+use fingerprinting_grpc_agent::CeremonyMember;
+use fingerprinting_store::{EmbeddedStore, FingerprintStore, MemoryStore};
+use halo2_axiom::halo2curves::bn256::G1;
 use serde_derive::Deserialize;
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use volo::net::Address;
 
 #[derive(Deserialize, Debug)]
 pub struct AgentConfig {
     pub agent_id: usize,
+    /// Secret generation of the `secret_shard`
+    pub generation: u64,
     pub secret_shard: String,
+    /// Published Feldman commitments of the sharing polynomial
+    pub commitments: Option<String>,
+    /// Shards of other generations still served during the proactive refresh
+    pub retained_generations: Vec<SecretShardConfig>,
+    /// Domain separation tag of the network, reported to other agents
+    pub dst: String,
+    /// Curve of the network, `bn256` or `bls12-381`
+    pub curve: String,
+    /// Secret tenant scalars are derived with (base58), only the default tenant is served without it
+    pub tenant_key: Option<String>,
+    /// Identity key of the agent, required with the `tenant_key` to authenticate requests of the `members`
+    pub identity_key: Option<String>,
+    /// Members whose requests for tenants other than the default one are served
+    pub members: Option<Vec<MemberIdentityConfig>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MemberIdentityConfig {
+    pub agent_id: usize,
+    /// Public identity `[x_i] G` authenticating requests of the agent
+    pub identity: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SecretShardConfig {
+    pub generation: u64,
+    pub secret_shard: String,
+    pub commitments: Option<String>,
 }
 #[derive(Deserialize, Debug)]
 pub struct AgentReferenceConfig {
     pub agent_id: usize,
     pub address: String,
+    /// Public share commitment `[s_i] G` used to verify agent responses, derived from the commitments when not set
+    pub public_share: Option<String>,
+    /// Public identity `[x_i] G` authenticating messages of the agent, required for ceremonies and tenants
+    pub identity: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -21,15 +68,93 @@ pub struct GrpcConfig {
 #[derive(Deserialize, Debug)]
 pub struct CooperativeTopologyConfig {
     pub agent_id: usize,
+    /// Secret generation of the `secret_shard`, requested from other agents
+    pub generation: u64,
     pub secret_shard: String,
+    /// Published Feldman commitments of the sharing polynomial
+    pub commitments: Option<String>,
+    /// Shards of other generations still served during the proactive refresh
+    pub retained_generations: Vec<SecretShardConfig>,
     pub agents: usize,
     pub threshold: usize,
+    /// Delay before asking one more member while the fastest `threshold - 1` haven't responded,
+    /// `0` asks all members at once
+    pub hedge_delay_ms: u64,
+    /// Responses requested beyond the threshold to detect and exclude agents returning faulty shards,
+    /// `0` trusts the first threshold responses
+    pub redundancy: usize,
     pub members: Vec<AgentReferenceConfig>,
+    /// Domain separation tag of the hash to curve, all members must use the same one
+    pub dst: String,
+    /// Curve of the network, `bn256` or `bls12-381`, all members must use the same one
+    pub curve: String,
+    /// Secret tenant scalars are derived with (base58), all members must use the same one
+    pub tenant_key: Option<String>,
+    /// Identity key of the agent, required with the `tenant_key` to authenticate requests for tenants
+    /// to the members, which must have their `identity` configured
+    pub identity_key: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct KeyGenerationConfig {
+    /// Identifier of the ceremony, must be the same for all participating agents
+    pub ceremony_id: String,
+    pub agent_id: usize,
+    pub agents: usize,
+    pub threshold: usize,
+    /// Secret generation produced by the ceremony, 0 for the key generation
+    pub generation: u64,
+    /// Secret shard and commitments of the previous generation (refresh only)
+    pub secret_shard: Option<String>,
+    pub commitments: Option<String>,
+    /// Interval between delivery attempts to unreachable members
+    pub retry_interval_ms: u64,
+    /// Identity key of the agent authenticating its dealings, members know its public identity
+    pub identity_key: String,
+    pub members: Vec<AgentReferenceConfig>,
+    /// Curve of the generated secret, `bn256` or `bls12-381`
+    pub curve: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ResharingConfig {
+    /// Identifier of the ceremony, must be the same for all participating agents
+    pub ceremony_id: String,
+    /// Secret generation produced for the new committee
+    pub generation: u64,
+    /// Published commitments of the old committee
+    pub commitments: String,
+    /// Agents of the old committee redistributing their shards, at least the old threshold
+    pub dealers: Vec<usize>,
+    /// Agent number and secret shard in the old committee (dealers only)
+    pub old_agent_id: Option<usize>,
+    pub secret_shard: Option<String>,
+    /// Agent number in the new committee (new committee members only)
+    pub agent_id: Option<usize>,
+    /// Size and threshold of the new committee
+    pub agents: usize,
+    pub threshold: usize,
+    /// Interval between delivery attempts to unreachable members
+    pub retry_interval_ms: u64,
+    /// Identity key of the agent authenticating its dealings and transcripts, other agents know its public identity
+    pub identity_key: String,
+    /// Public identities of the other `dealers` by their agent number in the old committee (new committee members only)
+    pub dealer_identities: Vec<MemberIdentityConfig>,
+    /// Members of the new committee with their public `identity`
+    pub members: Vec<AgentReferenceConfig>,
+    /// Curve of the reshared secret, `bn256` or `bls12-381`
+    pub curve: String,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct NaiveTopologyConfig {
     pub secret: String,
+    /// Domain separation tag of the hash to curve
+    pub dst: String,
+    /// Curve of the secret, `bn256` or `bls12-381`
+    pub curve: String,
+    /// Secret tenant scalars are derived with (base58)
+    pub tenant_key: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -39,6 +164,180 @@ pub enum FingerprintServiceConfig {
     Naive(NaiveTopologyConfig),
 }
 
+impl FingerprintServiceConfig {
+    /// Domain separation tag the protocol and the schemas hash to curve with
+    pub fn dst(&self) -> Result<DomainSeparationTag, anyhow::Error> {
+        match self {
+            FingerprintServiceConfig::Cooperative(cooperative) => {
+                DomainSeparationTag::new(&cooperative.dst)
+            }
+            FingerprintServiceConfig::Naive(naive) => DomainSeparationTag::new(&naive.dst),
+        }
+    }
+
+    /// Curve the secret is applied on
+    pub fn curve(&self) -> Result<Curve, anyhow::Error> {
+        match self {
+            FingerprintServiceConfig::Cooperative(cooperative) => cooperative.curve.parse(),
+            FingerprintServiceConfig::Naive(naive) => naive.curve.parse(),
+        }
+    }
+
+    /// Key the tenant scalars are derived with, if configured
+    pub fn tenant_key(&self) -> Result<Option<TenantKey>, anyhow::Error> {
+        match self {
+            FingerprintServiceConfig::Cooperative(cooperative) => {
+                load_tenant_key(cooperative.tenant_key.as_deref())
+            }
+            FingerprintServiceConfig::Naive(naive) => load_tenant_key(naive.tenant_key.as_deref()),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TenantAllowlistConfig {
+    pub callers: Vec<TenantCallerConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct TenantCallerConfig {
+    pub name: String,
+    /// Bearer token the caller authenticates with
+    pub token: String,
+    /// Tenants the caller may use, `""` is the default tenant
+    pub tenants: Vec<String>,
+}
+
+// The token is a secret, it is never printed
+impl std::fmt::Debug for TenantCallerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TenantCallerConfig")
+            .field("name", &self.name)
+            .field("tenants", &self.tenants)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TryInto<TenantAllowlist> for TenantAllowlistConfig {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<TenantAllowlist, Self::Error> {
+        self.callers
+            .iter()
+            .try_fold(TenantAllowlist::default(), |allowlist, caller| {
+                let tenants = caller
+                    .tenants
+                    .iter()
+                    .map(|tenant| Tenant::new(tenant))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                allowlist.with_caller(&caller.name, &caller.token, tenants)
+            })
+    }
+}
+
+/// Decode the tenant key if configured, only its public identifier is logged
+pub fn load_tenant_key(encoded: Option<&str>) -> Result<Option<TenantKey>, anyhow::Error> {
+    let Some(encoded) = encoded else {
+        log::info!("== No tenant key configured, only the default tenant is served");
+        return Ok(None);
+    };
+
+    let key = TenantKey::from_base58(encoded)?;
+    log::info!("== Tenant key {}", key.id());
+
+    Ok(Some(key))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FingerprintSchemaConfig {
+    /// Version of the schema new fingerprints are computed with
+    pub current: u32,
+    /// Custom schemas supported in addition to the built-in version 1
+    pub schemas: Vec<SchemaVersionConfig>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SchemaVersionConfig {
+    pub version: u32,
+    /// Components in the order of serialization: `bank_identifier`, `amount`, `currency`, `iban`, `reference`, `direction`, `date_time`
+    pub components: Vec<String>,
+    /// Bucket of the date time: `exact` (default), `minute`, `hour` or `day`
+    pub time_granularity: Option<String>,
+}
+
+impl TryInto<SchemaRegistry> for FingerprintSchemaConfig {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<SchemaRegistry, Self::Error> {
+        let mut registry = SchemaRegistry::default();
+
+        for schema in self.schemas {
+            let time_granularity = schema
+                .time_granularity
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default();
+
+            let schema = schema
+                .components
+                .iter()
+                .try_fold(
+                    FingerprintSchema::builder(schema.version),
+                    |builder, component| {
+                        Ok::<_, anyhow::Error>(builder.component(component.parse()?))
+                    },
+                )?
+                .time_granularity(time_granularity)
+                .build()?;
+
+            registry.register(schema)?;
+        }
+
+        registry.with_current(self.current)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MemoryStoreConfig {
+    /// Days occurrences of fingerprints are remembered
+    pub retention_days: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmbeddedStoreConfig {
+    /// Database file, created when it doesn't exist
+    pub path: String,
+    /// Days occurrences of fingerprints are remembered
+    pub retention_days: u32,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+pub enum FingerprintStoreConfig {
+    Memory(MemoryStoreConfig),
+    Embedded(EmbeddedStoreConfig),
+}
+
+impl TryInto<Arc<dyn FingerprintStore>> for FingerprintStoreConfig {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<Arc<dyn FingerprintStore>, Self::Error> {
+        let store: Arc<dyn FingerprintStore> = match self {
+            FingerprintStoreConfig::Memory(memory) => Arc::new(MemoryStore::new(
+                chrono::Duration::days(i64::from(memory.retention_days)),
+            )),
+            FingerprintStoreConfig::Embedded(embedded) => Arc::new(EmbeddedStore::open(
+                &embedded.path,
+                chrono::Duration::days(i64::from(embedded.retention_days)),
+            )?),
+        };
+
+        Ok(store)
+    }
+}
+
 impl TryInto<Address> for GrpcConfig {
     type Error = anyhow::Error;
 
@@ -49,3 +348,143 @@ impl TryInto<Address> for GrpcConfig {
         Ok(Address::from(addr))
     }
 }
+
+/// Decode the identity key of the agent
+pub fn load_identity<G: FingerprintCurve>(
+    identity_key: &str,
+) -> Result<AgentIdentity<G>, anyhow::Error> {
+    let identity = AgentIdentity::<G>::new(Compact::unwrap(identity_key)?)?;
+    log::info!("== Agent identity: {}", identity.public().compact());
+
+    Ok(identity)
+}
+
+/// Members of a ceremony, all of them must have a configured public identity
+pub fn ceremony_members<G: FingerprintCurve>(
+    members: &[AgentReferenceConfig],
+) -> Result<Vec<CeremonyMember<G>>, anyhow::Error> {
+    members
+        .iter()
+        .map(|member| {
+            let identity = member.identity.as_ref().ok_or(anyhow!(
+                "No identity configured for agent {}, ceremony messages can't be authenticated",
+                member.agent_id
+            ))?;
+
+            Ok(CeremonyMember {
+                agent_id: member.agent_id,
+                address: member.address.to_string(),
+                identity: Compact::unwrap(identity)?,
+            })
+        })
+        .collect()
+}
+
+/// Identity of the agent with the public identities of members by their agent number
+pub type MemberIdentities<G> = (AgentIdentity<G>, Vec<(usize, G)>);
+
+/// Identity of the agent and public identities of the `members` authenticating cooperation requests for tenants,
+/// all members must have one
+pub fn member_identities<'a, G: FingerprintCurve>(
+    identity_key: Option<&str>,
+    members: impl IntoIterator<Item = (usize, Option<&'a String>)>,
+) -> Result<MemberIdentities<G>, anyhow::Error> {
+    let identity_key = identity_key.ok_or(anyhow!(
+        "No identity key configured, tenants are served only to members authenticated with their identities"
+    ))?;
+
+    let members = members
+        .into_iter()
+        .map(|(agent_id, identity)| {
+            let identity = identity.ok_or(anyhow!(
+                "No identity configured for agent {}, its requests for tenants can't be authenticated",
+                agent_id
+            ))?;
+
+            Ok((agent_id, Compact::unwrap(identity)?))
+        })
+        .collect::<Result<_, anyhow::Error>>()?;
+
+    Ok((load_identity(identity_key)?, members))
+}
+
+/// Decode the agent secret shard and check it against published commitments (if configured).
+/// Agent must not start with a shard that is inconsistent with the rest of the network.
+pub fn load_secret_shard<G: FingerprintCurve>(
+    agent_id: usize,
+    secret_shard: &str,
+    commitments: Option<&String>,
+) -> Result<(G::ScalarExt, Option<ShareCommitments<G>>), anyhow::Error> {
+    let secret_shard: G::ScalarExt = Compact::unwrap(secret_shard)?;
+
+    let Some(commitments) = commitments else {
+        log::warn!("== No commitments configured, secret shard is not verified");
+        return Ok((secret_shard, None));
+    };
+
+    let commitments: ShareCommitments<G> = Compact::unwrap(commitments)?;
+
+    if !SecretSharing::verify_share(agent_id, secret_shard, &commitments) {
+        return Err(anyhow!(
+            "Configured secret shard of agent {} does not match published commitments",
+            agent_id
+        ));
+    }
+    log::info!(
+        "== Secret shard of agent {} matches published commitments",
+        agent_id
+    );
+
+    Ok((secret_shard, Some(commitments)))
+}
+
+/// Secret shards of the agent by generation together with published commitments
+pub struct AgentSecretShards<G: FingerprintCurve = G1> {
+    pub secret_shards: HashMap<u64, G::ScalarExt>,
+    pub commitments: HashMap<u64, ShareCommitments<G>>,
+}
+
+/// Load the shard of the current `generation` and all retained generations, each verified against
+/// its published commitments (if configured)
+pub fn load_secret_shards<G: FingerprintCurve>(
+    agent_id: usize,
+    generation: u64,
+    secret_shard: &str,
+    commitments: Option<&String>,
+    retained_generations: &[SecretShardConfig],
+) -> Result<AgentSecretShards<G>, anyhow::Error> {
+    let mut shards = AgentSecretShards {
+        secret_shards: HashMap::new(),
+        commitments: HashMap::new(),
+    };
+
+    let current = std::iter::once((generation, secret_shard, commitments));
+    let retained = retained_generations.iter().map(|retained| {
+        (
+            retained.generation,
+            retained.secret_shard.as_str(),
+            retained.commitments.as_ref(),
+        )
+    });
+
+    for (generation, secret_shard, commitments) in current.chain(retained) {
+        log::info!("== Loading secret shard of generation {}", generation);
+        let (secret_shard, commitments) = load_secret_shard(agent_id, secret_shard, commitments)?;
+
+        if shards
+            .secret_shards
+            .insert(generation, secret_shard)
+            .is_some()
+        {
+            return Err(anyhow!(
+                "Secret shard of generation {} is configured twice",
+                generation
+            ));
+        }
+        if let Some(commitments) = commitments {
+            shards.commitments.insert(generation, commitments);
+        }
+    }
+
+    Ok(shards)
+}
@@ -1,16 +1,51 @@
 use anyhow::Result;
-use clap::Parser;
-use fingerprinting_core::secret_sharing::SecretSharing;
+use clap::{Parser, Subcommand};
+use fingerprinting_cli::config::{
+    ceremony_members, load_identity, load_secret_shard, GrpcConfig, KeyGenerationConfig,
+    ResharingConfig,
+};
+use fingerprinting_core::curves::{Curve, FingerprintCurve};
+use fingerprinting_core::dkg::DkgParticipant;
+use fingerprinting_core::identity::AgentIdentity;
+use fingerprinting_core::resharing::{ResharingDealer, ResharingReceiver};
+use fingerprinting_core::secret_sharing::{SecretSharing, ShareCommitments};
+use fingerprinting_core::tenant::TenantKey;
+use fingerprinting_core::vectors::TestVectorFile;
 use fingerprinting_core::Compact;
+use fingerprinting_grpc_agent::{net as fp_agent, KeyGenerationCeremony, ResharingCeremony};
 use halo2_axiom::arithmetic::Field;
-use halo2_axiom::halo2curves::bn256::Fr;
+use halo2_axiom::halo2curves::{bls12_381, bn256};
+use hocon::HoconLoader;
 use rand_core::OsRng;
+use serde_derive::Deserialize;
+use std::time::Duration;
+use volo::net::Address;
+use volo_grpc::server::{Server, ServiceBuilder};
 
 /// Generate a transaction fingerprint
 #[derive(Parser, Debug)]
 #[command(name = "fingerprinting-cli")]
 #[command(about = "Fingerprint CLI utility", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Threshold for cooperative computation, without a command the secret is generated as with `keygen`
+    #[arg(long, requires = "agents")]
+    threshold: Option<usize>,
+
+    /// Total number of cooperative agents network size
+    #[arg(long, requires = "threshold")]
+    agents: Option<usize>,
+
+    /// Curve of the secret, `bn256` or `bls12-381`
+    #[arg(long, default_value = "bn256")]
+    curve: Curve,
+}
+
+#[derive(clap::Args, Debug)]
+struct KeygenArgs {
     /// Threshold for cooperative computation
     #[arg(long)]
     threshold: usize,
@@ -18,27 +53,531 @@ struct Args {
     /// Total number of cooperative agents network size
     #[arg(long)]
     agents: usize,
+
+    /// Curve of the secret, `bn256` or `bls12-381`
+    #[arg(long, default_value = "bn256")]
+    curve: Curve,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate random secret on this machine and split it into shares (trusted dealer)
+    Keygen(KeygenArgs),
+    /// Generate random tenant key, shared by all agents of the network to derive the secrets of tenants
+    TenantKeygen,
+    /// Generate random identity key of an agent authenticating its messages to other agents
+    IdentityKeygen {
+        /// Curve of the network, `bn256` or `bls12-381`
+        #[arg(long, default_value = "bn256")]
+        curve: Curve,
+    },
+    /// Jointly generate secret shards with other agents, nobody learns the master secret
+    Dkg {
+        /// Config file location
+        #[arg(long)]
+        config: String,
+
+        /// File to write the generated agent keys to
+        #[arg(long)]
+        output: String,
+    },
+    /// Jointly refresh secret shards with other agents, the master secret stays the same
+    Refresh {
+        /// Config file location
+        #[arg(long)]
+        config: String,
+
+        /// File to write the refreshed agent keys to
+        #[arg(long)]
+        output: String,
+    },
+    /// Redistribute the secret to a new committee with different threshold and number of agents
+    Reshare {
+        /// Config file location
+        #[arg(long)]
+        config: String,
+
+        /// File to write the agent keys of the new committee to
+        #[arg(long)]
+        output: String,
+    },
+    /// Check the serialization and fingerprints of a known-answer test vector file
+    VerifyVectors {
+        /// Test vector file location
+        #[arg(long)]
+        file: String,
+    },
+    /// Write the known-answer test vectors of the built-in cases
+    GenerateVectors {
+        /// File to write the test vectors to
+        #[arg(long)]
+        output: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct DkgCommandConfig {
+    grpc: GrpcConfig,
+    dkg: KeyGenerationConfig,
 }
 
-fn main() -> Result<()> {
+#[derive(Deserialize)]
+struct ReshareCommandConfig {
+    grpc: GrpcConfig,
+    reshare: ResharingConfig,
+}
+
+#[volo::main]
+async fn main() -> Result<()> {
     env_logger::Builder::from_default_env()
         .filter_level(log::LevelFilter::Info)
         .init();
 
     let args = Args::parse();
+
+    // `fingerprinting-cli --threshold 3 --agents 5` is the original invocation of `keygen`
+    let command = match (args.command, args.threshold, args.agents) {
+        (Some(command), _, _) => command,
+        (None, Some(threshold), Some(agents)) => Command::Keygen(KeygenArgs {
+            threshold,
+            agents,
+            curve: args.curve,
+        }),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Either a command or --threshold and --agents are required, see --help"
+            ))
+        }
+    };
+
+    match command {
+        Command::Keygen(KeygenArgs {
+            threshold,
+            agents,
+            curve,
+        }) => match curve {
+            Curve::Bn256 => keygen::<bn256::G1>(threshold, agents),
+            Curve::Bls12_381 => keygen::<bls12_381::G1>(threshold, agents),
+        },
+        Command::TenantKeygen => tenant_keygen(),
+        Command::IdentityKeygen { curve } => match curve {
+            Curve::Bn256 => identity_keygen::<bn256::G1>(),
+            Curve::Bls12_381 => identity_keygen::<bls12_381::G1>(),
+        },
+        Command::Dkg { config, output } => {
+            let conf = load_ceremony_config(&config)?;
+            match conf.dkg.curve.parse()? {
+                Curve::Bn256 => dkg::<bn256::G1>(conf, &output).await,
+                Curve::Bls12_381 => dkg::<bls12_381::G1>(conf, &output).await,
+            }
+        }
+        Command::Refresh { config, output } => {
+            let conf = load_ceremony_config(&config)?;
+            match conf.dkg.curve.parse()? {
+                Curve::Bn256 => refresh::<bn256::G1>(conf, &output).await,
+                Curve::Bls12_381 => refresh::<bls12_381::G1>(conf, &output).await,
+            }
+        }
+        Command::Reshare { config, output } => {
+            let conf = load_reshare_config(&config)?;
+            match conf.reshare.curve.parse()? {
+                Curve::Bn256 => reshare::<bn256::G1>(conf, &output).await,
+                Curve::Bls12_381 => reshare::<bls12_381::G1>(conf, &output).await,
+            }
+        }
+        Command::VerifyVectors { file } => verify_vectors(&file).await,
+        Command::GenerateVectors { output } => generate_vectors(&output).await,
+    }
+}
+
+fn keygen<G: FingerprintCurve>(threshold: usize, agents: usize) -> Result<()> {
     let mut rng = OsRng;
 
-    let random_secret = Fr::random(&mut rng);
+    let random_secret = G::ScalarExt::random(&mut rng);
 
-    let secret_sharing = SecretSharing::generate(random_secret, args.threshold, args.agents);
+    let secret_sharing = SecretSharing::generate(random_secret, threshold, agents);
 
     let shares_set = secret_sharing.get_shares();
 
+    log::info!("Curve: {}", G::CURVE.name());
     log::info!("Random secret: {}", random_secret.compact());
     log::info!("Shares:");
     for (agent, secret) in shares_set.iter() {
         log::info!("== share {}: {}", agent, secret.compact());
     }
+    log::info!(
+        "Commitments: {}",
+        secret_sharing.commitments::<G>().compact()
+    );
+    log::info!("Public shares:");
+    for (agent, secret) in shares_set.iter() {
+        log::info!(
+            "== public share {}: {}",
+            agent,
+            (G::generator() * secret).compact()
+        );
+    }
+
+    Ok(())
+}
+
+fn tenant_keygen() -> Result<()> {
+    let key = TenantKey::generate();
+
+    log::info!("Tenant key: {}", key.to_base58());
+    log::info!("Tenant key id: {}", key.id());
+
+    Ok(())
+}
+
+fn identity_keygen<G: FingerprintCurve>() -> Result<()> {
+    let identity = AgentIdentity::<G>::generate();
+
+    log::info!("Curve: {}", G::CURVE.name());
+    log::info!("Identity key: {}", identity.secret().compact());
+    log::info!("Public identity: {}", identity.public().compact());
+
+    Ok(())
+}
+
+fn load_ceremony_config(config: &str) -> Result<DkgCommandConfig> {
+    let reference_config = include_str!("../config/dkg-reference.conf");
+    log::info!("== loading configuration from {}", config);
+
+    let conf = HoconLoader::new()
+        .load_str(reference_config)?
+        .load_file(config)?
+        .resolve()?;
+
+    Ok(conf)
+}
+
+fn load_reshare_config(config: &str) -> Result<ReshareCommandConfig> {
+    let reference_config = include_str!("../config/reshare-reference.conf");
+    log::info!("== loading configuration from {}", config);
+
+    let conf = HoconLoader::new()
+        .load_str(reference_config)?
+        .load_file(config)?
+        .resolve()?;
+
+    Ok(conf)
+}
+
+async fn dkg<G: FingerprintCurve>(conf: DkgCommandConfig, output: &str) -> Result<()> {
+    log::info!(
+        "== Starting key generation ceremony {} as agent {} with {} agents and {} threshold",
+        conf.dkg.ceremony_id,
+        conf.dkg.agent_id,
+        conf.dkg.agents,
+        conf.dkg.threshold
+    );
+
+    if conf.dkg.generation != 0 {
+        return Err(anyhow::anyhow!(
+            "Key generation produces generation 0, use refresh for generation {}",
+            conf.dkg.generation
+        ));
+    }
+
+    let participant = DkgParticipant::<G::ScalarExt, G>::new(
+        conf.dkg.agent_id,
+        conf.dkg.threshold,
+        conf.dkg.agents,
+    )?;
+
+    run_ceremony(conf, participant, output).await
+}
+
+async fn refresh<G: FingerprintCurve>(conf: DkgCommandConfig, output: &str) -> Result<()> {
+    log::info!(
+        "== Starting refresh ceremony {} of generation {} as agent {} with {} agents and {} threshold",
+        conf.dkg.ceremony_id,
+        conf.dkg.generation,
+        conf.dkg.agent_id,
+        conf.dkg.agents,
+        conf.dkg.threshold
+    );
+
+    let secret_shard = conf.dkg.secret_shard.as_ref().ok_or(anyhow::anyhow!(
+        "Secret shard of the previous generation is required for refresh"
+    ))?;
+    let (secret_shard, commitments) = load_secret_shard::<G>(
+        conf.dkg.agent_id,
+        secret_shard,
+        conf.dkg.commitments.as_ref(),
+    )?;
+    let commitments = commitments.ok_or(anyhow::anyhow!(
+        "Commitments of the previous generation are required for refresh"
+    ))?;
+
+    if commitments.threshold() != conf.dkg.threshold {
+        return Err(anyhow::anyhow!(
+            "Published commitments are for threshold {}, but configured threshold is {}",
+            commitments.threshold(),
+            conf.dkg.threshold
+        ));
+    }
+
+    let participant = DkgParticipant::refresh(
+        conf.dkg.agent_id,
+        conf.dkg.agents,
+        secret_shard,
+        commitments,
+    )?;
+
+    run_ceremony(conf, participant, output).await
+}
+
+async fn run_ceremony<G: FingerprintCurve>(
+    conf: DkgCommandConfig,
+    participant: DkgParticipant<G::ScalarExt, G>,
+    output: &str,
+) -> Result<()> {
+    let dkg = conf.dkg;
+
+    let ceremony = KeyGenerationCeremony::new(
+        dkg.ceremony_id,
+        dkg.generation,
+        participant,
+        &load_identity(&dkg.identity_key)?,
+        ceremony_members(&dkg.members)?,
+    )?;
+
+    let grpc_address: Address = conf.grpc.try_into()?;
+    log::info!("== starting key generation GRPC server on {}", grpc_address);
+
+    let server = Server::new()
+        .add_service(
+            ServiceBuilder::new(
+                fp_agent::pso::transaction_fingerprinting::fingerprint::agent::v1::KeyGenerationServiceServer::new(
+                    ceremony.service(),
+                ),
+            )
+            .build(),
+        )
+        .run(grpc_address);
+
+    let (secret_shard, commitments) = tokio::select! {
+        result = ceremony.run(Duration::from_millis(dkg.retry_interval_ms)) => result?,
+        result = server => {
+            result.map_err(|e| anyhow::anyhow!(e))?;
+            return Err(anyhow::anyhow!("Key generation server stopped before ceremony completed"));
+        }
+    };
+
+    log::info!("== Ceremony completed, generation {}", dkg.generation);
+    log::info!("Commitments: {}", commitments.compact());
+    log::info!(
+        "Public share: {}",
+        commitments.public_share(dkg.agent_id).compact()
+    );
+
+    write_agent_keys(
+        output,
+        dkg.agent_id,
+        dkg.generation,
+        secret_shard,
+        &commitments,
+    )
+}
+
+async fn reshare<G: FingerprintCurve>(conf: ReshareCommandConfig, output: &str) -> Result<()> {
+    let reshare = conf.reshare;
+
+    log::info!(
+        "== Starting resharing ceremony {} of generation {} from dealers {:?} to {} agents with {} threshold",
+        reshare.ceremony_id,
+        reshare.generation,
+        reshare.dealers,
+        reshare.agents,
+        reshare.threshold
+    );
+
+    let old_commitments: ShareCommitments<G> = Compact::unwrap(&reshare.commitments)?;
+
+    let dealer = match (reshare.old_agent_id, &reshare.secret_shard) {
+        (Some(old_agent_id), Some(secret_shard)) => {
+            if !reshare.dealers.contains(&old_agent_id) {
+                return Err(anyhow::anyhow!(
+                    "Agent {} of the old committee is not among the dealers {:?}",
+                    old_agent_id,
+                    reshare.dealers
+                ));
+            }
+
+            let (secret_shard, _) =
+                load_secret_shard::<G>(old_agent_id, secret_shard, Some(&reshare.commitments))?;
+            log::info!("== Dealing secret shard of old agent {}", old_agent_id);
+
+            Some(ResharingDealer::new(
+                old_agent_id,
+                secret_shard,
+                &old_commitments,
+                reshare.threshold,
+                reshare.agents,
+            )?)
+        }
+        (None, None) => None,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Both old_agent_id and secret_shard are required for the dealer"
+            ))
+        }
+    };
+
+    let receiver = reshare
+        .agent_id
+        .map(|agent_id| {
+            log::info!("== Receiving secret shard as new agent {}", agent_id);
+
+            ResharingReceiver::new(
+                agent_id,
+                reshare.threshold,
+                reshare.agents,
+                old_commitments.clone(),
+                &reshare.dealers,
+            )
+        })
+        .transpose()?;
+
+    let dealer_identities = reshare
+        .dealer_identities
+        .iter()
+        .map(|dealer| Ok((dealer.agent_id, Compact::unwrap(&dealer.identity)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let ceremony = ResharingCeremony::new(
+        reshare.ceremony_id,
+        reshare.generation,
+        dealer,
+        receiver,
+        &load_identity(&reshare.identity_key)?,
+        dealer_identities,
+        ceremony_members(&reshare.members)?,
+    )?;
+
+    let grpc_address: Address = conf.grpc.try_into()?;
+    log::info!("== starting resharing GRPC server on {}", grpc_address);
+
+    let server = Server::new()
+        .add_service(
+            ServiceBuilder::new(
+                fp_agent::pso::transaction_fingerprinting::fingerprint::agent::v1::ResharingServiceServer::new(
+                    ceremony.service(),
+                ),
+            )
+            .build(),
+        )
+        .run(grpc_address);
+
+    let result = tokio::select! {
+        result = ceremony.run(Duration::from_millis(reshare.retry_interval_ms)) => result?,
+        result = server => {
+            result.map_err(|e| anyhow::anyhow!(e))?;
+            return Err(anyhow::anyhow!("Resharing server stopped before ceremony completed"));
+        }
+    };
+
+    let (Some(agent_id), Some((secret_shard, commitments))) = (reshare.agent_id, result) else {
+        log::info!("== Resharing completed, agent is not a member of the new committee");
+        return Ok(());
+    };
+
+    log::info!("== Resharing completed, generation {}", reshare.generation);
+    log::info!("Commitments: {}", commitments.compact());
+    log::info!(
+        "Public share: {}",
+        commitments.public_share(agent_id).compact()
+    );
+
+    write_agent_keys(
+        output,
+        agent_id,
+        reshare.generation,
+        secret_shard,
+        &commitments,
+    )
+}
+
+async fn verify_vectors(file: &str) -> Result<()> {
+    log::info!("== loading test vectors from {}", file);
+    let vectors = TestVectorFile::from_json(&std::fs::read_to_string(file)?)?;
+
+    let results = vectors.check().await?;
+    let mut failed = 0;
+    for (vector, result) in vectors.vectors.iter().zip(results) {
+        match result {
+            Ok(()) => log::info!("== vector {}: ok", vector.name),
+            Err(e) => {
+                failed += 1;
+                log::error!("== vector {}: {}", vector.name, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow::anyhow!(
+            "{} of {} test vectors failed",
+            failed,
+            vectors.vectors.len()
+        ));
+    }
+
+    log::info!("== All {} test vectors passed", vectors.vectors.len());
+
+    Ok(())
+}
+
+async fn generate_vectors(output: &str) -> Result<()> {
+    let vectors = TestVectorFile::generate().await?;
+    std::fs::write(output, format!("{}\n", vectors.to_json()?))?;
+
+    log::info!(
+        "== {} test vectors written to {}",
+        vectors.vectors.len(),
+        output
+    );
+
+    Ok(())
+}
+
+fn write_agent_keys<G: FingerprintCurve>(
+    output: &str,
+    agent_id: usize,
+    generation: u64,
+    secret_shard: G::ScalarExt,
+    commitments: &ShareCommitments<G>,
+) -> Result<()> {
+    let keys = format!(
+        "{{\n  agent_id: {}\n  generation: {}\n  secret_shard: \"{}\"\n  commitments: \"{}\"\n  curve: \"{}\"\n}}\n",
+        agent_id,
+        generation,
+        secret_shard.compact(),
+        commitments.compact(),
+        G::CURVE.name()
+    );
+    write_secret_file(output, &keys)?;
+
+    log::info!("== Agent keys written to {}", output);
+
+    Ok(())
+}
+
+fn write_secret_file(path: &str, content: &str) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(content.as_bytes())?;
 
     Ok(())
 }
@@ -0,0 +1,613 @@
+use anyhow::{anyhow, Error};
+use fingerprinting_core::curves::{decode_point, FingerprintCurve};
+use fingerprinting_core::schema::FingerprintSchema;
+use fingerprinting_core::tenant::Tenant;
+use fingerprinting_core::verification::{decode_g2, verify_neighbour_fingerprint};
+use fingerprinting_core::{
+    BlindedEvaluation, BlindedProtocol, Fingerprint, TransactionFingerprintData,
+};
+use fingerprinting_grpc::net::pso::transaction_fingerprinting::fingerprint::v1::{
+    CheckAndRecordFingerprintRequest, EvaluateBlindedBatchRequest, EvaluateBlindedRequest,
+    FingerprintServiceClient, FingerprintServiceClientBuilder, GetGroupKeyRequest,
+};
+use fingerprinting_grpc::MAX_BLINDED_BATCH_SIZE;
+use fingerprinting_types::RawTransaction;
+use halo2_axiom::halo2curves::bn256::{Fr, G1};
+use pilota::Bytes;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use volo::net::Address;
+use volo_grpc::metadata::{Ascii, MetadataValue};
+use volo_grpc::Request;
+
+///
+/// Client of the Fingerprint Service computing fingerprints without disclosing transaction data.
+///
+/// Transaction data is canonicalized and squeezed locally, the service evaluates only the blinded point
+/// and the result is unblinded locally, so fingerprints are the same as computed by the service itself.
+/// Every unblinded point is verified against the group key of the service before the fingerprint is returned.
+pub struct FingerprintClient<G: FingerprintCurve = G1> {
+    protocol: BlindedProtocol<RemoteEvaluation, G>,
+    schema: FingerprintSchema,
+    // fetched from the service on first use unless pinned with `with_group_key`
+    group_key: OnceLock<G::G2>,
+}
+
+impl FingerprintClient {
+    pub fn new(address: SocketAddr) -> Self {
+        let client =
+            FingerprintServiceClientBuilder::new(format!("fingerprinting-client-{}", address))
+                .address(Address::from(address))
+                .build();
+
+        Self::with_client(client)
+    }
+
+    pub fn with_client(client: FingerprintServiceClient) -> Self {
+        Self {
+            protocol: BlindedProtocol::new(RemoteEvaluation {
+                client,
+                tenant: Tenant::default(),
+                authorization: None,
+            }),
+            schema: FingerprintSchema::default(),
+            group_key: OnceLock::new(),
+        }
+    }
+}
+
+impl<G: FingerprintCurve> FingerprintClient<G> {
+    /// Compute fingerprints on the curve `C` of the service instead of BN256
+    pub fn with_curve<C: FingerprintCurve>(self) -> FingerprintClient<C> {
+        let evaluator = self.protocol.evaluator().clone();
+
+        self.with_evaluator(evaluator)
+    }
+
+    /// Compute fingerprints of the `tenant` instead of the default one, the caller must be allowed to use it
+    pub fn with_tenant(self, tenant: Tenant) -> Self {
+        let evaluator = RemoteEvaluation {
+            tenant,
+            ..self.protocol.evaluator().clone()
+        };
+
+        self.with_evaluator(evaluator)
+    }
+
+    /// Authenticate with the bearer `token` of the caller, required by services with a tenant allowlist
+    pub fn with_token(self, token: &str) -> Result<Self, Error> {
+        let authorization = format!("Bearer {}", token)
+            .parse()
+            .map_err(|_| anyhow!("Token must consist of printable ASCII characters"))?;
+        let evaluator = RemoteEvaluation {
+            authorization: Some(authorization),
+            ..self.protocol.evaluator().clone()
+        };
+
+        Ok(self.with_evaluator(evaluator))
+    }
+
+    fn with_evaluator<C: FingerprintCurve>(
+        self,
+        evaluator: RemoteEvaluation,
+    ) -> FingerprintClient<C> {
+        FingerprintClient {
+            protocol: BlindedProtocol::new(evaluator).with_dst(self.schema.dst().clone()),
+            schema: self.schema,
+            group_key: OnceLock::new(),
+        }
+    }
+
+    /// Verify fingerprints against the trusted `group_key` (e.g. derived from the published share commitments)
+    /// instead of the one reported by the service
+    pub fn with_group_key(self, group_key: G::G2) -> Self {
+        Self {
+            group_key: OnceLock::from(group_key),
+            ..self
+        }
+    }
+
+    /// Compute fingerprints with the `schema` instead of the default one, hashing to curve with its domain separation tag
+    pub fn with_schema(mut self, schema: FingerprintSchema) -> Self {
+        self.protocol = self.protocol.with_dst(schema.dst().clone());
+        self.schema = schema;
+        self
+    }
+
+    fn prepare(&self, tx: &RawTransaction) -> Result<TransactionFingerprintData<Fr>, Error> {
+        let tx_data: TransactionFingerprintData<Fr> = tx.try_into()?;
+
+        Ok(tx_data.with_schema(self.schema.clone()))
+    }
+
+    /// Compute the fingerprint of the transaction, verified against the group key
+    pub async fn fingerprint(&self, tx: &RawTransaction) -> Result<Fr, Error> {
+        self.verifiable_fingerprint(tx)
+            .await
+            .map(|(fingerprint, _)| fingerprint)
+    }
+
+    /// Compute the fingerprint of the transaction with the point `[k] H(d)` it is squeezed from,
+    /// the point is verified against the group key, see `fingerprinting_core::verification::verify_fingerprint`
+    pub async fn verifiable_fingerprint(&self, tx: &RawTransaction) -> Result<(Fr, G), Error> {
+        let tx_data = self.prepare(tx)?;
+        let group_key = self.trusted_group_key().await?;
+
+        let (fingerprint, point) = tx_data
+            .complete_verifiable_fingerprint(&self.protocol)
+            .await?;
+        verify(&tx_data, &fingerprint, &point, &group_key)?;
+
+        Ok((fingerprint, point))
+    }
+
+    /// Compute the fingerprints of the date time bucket of the transaction and `distance` buckets
+    /// around it with one request, results are `(offset, fingerprint)` ordered by the offset.
+    /// Every fingerprint is verified against the group key.
+    pub async fn neighbour_fingerprints(
+        &self,
+        tx: &RawTransaction,
+        distance: u32,
+    ) -> Result<Vec<(i64, Fr)>, Error> {
+        let tx_data = self.prepare(tx)?;
+        let group_key = self.trusted_group_key().await?;
+
+        tx_data
+            .complete_verifiable_neighbour_fingerprints(&self.protocol, distance)
+            .await?
+            .into_iter()
+            .map(|(offset, (fingerprint, point))| {
+                verify_neighbour(&tx_data, offset, &fingerprint, &point, &group_key)?;
+
+                Ok((offset, fingerprint))
+            })
+            .collect()
+    }
+
+    ///
+    /// Record the fingerprint of the transaction for the `item_id` in the store of the service and return
+    /// ids of other items recorded with the same fingerprint within the retention window, oldest first.
+    /// The service records only fingerprints it computes itself, so the transaction data is sent.
+    pub async fn check_and_record(
+        &self,
+        item_id: &str,
+        tx: &RawTransaction,
+    ) -> Result<Vec<String>, Error> {
+        let evaluator = self.protocol.evaluator();
+
+        let response = evaluator
+            .client
+            .check_and_record_fingerprint(evaluator.request(CheckAndRecordFingerprintRequest {
+                item_id: item_id.to_string().into(),
+                tenant: evaluator.tenant.as_str().to_string().into(),
+                transaction_data: Some(tx.try_into()?),
+                schema_version: self.schema.version(),
+                _unknown_fields: Default::default(),
+            }))
+            .await?
+            .into_inner();
+
+        Ok(response
+            .earlier_occurrences
+            .into_iter()
+            .map(|occurrence| occurrence.item_id.to_string())
+            .collect())
+    }
+
+    /// Group public key `[k] G2` reported by the service (`[k * t] G2` of the tenant) verifying fingerprint points
+    pub async fn group_key(&self) -> Result<G::G2, Error> {
+        let evaluator = self.protocol.evaluator();
+
+        let response = evaluator
+            .client
+            .get_group_key(evaluator.request(GetGroupKeyRequest {
+                tenant: evaluator.tenant.as_str().to_string().into(),
+                _unknown_fields: Default::default(),
+            }))
+            .await?
+            .into_inner();
+
+        if response.curve != G::CURVE.name() {
+            return Err(anyhow!(
+                "Service computes on curve {}, client on {}",
+                response.curve,
+                G::CURVE.name()
+            ));
+        }
+
+        decode_g2::<G>(&response.group_key_g2)
+    }
+
+    /// Compute fingerprints of all transactions with one request per `MAX_BLINDED_BATCH_SIZE` of them,
+    /// results are in the order of `batch`
+    pub async fn fingerprints(&self, batch: &[RawTransaction]) -> Vec<Result<Fr, Error>> {
+        let mut transactions = Vec::with_capacity(batch.len());
+        let mut prepared = Vec::with_capacity(batch.len());
+
+        for tx in batch {
+            match self.prepare(tx) {
+                Ok(tx_data) => {
+                    transactions.push(tx_data);
+                    prepared.push(Ok(()));
+                }
+                Err(e) => prepared.push(Err(e)),
+            }
+        }
+
+        let group_key = match self.trusted_group_key().await {
+            Ok(group_key) => group_key,
+            Err(e) => {
+                return batch
+                    .iter()
+                    .map(|_| Err(anyhow!("Group key is not available: {}", e)))
+                    .collect()
+            }
+        };
+
+        let mut fingerprints = TransactionFingerprintData::complete_verifiable_fingerprints(
+            &transactions,
+            &self.protocol,
+        )
+        .await
+        .into_iter()
+        .zip(&transactions)
+        .map(|(result, tx_data)| {
+            let (fingerprint, point) = result?;
+            verify(tx_data, &fingerprint, &point, &group_key)?;
+
+            Ok(fingerprint)
+        });
+
+        prepared
+            .into_iter()
+            .map(|item| {
+                item?;
+                fingerprints.next().ok_or(anyhow!("Missing fingerprint"))?
+            })
+            .collect()
+    }
+
+    /// Group key pinned with `with_group_key`, otherwise fetched from the service once
+    async fn trusted_group_key(&self) -> Result<G::G2, Error> {
+        if let Some(group_key) = self.group_key.get() {
+            return Ok(*group_key);
+        }
+
+        let group_key = self.group_key().await?;
+
+        Ok(*self.group_key.get_or_init(|| group_key))
+    }
+}
+
+///
+/// Evaluation of blinded points by the remote Fingerprint Service
+#[derive(Clone)]
+pub struct RemoteEvaluation {
+    client: FingerprintServiceClient,
+    tenant: Tenant,
+    authorization: Option<MetadataValue<Ascii>>,
+}
+
+impl<G: FingerprintCurve> BlindedEvaluation<G> for RemoteEvaluation {
+    async fn evaluate(&self, blinded: G) -> Result<G, Error> {
+        let response = self
+            .client
+            .evaluate_blinded(self.request(EvaluateBlindedRequest {
+                tenant: self.tenant.as_str().to_string().into(),
+                blinded_element: Bytes::copy_from_slice(blinded.to_bytes().as_ref()),
+                _unknown_fields: Default::default(),
+            }))
+            .await?
+            .into_inner();
+
+        decode_evaluated(&response.evaluated_element)
+    }
+
+    async fn evaluate_batch(&self, blinded: Vec<G>) -> Vec<Result<G, Error>> {
+        let mut results = Vec::with_capacity(blinded.len());
+
+        for chunk in blinded.chunks(MAX_BLINDED_BATCH_SIZE) {
+            match self.evaluate_chunk(chunk).await {
+                Ok(evaluated) => {
+                    results.extend(evaluated.iter().map(|point| decode_evaluated(point)))
+                }
+                Err(e) => results.extend(
+                    chunk
+                        .iter()
+                        .map(|_| Err(anyhow!("Batch evaluation failed: {}", e))),
+                ),
+            }
+        }
+
+        results
+    }
+}
+
+impl RemoteEvaluation {
+    /// Request of the `message` with the authorization metadata of the caller
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert("authorization", authorization.clone());
+        }
+
+        request
+    }
+
+    async fn evaluate_chunk<G: FingerprintCurve>(&self, chunk: &[G]) -> Result<Vec<Bytes>, Error> {
+        let response = self
+            .client
+            .evaluate_blinded_batch(
+                self.request(EvaluateBlindedBatchRequest {
+                    tenant: self.tenant.as_str().to_string().into(),
+                    blinded_elements: chunk
+                        .iter()
+                        .map(|point| Bytes::copy_from_slice(point.to_bytes().as_ref()))
+                        .collect(),
+                    _unknown_fields: Default::default(),
+                }),
+            )
+            .await?
+            .into_inner();
+
+        if response.evaluated_elements.len() != chunk.len() {
+            return Err(anyhow!(
+                "Service returned {} evaluated elements for batch of {}",
+                response.evaluated_elements.len(),
+                chunk.len()
+            ));
+        }
+
+        Ok(response.evaluated_elements)
+    }
+}
+
+/// Check the point unblinded from the evaluation of the service against the group key
+fn verify<G: FingerprintCurve>(
+    tx_data: &TransactionFingerprintData<Fr>,
+    fingerprint: &Fr,
+    point: &G,
+    group_key: &G::G2,
+) -> Result<(), Error> {
+    verify_neighbour(tx_data, 0, fingerprint, point, group_key)
+}
+
+fn verify_neighbour<G: FingerprintCurve>(
+    tx_data: &TransactionFingerprintData<Fr>,
+    offset: i64,
+    fingerprint: &Fr,
+    point: &G,
+    group_key: &G::G2,
+) -> Result<(), Error> {
+    verify_neighbour_fingerprint(tx_data, offset, fingerprint, point, group_key)
+        .map_err(|e| anyhow!("Evaluation of the service is not verifiable, {}", e))
+}
+
+fn decode_evaluated<G: FingerprintCurve>(bytes: &[u8]) -> Result<G, Error> {
+    decode_point::<G>(bytes).map_err(|e| anyhow!("Invalid evaluated element, {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use fingerprinting_core::tenant::TenantKey;
+    use fingerprinting_core::verification::verify_fingerprint;
+    use fingerprinting_core::{NaiveProtocol, TenantEvaluation, TenantEvaluator};
+    use fingerprinting_grpc::net::pso::transaction_fingerprinting::fingerprint::v1::FingerprintServiceServer;
+    use fingerprinting_grpc::{FingerprintService, GroupKey, TenantAllowlist};
+    use fingerprinting_types::test_utils::{euros, sample_transaction};
+    use halo2_axiom::halo2curves::bls12_381;
+    use halo2_axiom::halo2curves::bn256::G2;
+    use std::net::TcpListener;
+    use std::time::Duration;
+    use volo_grpc::server::{Server, ServiceBuilder};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_blinded_fingerprint_computation() -> Result<(), Error> {
+        let secret = Fr::from(42u64);
+        let group_key = G2::generator() * secret;
+
+        // Naive service on a free local port
+        let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        tokio::spawn(
+            Server::new()
+                .add_service(
+                    ServiceBuilder::new(FingerprintServiceServer::new(
+                        FingerprintService::new(NaiveProtocol::<G1>::new(secret)).with_group_key(
+                            GroupKey::new((G1::generator() * secret, group_key).into()),
+                        ),
+                    ))
+                    .build(),
+                )
+                .run(Address::from(addr)),
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let client = FingerprintClient::new(addr);
+
+        let transactions = (1..=5u64)
+            .map(|amount| {
+                sample_transaction()
+                    .amount(euros(amount))
+                    .date_time(Utc.with_ymd_and_hms(2025, 9, 16, 10, 0, 0).unwrap())
+                    .build()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let protocol = NaiveProtocol::<G1>::new(secret);
+        let batch_fingerprints = client.fingerprints(&transactions).await;
+
+        for (tx, batch_fingerprint) in transactions.iter().zip(batch_fingerprints) {
+            let tx_data: TransactionFingerprintData<Fr> = tx.try_into()?;
+            let expected = tx_data.complete_fingerprint(&protocol).await?;
+
+            assert_eq!(client.fingerprint(tx).await?, expected);
+            assert_eq!(batch_fingerprint?, expected);
+
+            // Fingerprint is verifiable with the published group key
+            let (fingerprint, point) = client.verifiable_fingerprint(tx).await?;
+            assert_eq!(fingerprint, expected);
+            verify_fingerprint(&tx_data, &fingerprint, &point, &client.group_key().await?)?;
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unverifiable_evaluation_rejected() -> Result<(), Error> {
+        let secret = Fr::from(42u64);
+        let other_key = G2::generator() * Fr::from(43u64);
+
+        // Service evaluating with another secret than the one of its group key
+        let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        tokio::spawn(
+            Server::new()
+                .add_service(
+                    ServiceBuilder::new(FingerprintServiceServer::new(
+                        FingerprintService::new(NaiveProtocol::<G1>::new(secret)).with_group_key(
+                            GroupKey::new((G1::generator() * Fr::from(43u64), other_key).into()),
+                        ),
+                    ))
+                    .build(),
+                )
+                .run(Address::from(addr)),
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let tx = sample_transaction().build()?;
+
+        let client = FingerprintClient::new(addr);
+        assert!(client.fingerprint(&tx).await.is_err());
+        assert!(client.verifiable_fingerprint(&tx).await.is_err());
+        assert!(client
+            .fingerprints(&[tx.clone(), tx.clone()])
+            .await
+            .iter()
+            .all(Result::is_err));
+
+        // Evaluations verify against the pinned group key of the secret
+        let pinned = FingerprintClient::new(addr).with_group_key(G2::generator() * secret);
+        let expected = TransactionFingerprintData::<Fr>::try_from(&tx)?
+            .complete_fingerprint(&NaiveProtocol::<G1>::new(secret))
+            .await?;
+        assert_eq!(pinned.fingerprint(&tx).await?, expected);
+        assert_eq!(pinned.fingerprints(&[tx]).await.remove(0)?, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bls12_381_fingerprint_computation() -> Result<(), Error> {
+        let secret = bls12_381::Fr::from(42u64);
+        let group_key = bls12_381::G2::generator() * secret;
+
+        let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        tokio::spawn(
+            Server::new()
+                .add_service(
+                    ServiceBuilder::new(FingerprintServiceServer::new(
+                        FingerprintService::new(NaiveProtocol::<bls12_381::G1>::new(secret))
+                            .with_group_key(GroupKey::new(
+                                (bls12_381::G1::generator() * secret, group_key).into(),
+                            )),
+                    ))
+                    .build(),
+                )
+                .run(Address::from(addr)),
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let tx = sample_transaction().build()?;
+        let tx_data: TransactionFingerprintData<Fr> = (&tx).try_into()?;
+        let expected = tx_data
+            .complete_fingerprint(&NaiveProtocol::<bls12_381::G1>::new(secret))
+            .await?;
+
+        let client = FingerprintClient::new(addr).with_curve::<bls12_381::G1>();
+        assert_eq!(client.fingerprint(&tx).await?, expected);
+
+        let (fingerprint, point) = client.verifiable_fingerprint(&tx).await?;
+        assert_eq!(client.group_key().await?, group_key);
+        verify_fingerprint(&tx_data, &fingerprint, &point, &group_key)?;
+
+        // Points of BN256 are not accepted by the service
+        let bn256_client = FingerprintClient::new(addr);
+        assert!(bn256_client.fingerprint(&tx).await.is_err());
+        assert!(bn256_client.group_key().await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tenant_fingerprint_computation() -> Result<(), Error> {
+        let secret = Fr::from(42u64);
+        let group_key = G2::generator() * secret;
+        let key = TenantKey::generate();
+        let retail = Tenant::new("retail")?;
+        let corporate = Tenant::new("corporate")?;
+
+        let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        tokio::spawn(
+            Server::new()
+                .add_service(
+                    ServiceBuilder::new(FingerprintServiceServer::new(
+                        FingerprintService::new(
+                            NaiveProtocol::<G1>::new(secret).with_tenant_key(key.clone()),
+                        )
+                        .with_group_key(GroupKey::new((G1::generator() * secret, group_key).into()))
+                        .with_tenant_allowlist(
+                            TenantAllowlist::default()
+                                .with_caller("retail-app", "retail-token", [retail.clone()])?
+                                .with_caller("corporate-app", "corporate-token", [corporate])?,
+                        ),
+                    ))
+                    .build(),
+                )
+                .run(Address::from(addr)),
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let tx = sample_transaction().build()?;
+        let tx_data: TransactionFingerprintData<Fr> = (&tx).try_into()?;
+
+        let protocol = NaiveProtocol::<G1>::new(secret).with_tenant_key(key);
+        let scope = protocol.scope(&retail)?;
+        let tenant_group_key = group_key * scope.scalar();
+        let expected = tx_data
+            .complete_fingerprint(&BlindedProtocol::new(TenantEvaluator::new(
+                &protocol, scope,
+            )))
+            .await?;
+
+        let client = FingerprintClient::new(addr)
+            .with_token("retail-token")?
+            .with_tenant(retail.clone());
+        assert_eq!(client.fingerprint(&tx).await?, expected);
+        assert_ne!(expected, tx_data.complete_fingerprint(&protocol).await?);
+
+        // Fingerprints of the tenant verify with the group key of the tenant
+        let (fingerprint, point) = client.verifiable_fingerprint(&tx).await?;
+        assert_eq!(client.group_key().await?, tenant_group_key);
+        verify_fingerprint(&tx_data, &fingerprint, &point, &tenant_group_key)?;
+        assert!(verify_fingerprint(&tx_data, &fingerprint, &point, &group_key).is_err());
+
+        // Callers use only their tenants, and must authenticate even for the default one
+        let corporate_client = FingerprintClient::new(addr)
+            .with_token("corporate-token")?
+            .with_tenant(retail.clone());
+        assert!(corporate_client.fingerprint(&tx).await.is_err());
+        assert!(corporate_client.group_key().await.is_err());
+        assert!(FingerprintClient::new(addr)
+            .with_tenant(retail)
+            .fingerprint(&tx)
+            .await
+            .is_err());
+        assert!(FingerprintClient::new(addr).fingerprint(&tx).await.is_err());
+
+        Ok(())
+    }
+}
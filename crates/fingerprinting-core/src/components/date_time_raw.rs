@@ -1,4 +1,5 @@
 use crate::components::{FingerprintComponent, SqueezeComponent};
+use crate::schema::TimeGranularity;
 use crate::EPOCH;
 use anyhow::{anyhow, Error};
 use chrono::{DateTime, NaiveDate, Utc};
@@ -9,18 +10,32 @@ use std::io::Write;
 
 pub type Amount = (u64, u64);
 
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub struct DateTimeRaw {
     date_time: DateTime<Utc>,
-    wwd: NaiveDate,
+    /// Explicit World Wide Date (value date) other than the date of `date_time`, the date of `date_time` when not given
+    wwd: Option<NaiveDate>,
     amount: Amount,
 }
 
 impl DateTimeRaw {
+    /// World Wide Date equal to the date of the `date_time` is the same as no World Wide Date,
+    /// it moves along with date time buckets
     pub fn new(date_time: DateTime<Utc>, wwd: NaiveDate, amount: Amount) -> Self {
         DateTimeRaw {
             date_time,
-            wwd,
+            wwd: Some(wwd).filter(|wwd| *wwd != date_time.date_naive()),
+            amount,
+        }
+    }
+
+    /// World Wide Date is the date of the `date_time`, it moves along with date time buckets
+    pub fn from_date_time(date_time: DateTime<Utc>, amount: Amount) -> Self {
+        DateTimeRaw {
+            date_time,
+            wwd: None,
             amount,
         }
     }
@@ -28,6 +43,10 @@ impl DateTimeRaw {
     pub fn date_time(&self) -> &DateTime<Utc> {
         &self.date_time
     }
+
+    pub fn wwd(&self) -> NaiveDate {
+        self.wwd.unwrap_or(self.date_time.date_naive())
+    }
 }
 
 #[derive(Debug)]
@@ -64,23 +83,27 @@ impl FingerprintComponent<DateTimeRaw, 32> for DateTimeComponent {
 
 impl SqueezeComponent<Fr> for DateTimeComponent {
     fn squeeze(&self) -> Result<Fr, Error> {
+        self.squeeze_bucket(TimeGranularity::Exact, 0)
+    }
+}
+
+impl DateTimeComponent {
+    ///
+    /// Squeeze the start of the date time bucket of the `granularity`, shifted by `offset` buckets.
+    /// The exact granularity without offset squeezes the date time itself.
+    pub fn squeeze_bucket(&self, granularity: TimeGranularity, offset: i64) -> Result<Fr, Error> {
         let amount_base = U256::from(self.raw.amount.0);
         let amount_atto = U256::from(self.raw.amount.1);
         let full_amount = amount_base * U256::from(10u64.pow(18)) + amount_atto;
 
-        let date_time = self.raw.date_time;
-        let seconds_since_epoch = date_time
-            .naive_local()
-            .signed_duration_since(EPOCH)
-            .num_seconds();
+        let (bucket_start, days_since_epoch) = self.bucket(granularity, offset)?;
 
-        if seconds_since_epoch < 0 {
+        if bucket_start < 0 {
             return Err(anyhow!("Date cannot be earlier than Epoch: 01.01.2025"));
         }
 
         let seconds_since_epoch =
-            U256::from(u64::try_from(seconds_since_epoch).expect("validated non-negative above"));
-        let days_since_epoch = self.raw.wwd.signed_duration_since(EPOCH.date()).num_days();
+            U256::from(u64::try_from(bucket_start).expect("validated non-negative above"));
 
         if days_since_epoch < 0 || days_since_epoch > i64::from(u32::MAX) {
             return Err(anyhow!(
@@ -91,8 +114,11 @@ impl SqueezeComponent<Fr> for DateTimeComponent {
         let days_since_epoch =
             U256::from(u64::try_from(days_since_epoch).expect("validated in range [0, u32::MAX]"));
 
-        // Calculating pair function
-        let paired_data = cantor_pair_function(seconds_since_epoch, full_amount / days_since_epoch);
+        // Calculating pair function, the amount is divided by the days since the Epoch
+        let amount_per_day = full_amount.checked_div(days_since_epoch).ok_or(anyhow!(
+            "World Wide Date cannot be the day of Epoch: 01.01.2025"
+        ))?;
+        let paired_data = cantor_pair_function(seconds_since_epoch, amount_per_day);
 
         // Specs for 3 Fr input
         let mut poseidon = Poseidon::<Fr>::new_circom(3)?;
@@ -109,4 +135,55 @@ impl SqueezeComponent<Fr> for DateTimeComponent {
 
         Ok(hash)
     }
+
+    ///
+    /// Whether the date time bucket of the `granularity` shifted by `offset` buckets starts before the Epoch
+    /// or its World Wide Date is not after the Epoch, such buckets cannot be squeezed.
+    pub fn is_before_epoch(
+        &self,
+        granularity: TimeGranularity,
+        offset: i64,
+    ) -> Result<bool, Error> {
+        let (bucket_start, days_since_epoch) = self.bucket(granularity, offset)?;
+
+        Ok(bucket_start < 0 || days_since_epoch <= 0)
+    }
+
+    /// Start of the shifted date time bucket in seconds since the Epoch together with its World Wide Date
+    /// in days since the Epoch
+    fn bucket(&self, granularity: TimeGranularity, offset: i64) -> Result<(i64, i64), Error> {
+        let seconds_since_epoch = self
+            .raw
+            .date_time
+            .naive_local()
+            .signed_duration_since(EPOCH)
+            .num_seconds();
+
+        // Start of the bucket, the World Wide Date derived from the date time follows
+        // when the bucket starts on another day, explicit one stays
+        let bucket_size = granularity.seconds();
+        let bucket_start = seconds_since_epoch
+            .div_euclid(bucket_size)
+            .checked_add(offset)
+            .and_then(|bucket| bucket.checked_mul(bucket_size))
+            .ok_or(anyhow!(
+                "Date time bucket offset {} is out of range",
+                offset
+            ))?;
+        let days_shift = match self.raw.wwd {
+            Some(_) => 0,
+            None => {
+                bucket_start.div_euclid(SECONDS_PER_DAY)
+                    - seconds_since_epoch.div_euclid(SECONDS_PER_DAY)
+            }
+        };
+        let days_since_epoch = self
+            .raw
+            .wwd()
+            .signed_duration_since(EPOCH.date())
+            .num_days()
+            + days_shift;
+
+        Ok((bucket_start, days_since_epoch))
+    }
 }
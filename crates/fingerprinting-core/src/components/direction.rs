@@ -0,0 +1,35 @@
+use fingerprinting_types::Direction;
+use std::io::Write;
+
+use crate::components::FingerprintComponent;
+
+#[derive(Debug)]
+pub struct DirectionComponent {
+    direction: Option<Direction>,
+}
+
+impl FingerprintComponent<Option<Direction>, 1> for DirectionComponent {
+    fn new(original: Option<Direction>) -> Self {
+        Self {
+            direction: original,
+        }
+    }
+
+    fn serialize<W: Write>(&self, buffer: &mut W) -> Result<(), anyhow::Error> {
+        // Single byte indicator, transactions of unknown direction are serialized as zero
+        let indicator: u8 = match self.direction {
+            None => 0,
+            Some(Direction::Debit) => 1,
+            Some(Direction::Credit) => 2,
+        };
+
+        let written = buffer.write(&[indicator])?;
+
+        debug_assert_eq!(written, Self::size());
+        Ok(())
+    }
+
+    fn raw(&self) -> &Option<Direction> {
+        &self.direction
+    }
+}
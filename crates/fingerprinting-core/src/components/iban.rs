@@ -0,0 +1,161 @@
+use anyhow::anyhow;
+use std::io::Write;
+
+use crate::components::FingerprintComponent;
+
+// IBAN lengths by country code, according to the ISO 13616 registry
+#[rustfmt::skip]
+static IBAN_LENGTHS: &[(&str, usize)] = &[
+    ("AD", 24), ("AE", 23), ("AL", 28), ("AT", 20), ("AZ", 28), ("BA", 20), ("BE", 16), ("BG", 22),
+    ("BH", 22), ("BI", 27), ("BR", 29), ("BY", 28), ("CH", 21), ("CR", 22), ("CY", 28), ("CZ", 24),
+    ("DE", 22), ("DJ", 27), ("DK", 18), ("DO", 28), ("EE", 20), ("EG", 29), ("ES", 24), ("FI", 18),
+    ("FK", 18), ("FO", 18), ("FR", 27), ("GB", 22), ("GE", 22), ("GI", 23), ("GL", 18), ("GR", 27),
+    ("GT", 28), ("HR", 21), ("HU", 28), ("IE", 22), ("IL", 23), ("IQ", 23), ("IS", 26), ("IT", 27),
+    ("JO", 30), ("KW", 30), ("KZ", 20), ("LB", 28), ("LC", 32), ("LI", 21), ("LT", 20), ("LU", 20),
+    ("LV", 21), ("LY", 25), ("MC", 27), ("MD", 24), ("ME", 22), ("MK", 19), ("MN", 20), ("MR", 27),
+    ("MT", 31), ("MU", 30), ("NI", 28), ("NL", 18), ("NO", 15), ("OM", 23), ("PK", 24), ("PL", 28),
+    ("PS", 29), ("PT", 25), ("QA", 29), ("RO", 24), ("RS", 22), ("RU", 33), ("SA", 24), ("SC", 31),
+    ("SD", 18), ("SE", 24), ("SI", 19), ("SK", 24), ("SM", 27), ("SO", 23), ("ST", 25), ("SV", 28),
+    ("TL", 23), ("TN", 24), ("TR", 26), ("UA", 29), ("VA", 22), ("VG", 24), ("XK", 20), ("YE", 30),
+];
+
+#[derive(Debug)]
+pub struct IbanComponent {
+    iban: Option<String>,
+}
+
+impl IbanComponent {
+    ///
+    /// IBAN in the electronic format (no whitespace, upper case) validated against ISO 13616:
+    /// country specific length and mod-97 check digits
+    pub fn normalize(iban: &str) -> Result<String, anyhow::Error> {
+        let iban = iban
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+
+        if !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(anyhow!(
+                "IBAN is invalid, only letters and digits are allowed"
+            ));
+        }
+
+        let country_code = iban.get(0..2).unwrap_or_default();
+        let expected_length = IBAN_LENGTHS
+            .iter()
+            .find(|(country, _)| *country == country_code)
+            .map(|(_, length)| *length)
+            .ok_or(anyhow!(
+                "IBAN is invalid, unknown country code {}",
+                country_code
+            ))?;
+
+        if iban.len() != expected_length {
+            return Err(anyhow!(
+                "IBAN is invalid, {} IBAN should be {} characters long, got {}",
+                country_code,
+                expected_length,
+                iban.len()
+            ));
+        }
+
+        // Move country code and check digits to the end, letters are 10 to 35
+        let remainder = iban[4..]
+            .chars()
+            .chain(iban[..4].chars())
+            .filter_map(|c| c.to_digit(36))
+            .fold(0u32, |remainder, digit| {
+                if digit < 10 {
+                    (remainder * 10 + digit) % 97
+                } else {
+                    (remainder * 100 + digit) % 97
+                }
+            });
+
+        if remainder != 1 {
+            return Err(anyhow!("IBAN is invalid, check digits do not match"));
+        }
+
+        Ok(iban)
+    }
+}
+
+impl FingerprintComponent<Option<String>, 34> for IbanComponent {
+    fn new(original: Option<String>) -> Self {
+        Self { iban: original }
+    }
+
+    fn serialize<W: Write>(&self, buffer: &mut W) -> Result<(), anyhow::Error> {
+        // Normalized IBAN (at most 34 characters) padded with zeros,
+        // transactions without IBAN are serialized as zeros only
+        let mut serialized = [0u8; 34];
+
+        if let Some(iban) = &self.iban {
+            let iban = Self::normalize(iban)?;
+            serialized[..iban.len()].copy_from_slice(iban.as_bytes());
+        }
+
+        buffer.write_all(&serialized)?;
+
+        debug_assert_eq!(serialized.len(), Self::size());
+        Ok(())
+    }
+
+    fn raw(&self) -> &Option<String> {
+        &self.iban
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iban_normalization() -> Result<(), anyhow::Error> {
+        assert_eq!(
+            IbanComponent::normalize("de89 3704 0044 0532 0130 00")?,
+            "DE89370400440532013000"
+        );
+        assert_eq!(
+            IbanComponent::normalize("GB82WEST12345698765432")?,
+            "GB82WEST12345698765432"
+        );
+        assert_eq!(
+            IbanComponent::normalize("LU28 0019 4006 4475 0000")?,
+            "LU280019400644750000"
+        );
+
+        // Wrong check digits
+        assert!(IbanComponent::normalize("DE88370400440532013000").is_err());
+        // Wrong length for the country
+        assert!(IbanComponent::normalize("DE8937040044053201300").is_err());
+        // Unknown country
+        assert!(IbanComponent::normalize("ZZ89370400440532013000").is_err());
+        // Not alphanumeric
+        assert!(IbanComponent::normalize("DE89-3704-0044-0532-0130-00").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_iban_serialization() -> Result<(), anyhow::Error> {
+        let mut with_iban = Vec::new();
+        IbanComponent::new(Some("de89 3704 0044 0532 0130 00".to_string()))
+            .serialize(&mut with_iban)?;
+        let mut without_iban = Vec::new();
+        IbanComponent::new(None).serialize(&mut without_iban)?;
+
+        assert_eq!(with_iban.len(), IbanComponent::size());
+        assert_eq!(&with_iban[..22], b"DE89370400440532013000");
+        assert_eq!(without_iban, vec![0u8; 34]);
+
+        assert!(
+            IbanComponent::new(Some("DE88370400440532013000".to_string()))
+                .serialize(&mut Vec::new())
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
@@ -5,6 +5,9 @@ mod amount;
 mod bank_identifier;
 mod currency;
 mod date_time_raw;
+mod direction;
+mod iban;
+mod reference;
 mod scalar;
 
 pub trait SqueezeComponent<F: PrimeField> {
@@ -32,4 +35,7 @@ pub use bank_identifier::BankIdentifierComponent;
 pub use currency::CurrencyComponent;
 pub use date_time_raw::DateTimeComponent;
 pub use date_time_raw::DateTimeRaw;
+pub use direction::DirectionComponent;
+pub use iban::IbanComponent;
+pub use reference::ReferenceComponent;
 pub use scalar::ScalarComponent;
@@ -0,0 +1,149 @@
+use anyhow::anyhow;
+use regex::Regex;
+use std::io::Write;
+
+use crate::components::FingerprintComponent;
+
+/// Placeholder of SEPA payments without an end-to-end identification, it doesn't identify anything
+const NOT_PROVIDED: &str = "NOTPROVIDED";
+
+/// End-to-end reference of the payment, holds the reference returned by [`ReferenceComponent::normalize`]
+#[derive(Debug)]
+pub struct ReferenceComponent {
+    reference: Option<String>,
+}
+
+impl ReferenceComponent {
+    ///
+    /// Normalized end-to-end reference of the payment, `None` for the `NOTPROVIDED` placeholder.
+    ///
+    /// Reference in the UUID format is the UETR of SWIFT gpi (36 characters, UUIDv4) and is normalized
+    /// to lower case. Any other reference is the EndToEndId (ISO 20022 Max35Text, SEPA character set)
+    /// normalized to upper case without whitespace. The EndToEndId is at most 35 characters long,
+    /// so the two never collide.
+    pub fn normalize(reference: &str) -> Result<Option<String>, anyhow::Error> {
+        let reference = reference.trim();
+
+        let uuid_format = Regex::new(
+            r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+        )?;
+
+        if uuid_format.is_match(reference) {
+            let uetr = reference.to_ascii_lowercase();
+
+            // Version nibble 4 and RFC 4122 variant (8, 9, a, b)
+            if uetr.as_bytes()[14] != b'4' || !matches!(uetr.as_bytes()[19], b'8'..=b'b') {
+                return Err(anyhow!("UETR is invalid, UUIDv4 expected, got {}", uetr));
+            }
+
+            return Ok(Some(uetr));
+        }
+
+        let end_to_end_id = reference
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+
+        if end_to_end_id == NOT_PROVIDED {
+            return Ok(None);
+        }
+
+        let end_to_end_id_format = Regex::new(r"^[A-Z0-9/\-?:().,'+]{1,35}$")?;
+
+        if !end_to_end_id_format.is_match(&end_to_end_id) {
+            return Err(anyhow!(
+                "EndToEndId is invalid, expected 1 to 35 characters of A-Z, 0-9 and / - ? : ( ) . , ' +"
+            ));
+        }
+
+        Ok(Some(end_to_end_id))
+    }
+}
+
+impl FingerprintComponent<Option<String>, 36> for ReferenceComponent {
+    fn new(original: Option<String>) -> Self {
+        Self {
+            reference: original,
+        }
+    }
+
+    fn serialize<W: Write>(&self, buffer: &mut W) -> Result<(), anyhow::Error> {
+        // Normalized reference (at most 36 characters) padded with zeros,
+        // transactions without reference are serialized as zeros only
+        let mut serialized = [0u8; 36];
+
+        if let Some(reference) = &self.reference {
+            serialized
+                .get_mut(..reference.len())
+                .ok_or(anyhow!(
+                    "Reference is not normalized, {} characters exceed the size of the component",
+                    reference.len()
+                ))?
+                .copy_from_slice(reference.as_bytes());
+        }
+
+        buffer.write_all(&serialized)?;
+
+        debug_assert_eq!(serialized.len(), Self::size());
+        Ok(())
+    }
+
+    fn raw(&self) -> &Option<String> {
+        &self.reference
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_normalization() -> Result<(), anyhow::Error> {
+        // UETR
+        assert_eq!(
+            ReferenceComponent::normalize(" EB6305C9-1F7F-49DE-AED0-16487C27B42D ")?.as_deref(),
+            Some("eb6305c9-1f7f-49de-aed0-16487c27b42d")
+        );
+        // Not a UUIDv4 (version 1, variant 0)
+        assert!(ReferenceComponent::normalize("eb6305c9-1f7f-19de-aed0-16487c27b42d").is_err());
+        assert!(ReferenceComponent::normalize("eb6305c9-1f7f-49de-7ed0-16487c27b42d").is_err());
+
+        // EndToEndId
+        assert_eq!(
+            ReferenceComponent::normalize("inv 2025/0042")?.as_deref(),
+            Some("INV2025/0042")
+        );
+        assert_eq!(ReferenceComponent::normalize("NotProvided")?, None);
+        assert!(ReferenceComponent::normalize("").is_err());
+        assert!(ReferenceComponent::normalize("INV_2025").is_err());
+        assert!(ReferenceComponent::normalize(&"A".repeat(36)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reference_serialization() -> Result<(), anyhow::Error> {
+        let mut uetr = Vec::new();
+        ReferenceComponent::new(ReferenceComponent::normalize(
+            "EB6305C9-1F7F-49DE-AED0-16487C27B42D",
+        )?)
+        .serialize(&mut uetr)?;
+        let mut end_to_end_id = Vec::new();
+        ReferenceComponent::new(ReferenceComponent::normalize("inv 2025/0042")?)
+            .serialize(&mut end_to_end_id)?;
+        let mut not_provided = Vec::new();
+        ReferenceComponent::new(ReferenceComponent::normalize("NOTPROVIDED")?)
+            .serialize(&mut not_provided)?;
+        let mut without_reference = Vec::new();
+        ReferenceComponent::new(None).serialize(&mut without_reference)?;
+
+        assert_eq!(uetr, b"eb6305c9-1f7f-49de-aed0-16487c27b42d");
+        assert_eq!(end_to_end_id.len(), ReferenceComponent::size());
+        assert_eq!(&end_to_end_id[..12], b"INV2025/0042");
+        assert_eq!(not_provided, vec![0u8; 36]);
+        assert_eq!(without_reference, vec![0u8; 36]);
+
+        Ok(())
+    }
+}
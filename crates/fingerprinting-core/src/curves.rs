@@ -0,0 +1,153 @@
+//!
+//! Pairing friendly curves the secret is applied on.
+//!
+//! Values are hashed to G1 of the curve, secret shards are scalars of the curve and the group key is in its G2.
+//! Points are squeezed into fingerprints of the BN256 scalar field whatever the curve is, so the schemas,
+//! recorded fingerprints and zero-knowledge proofs don't depend on it. BN256 is the curve of the first networks,
+//! BLS12-381 is offered to partners requiring about 128 bits of security.
+use anyhow::{anyhow, Error};
+use halo2_axiom::halo2curves::bls12_381;
+use halo2_axiom::halo2curves::bn256::{self, Fr};
+use halo2_axiom::halo2curves::ff::{FromUniformBytes, PrimeField};
+use halo2_axiom::halo2curves::group::cofactor::CofactorGroup;
+use halo2_axiom::halo2curves::group::GroupEncoding;
+use halo2_axiom::halo2curves::CurveExt;
+use std::str::FromStr;
+
+use crate::{Compact, HashSqueeze};
+
+/// Curve of the network, all agents must use the same one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Curve {
+    #[default]
+    Bn256,
+    Bls12_381,
+}
+
+impl Curve {
+    /// Name of the curve in configurations, the inverse of `from_str`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Curve::Bn256 => "bn256",
+            Curve::Bls12_381 => "bls12-381",
+        }
+    }
+}
+
+impl FromStr for Curve {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "bn256" => Ok(Curve::Bn256),
+            "bls12-381" => Ok(Curve::Bls12_381),
+            _ => Err(anyhow!(
+                "Unknown curve {}, expected one of bn256, bls12-381",
+                name
+            )),
+        }
+    }
+}
+
+///
+/// G1 of the curve the fingerprints are computed on, together with its G2 and pairing
+/// used to verify fingerprints against the group key
+pub trait FingerprintCurve:
+    CurveExt<ScalarExt: FromUniformBytes<64> + Compact> + HashSqueeze<Fr>
+{
+    const CURVE: Curve;
+
+    /// Group of the group key `[k] G2`
+    type G2: CurveExt<ScalarExt = Self::ScalarExt> + Compact;
+
+    /// Points received from the outside must be in the prime order subgroup of G2
+    fn is_torsion_free_g2(point: &Self::G2) -> bool;
+
+    /// Check `e(a, b) == e(c, d)`
+    fn pairing_eq(a: &Self, b: &Self::G2, c: &Self, d: &Self::G2) -> bool;
+}
+
+impl FingerprintCurve for bn256::G1 {
+    const CURVE: Curve = Curve::Bn256;
+
+    type G2 = bn256::G2;
+
+    fn is_torsion_free_g2(point: &bn256::G2) -> bool {
+        point.is_torsion_free().into()
+    }
+
+    fn pairing_eq(a: &Self, b: &bn256::G2, c: &Self, d: &bn256::G2) -> bool {
+        bn256::pairing(&a.into(), &b.into()) == bn256::pairing(&c.into(), &d.into())
+    }
+}
+
+impl FingerprintCurve for bls12_381::G1 {
+    const CURVE: Curve = Curve::Bls12_381;
+
+    type G2 = bls12_381::G2;
+
+    fn is_torsion_free_g2(point: &bls12_381::G2) -> bool {
+        bls12_381::G2Affine::from(point).is_torsion_free().into()
+    }
+
+    fn pairing_eq(a: &Self, b: &bls12_381::G2, c: &Self, d: &bls12_381::G2) -> bool {
+        bls12_381::pairing(&a.into(), &b.into()) == bls12_381::pairing(&c.into(), &d.into())
+    }
+}
+
+///
+/// Decode the compressed point of `G`, identity is accepted (e.g. for commitments), points received
+/// as the input of computations must be checked for it
+pub fn decode_point<G: GroupEncoding>(bytes: &[u8]) -> Result<G, Error> {
+    let mut compressed = G::Repr::default();
+
+    if bytes.len() != compressed.as_ref().len() {
+        return Err(anyhow!(
+            "expected {} bytes of compressed point, got {}",
+            compressed.as_ref().len(),
+            bytes.len()
+        ));
+    }
+    compressed.as_mut().copy_from_slice(bytes);
+
+    G::from_bytes(&compressed)
+        .into_option()
+        .ok_or(anyhow!("value does not represent a point of the curve"))
+}
+
+///
+/// Decode the scalar in its canonical representation, e.g. a secret shard
+pub fn decode_scalar<F: PrimeField>(bytes: &[u8]) -> Result<F, Error> {
+    let mut repr = F::Repr::default();
+
+    if bytes.len() != repr.as_ref().len() {
+        return Err(anyhow!(
+            "expected {} bytes of scalar, got {}",
+            repr.as_ref().len(),
+            bytes.len()
+        ));
+    }
+    repr.as_mut().copy_from_slice(bytes);
+
+    F::from_repr(repr)
+        .into_option()
+        .ok_or(anyhow!("value is not a canonical scalar"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_names() -> Result<(), Error> {
+        for curve in [Curve::Bn256, Curve::Bls12_381] {
+            assert_eq!(curve.name().parse::<Curve>()?, curve);
+        }
+
+        assert_eq!(<bn256::G1 as FingerprintCurve>::CURVE, Curve::default());
+        assert_eq!(<bls12_381::G1 as FingerprintCurve>::CURVE, Curve::Bls12_381);
+        assert!("secp256k1".parse::<Curve>().is_err());
+
+        Ok(())
+    }
+}
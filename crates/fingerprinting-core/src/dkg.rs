@@ -0,0 +1,504 @@
+use anyhow::{anyhow, Error};
+use halo2_axiom::halo2curves::ff::PrimeField;
+use halo2_axiom::halo2curves::group::{Group, GroupEncoding};
+use rand_core::OsRng;
+use sha2::{Digest, Sha512};
+use std::collections::BTreeMap;
+
+use crate::secret_sharing::{SecretSharing, ShareCommitments};
+
+// Joint-Feldman distributed key generation
+//
+// Every participant `i` acts as a dealer of its own random polynomial `f_i` of degree `t - 1`:
+// - publishes Feldman commitments `[a_ij] G` of the polynomial coefficients
+// - privately sends `f_i(j)` to every participant `j`
+//
+// Every participant verifies received evaluations against the commitments of the dealer and computes
+// - own secret shard `s_j = sum(f_i(j))`
+// - group commitments `C_k = sum(C_ik)`
+//
+// Master secret `k = sum(f_i(0))` is never computed by anyone.
+//
+// A dealer could send different commitments to different participants (equivocation), so before
+// finalizing participants compare digests of all commitments they received, see `transcript_digest`.
+//
+// Proactive refresh uses the same rounds, but every dealer shares zero (`f_i(0) = 0`), so
+// - new secret shard `s_j' = s_j + sum(f_i(j))`
+// - new commitments `C_k' = C_k + sum(C_ik)`
+// The master secret stays the same, while shards of different generations cannot be combined.
+pub struct DkgParticipant<F: PrimeField, G: Group<Scalar = F>> {
+    index: usize,
+    threshold: usize,
+    participants: usize,
+    dealing: SecretSharing<F>,
+    commitments: ShareCommitments<G>,
+    received: BTreeMap<usize, (ShareCommitments<G>, F)>,
+    // secret shard and commitments of the previous generation (refresh only)
+    previous: Option<(F, ShareCommitments<G>)>,
+}
+
+impl<F: PrimeField, G: Group<Scalar = F>> DkgParticipant<F, G> {
+    pub fn new(index: usize, threshold: usize, participants: usize) -> Result<Self, Error> {
+        let mut rng = OsRng;
+
+        Self::with_secret(index, threshold, participants, F::random(&mut rng), None)
+    }
+
+    /// Participant of the proactive refresh of the existing `secret_shard`
+    pub fn refresh(
+        index: usize,
+        participants: usize,
+        secret_shard: F,
+        commitments: ShareCommitments<G>,
+    ) -> Result<Self, Error> {
+        if !SecretSharing::verify_share(index, secret_shard, &commitments) {
+            return Err(anyhow!(
+                "Secret shard of agent {} does not match current commitments",
+                index
+            ));
+        }
+
+        Self::with_secret(
+            index,
+            commitments.threshold(),
+            participants,
+            F::ZERO,
+            Some((secret_shard, commitments)),
+        )
+    }
+
+    fn with_secret(
+        index: usize,
+        threshold: usize,
+        participants: usize,
+        secret: F,
+        previous: Option<(F, ShareCommitments<G>)>,
+    ) -> Result<Self, Error> {
+        if threshold == 0 || threshold > participants {
+            return Err(anyhow!(
+                "Invalid threshold {}, should be in range 1 to {}",
+                threshold,
+                participants
+            ));
+        }
+
+        if index == 0 || index > participants {
+            return Err(anyhow!(
+                "Invalid agent number {}, should be in range 1 to {}",
+                index,
+                participants
+            ));
+        }
+
+        let dealing = SecretSharing::generate(secret, threshold, participants);
+        let commitments = dealing.commitments();
+
+        let mut participant = Self {
+            index,
+            threshold,
+            participants,
+            dealing,
+            commitments,
+            received: BTreeMap::new(),
+            previous,
+        };
+
+        // Own dealing is always accepted
+        let own_share = participant.share_for(index)?;
+        participant
+            .received
+            .insert(index, (participant.commitments.clone(), own_share));
+
+        Ok(participant)
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn participants(&self) -> usize {
+        self.participants
+    }
+
+    pub fn is_refresh(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    /// Commitments of our own dealing, broadcast to every participant
+    pub fn commitments(&self) -> &ShareCommitments<G> {
+        &self.commitments
+    }
+
+    /// Evaluation of our own dealing for the `participant`, must be sent privately
+    pub fn share_for(&self, participant: usize) -> Result<F, Error> {
+        self.dealing
+            .get_shares()
+            .get(&participant)
+            .copied()
+            .ok_or(anyhow!("Unknown participant {}", participant))
+    }
+
+    /// Accept the dealing of another participant after verification against its commitments
+    pub fn receive(
+        &mut self,
+        dealer: usize,
+        commitments: ShareCommitments<G>,
+        share: F,
+    ) -> Result<(), Error> {
+        if dealer == 0 || dealer > self.participants {
+            return Err(anyhow!(
+                "Invalid dealer {}, should be in range 1 to {}",
+                dealer,
+                self.participants
+            ));
+        }
+
+        if commitments.threshold() != self.threshold {
+            return Err(anyhow!(
+                "Dealer {} committed to polynomial for threshold {}, expected {}",
+                dealer,
+                commitments.threshold(),
+                self.threshold
+            ));
+        }
+
+        if self.is_refresh() && commitments.group_key() != G::identity() {
+            return Err(anyhow!(
+                "Dealer {} does not share zero during refresh, dealing would change the secret",
+                dealer
+            ));
+        }
+
+        if !SecretSharing::verify_share(self.index, share, &commitments) {
+            return Err(anyhow!(
+                "Share from dealer {} does not match its commitments",
+                dealer
+            ));
+        }
+
+        if let Some((known_commitments, known_share)) = self.received.get(&dealer) {
+            // Retransmission of the same dealing is fine, conflicting dealings are not
+            if *known_commitments != commitments || *known_share != share {
+                return Err(anyhow!("Dealer {} sent conflicting dealings", dealer));
+            }
+            return Ok(());
+        }
+
+        self.received.insert(dealer, (commitments, share));
+
+        Ok(())
+    }
+
+    /// Dealers we still wait for
+    pub fn missing(&self) -> Vec<usize> {
+        (1..=self.participants)
+            .filter(|dealer| !self.received.contains_key(dealer))
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.received.len() == self.participants
+    }
+
+    /// Combine all dealings into our secret shard and the group commitments
+    pub fn finalize(&self) -> Result<(F, ShareCommitments<G>), Error> {
+        if !self.is_complete() {
+            return Err(anyhow!(
+                "Key generation is not complete, missing dealings from {:?}",
+                self.missing()
+            ));
+        }
+
+        let (mut secret_shard, mut commitments) = match &self.previous {
+            Some((secret_shard, commitments)) => {
+                (*secret_shard, commitments.commitments().to_vec())
+            }
+            None => (F::ZERO, vec![G::identity(); self.threshold]),
+        };
+
+        for (dealer_commitments, share) in self.received.values() {
+            secret_shard += share;
+
+            for (combined, commitment) in
+                commitments.iter_mut().zip(dealer_commitments.commitments())
+            {
+                *combined += commitment;
+            }
+        }
+
+        Ok((secret_shard, ShareCommitments::new(commitments)))
+    }
+}
+
+impl<F: PrimeField, G: Group<Scalar = F> + GroupEncoding> DkgParticipant<F, G> {
+    /// Digest of the commitments of all dealings, participants compare it before finalizing
+    pub fn transcript_digest(&self) -> Result<[u8; 64], Error> {
+        if !self.is_complete() {
+            return Err(anyhow!(
+                "Key generation is not complete, missing dealings from {:?}",
+                self.missing()
+            ));
+        }
+
+        Ok(transcript_digest(
+            self.received
+                .iter()
+                .map(|(dealer, (commitments, _))| (*dealer, commitments)),
+        ))
+    }
+}
+
+///
+/// Digest of the commitments of the `dealings` by dealer, the same for all receivers of a ceremony
+/// unless some dealer sent different commitments to different receivers
+pub fn transcript_digest<'a, G: Group + GroupEncoding + 'a>(
+    dealings: impl IntoIterator<Item = (usize, &'a ShareCommitments<G>)>,
+) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update(TRANSCRIPT_DOMAIN);
+
+    for (dealer, commitments) in dealings {
+        hasher.update((dealer as u64).to_le_bytes());
+        hasher.update((commitments.threshold() as u64).to_le_bytes());
+        for commitment in commitments.commitments() {
+            hasher.update(commitment.to_bytes());
+        }
+    }
+
+    hasher.finalize().into()
+}
+
+// Domain separation of the transcript digest
+const TRANSCRIPT_DOMAIN: &[u8] = b"PSO_TX_FINGERPRINT_DKG_TRANSCRIPT_V1";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_axiom::halo2curves::bn256::{Fr, G1};
+
+    fn run_ceremony(t: usize, n: usize) -> Result<Vec<(Fr, ShareCommitments<G1>)>, Error> {
+        let mut participants = (1..=n)
+            .map(|i| DkgParticipant::<Fr, G1>::new(i, t, n))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        for dealer in 0..n {
+            let commitments = participants[dealer].commitments().clone();
+            let shares = (1..=n)
+                .map(|j| participants[dealer].share_for(j))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            for (receiver, share) in participants.iter_mut().zip(shares) {
+                receiver.receive(dealer + 1, commitments.clone(), share)?;
+            }
+        }
+
+        participants.iter().map(|p| p.finalize()).collect()
+    }
+
+    #[test]
+    fn test_dkg_shares_reconstruct_group_key() -> Result<(), Error> {
+        let results = run_ceremony(3, 5)?;
+
+        // All participants agree on the group commitments
+        let commitments = results[0].1.clone();
+        assert!(results.iter().all(|(_, c)| *c == commitments));
+
+        for (i, (share, _)) in results.iter().enumerate() {
+            assert!(SecretSharing::verify_share(i + 1, *share, &commitments));
+        }
+
+        // Any threshold subset reconstructs the same secret behind the group key
+        for indices in [vec![1, 2, 3], vec![2, 4, 5], vec![1, 3, 5]] {
+            let mut secret = Fr::zero();
+            for &i in &indices {
+                let lambda_i: Fr = SecretSharing::lagrange_coefficient(i, &indices);
+                secret += results[i - 1].0 * lambda_i;
+            }
+
+            assert_eq!(G1::generator() * secret, commitments.group_key());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dkg_rejects_invalid_share() -> Result<(), Error> {
+        let dealer = DkgParticipant::<Fr, G1>::new(1, 2, 3)?;
+        let mut receiver = DkgParticipant::<Fr, G1>::new(2, 2, 3)?;
+
+        let share = dealer.share_for(2)? + Fr::one();
+
+        assert!(receiver
+            .receive(1, dealer.commitments().clone(), share)
+            .is_err());
+        assert_eq!(receiver.missing(), vec![1, 3]);
+        assert!(receiver.finalize().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dkg_rejects_conflicting_dealings() -> Result<(), Error> {
+        let dealer = DkgParticipant::<Fr, G1>::new(1, 2, 3)?;
+        let another_dealer = DkgParticipant::<Fr, G1>::new(1, 2, 3)?;
+        let mut receiver = DkgParticipant::<Fr, G1>::new(2, 2, 3)?;
+
+        receiver.receive(1, dealer.commitments().clone(), dealer.share_for(2)?)?;
+        // Retransmission is accepted
+        receiver.receive(1, dealer.commitments().clone(), dealer.share_for(2)?)?;
+
+        assert!(receiver
+            .receive(
+                1,
+                another_dealer.commitments().clone(),
+                another_dealer.share_for(2)?
+            )
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_refresh_keeps_group_key() -> Result<(), Error> {
+        let (t, n) = (3, 5);
+        let initial = run_ceremony(t, n)?;
+        let initial_commitments = initial[0].1.clone();
+
+        let mut participants = initial
+            .iter()
+            .enumerate()
+            .map(|(i, (share, commitments))| {
+                DkgParticipant::<Fr, G1>::refresh(i + 1, n, *share, commitments.clone())
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        for dealer in 0..n {
+            let commitments = participants[dealer].commitments().clone();
+            let shares = (1..=n)
+                .map(|j| participants[dealer].share_for(j))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            for (receiver, share) in participants.iter_mut().zip(shares) {
+                receiver.receive(dealer + 1, commitments.clone(), share)?;
+            }
+        }
+
+        let refreshed = participants
+            .iter()
+            .map(|p| p.finalize())
+            .collect::<Result<Vec<_>, Error>>()?;
+        let refreshed_commitments = refreshed[0].1.clone();
+
+        assert_eq!(
+            refreshed_commitments.group_key(),
+            initial_commitments.group_key()
+        );
+        assert_ne!(refreshed_commitments, initial_commitments);
+
+        for (i, (share, _)) in refreshed.iter().enumerate() {
+            assert_ne!(*share, initial[i].0);
+            assert!(SecretSharing::verify_share(
+                i + 1,
+                *share,
+                &refreshed_commitments
+            ));
+        }
+
+        // Mixing shards of different generations does not give the secret
+        let indices = vec![1, 2, 3];
+        let mixed = [initial[0].0, refreshed[1].0, refreshed[2].0]
+            .iter()
+            .zip(&indices)
+            .map(|(share, i)| *share * SecretSharing::<Fr>::lagrange_coefficient(*i, &indices))
+            .fold(Fr::zero(), |acc, v| acc + v);
+
+        assert_ne!(G1::generator() * mixed, initial_commitments.group_key());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_refresh_rejects_non_zero_dealing() -> Result<(), Error> {
+        let initial = run_ceremony(2, 3)?;
+        let mut receiver =
+            DkgParticipant::<Fr, G1>::refresh(1, 3, initial[0].0, initial[0].1.clone())?;
+
+        // Dealer of the fresh key generation instead of refresh
+        let dealer = DkgParticipant::<Fr, G1>::new(2, 2, 3)?;
+
+        assert!(receiver
+            .receive(2, dealer.commitments().clone(), dealer.share_for(1)?)
+            .is_err());
+
+        // Refresh must start from the valid shard
+        assert!(
+            DkgParticipant::<Fr, G1>::refresh(1, 3, initial[1].0, initial[0].1.clone()).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcript_digest_detects_equivocation() -> Result<(), Error> {
+        let dealer = DkgParticipant::<Fr, G1>::new(1, 2, 3)?;
+        let equivocation = DkgParticipant::<Fr, G1>::new(1, 2, 3)?;
+        let mut receivers = (2..=3)
+            .map(|i| DkgParticipant::<Fr, G1>::new(i, 2, 3))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        assert!(receivers[0].transcript_digest().is_err());
+
+        // Dealer 1 sends another polynomial to agent 3, both dealings are valid on their own
+        receivers[0].receive(1, dealer.commitments().clone(), dealer.share_for(2)?)?;
+        receivers[1].receive(
+            1,
+            equivocation.commitments().clone(),
+            equivocation.share_for(3)?,
+        )?;
+
+        for dealer in 0..2 {
+            let commitments = receivers[dealer].commitments().clone();
+            let shares = [
+                receivers[dealer].share_for(2)?,
+                receivers[dealer].share_for(3)?,
+            ];
+            for (receiver, share) in receivers.iter_mut().zip(shares) {
+                receiver.receive(dealer + 2, commitments.clone(), share)?;
+            }
+        }
+
+        assert_ne!(
+            receivers[0].transcript_digest()?,
+            receivers[1].transcript_digest()?
+        );
+
+        // Honest dealings give the same digest to everyone
+        let mut participants = (1..=3)
+            .map(|i| DkgParticipant::<Fr, G1>::new(i, 2, 3))
+            .collect::<Result<Vec<_>, Error>>()?;
+        for dealer in 0..3 {
+            let commitments = participants[dealer].commitments().clone();
+            let shares = (1..=3)
+                .map(|j| participants[dealer].share_for(j))
+                .collect::<Result<Vec<_>, Error>>()?;
+            for (receiver, share) in participants.iter_mut().zip(shares) {
+                receiver.receive(dealer + 1, commitments.clone(), share)?;
+            }
+        }
+
+        let digest = participants[0].transcript_digest()?;
+        assert!(participants
+            .iter()
+            .all(|p| p.transcript_digest().ok() == Some(digest)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dkg_invalid_parameters() {
+        assert!(DkgParticipant::<Fr, G1>::new(1, 0, 3).is_err());
+        assert!(DkgParticipant::<Fr, G1>::new(1, 4, 3).is_err());
+        assert!(DkgParticipant::<Fr, G1>::new(0, 2, 3).is_err());
+        assert!(DkgParticipant::<Fr, G1>::new(4, 2, 3).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::{anyhow, Error};
+use halo2_axiom::halo2curves::ff::{FromUniformBytes, PrimeField};
+use halo2_axiom::halo2curves::group::{Group, GroupEncoding};
+use rand_core::OsRng;
+use sha2::{Digest, Sha512};
+
+// Domain separation for the Fiat-Shamir challenge of the proof
+const DLEQ_DOMAIN: &[u8] = b"PSO_TX_FINGERPRINT_DLEQ_V1";
+
+/// Chaum-Pedersen proof of discrete logarithm equality.
+///
+/// Proves that the agent used the same secret share `s_i` for its public share `Y = [s_i] G`
+/// and for the returned exponent `E = [s_i] B`, without revealing `s_i`.
+///
+/// Non-interactive form (Fiat-Shamir):
+/// - prover picks random `w`, computes `A_1 = [w] G`, `A_2 = [w] B`
+/// - challenge `c = H(G, Y, B, E, A_1, A_2)`
+/// - response `z = w + c * s_i`
+///
+/// The verifier recomputes `A_1 = [z] G - [c] Y`, `A_2 = [z] B - [c] E` and checks the challenge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DleqProof<F: PrimeField> {
+    challenge: F,
+    response: F,
+}
+
+impl<F: PrimeField + FromUniformBytes<64>> DleqProof<F> {
+    /// Prove that `exponent = [secret] base` uses the same `secret` as `[secret] G`
+    pub fn prove<G: Group<Scalar = F> + GroupEncoding>(secret: F, base: G, exponent: G) -> Self {
+        let mut rng = OsRng;
+        let generator = G::generator();
+        let public_share = generator * secret;
+
+        let nonce = F::random(&mut rng);
+        let a_1 = generator * nonce;
+        let a_2 = base * nonce;
+
+        let challenge = Self::challenge(&public_share, &base, &exponent, &a_1, &a_2);
+        let response = nonce + challenge * secret;
+
+        Self {
+            challenge,
+            response,
+        }
+    }
+
+    /// Verify that `exponent` was computed with the secret behind `public_share`
+    pub fn verify<G: Group<Scalar = F> + GroupEncoding>(
+        &self,
+        public_share: G,
+        base: G,
+        exponent: G,
+    ) -> bool {
+        let a_1 = G::generator() * self.response - public_share * self.challenge;
+        let a_2 = base * self.response - exponent * self.challenge;
+
+        Self::challenge(&public_share, &base, &exponent, &a_1, &a_2) == self.challenge
+    }
+
+    fn challenge<G: Group<Scalar = F> + GroupEncoding>(
+        public_share: &G,
+        base: &G,
+        exponent: &G,
+        a_1: &G,
+        a_2: &G,
+    ) -> F {
+        let mut hasher = Sha512::new();
+        hasher.update(DLEQ_DOMAIN);
+
+        for point in [&G::generator(), public_share, base, exponent, a_1, a_2] {
+            hasher.update(point.to_bytes().as_ref());
+        }
+
+        let digest: [u8; 64] = hasher.finalize().into();
+
+        F::from_uniform_bytes(&digest)
+    }
+
+    /// Serialized as `challenge || response`, each scalar in its canonical representation
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::size());
+        bytes.extend_from_slice(self.challenge.to_repr().as_ref());
+        bytes.extend_from_slice(self.response.to_repr().as_ref());
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let scalar_size = Self::size() / 2;
+
+        if bytes.len() != Self::size() {
+            return Err(anyhow!(
+                "Invalid proof length {}, expected {} bytes",
+                bytes.len(),
+                Self::size()
+            ));
+        }
+
+        let challenge = Self::scalar_from_bytes(&bytes[..scalar_size])?;
+        let response = Self::scalar_from_bytes(&bytes[scalar_size..])?;
+
+        Ok(Self {
+            challenge,
+            response,
+        })
+    }
+
+    fn scalar_from_bytes(bytes: &[u8]) -> Result<F, Error> {
+        let mut repr = F::Repr::default();
+        repr.as_mut().copy_from_slice(bytes);
+
+        F::from_repr(repr)
+            .into_option()
+            .ok_or(anyhow!("Invalid proof, scalar is not canonical"))
+    }
+
+    /// size of the serialized proof in bytes
+    pub fn size() -> usize {
+        2 * F::Repr::default().as_ref().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_axiom::halo2curves::bn256::{Fr, G1};
+    use halo2_axiom::halo2curves::ff::Field;
+
+    #[test]
+    fn test_valid_proof_verifies() {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let base = G1::random(&mut rng);
+        let exponent = base * secret;
+
+        let proof = DleqProof::prove(secret, base, exponent);
+
+        assert!(proof.verify(G1::generator() * secret, base, exponent));
+    }
+
+    #[test]
+    fn test_wrong_exponent_rejected() {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let base = G1::random(&mut rng);
+
+        // Agent claims it used `secret`, but returns exponent for another scalar
+        let exponent = base * Fr::random(&mut rng);
+        let proof = DleqProof::prove(secret, base, exponent);
+
+        assert!(!proof.verify(G1::generator() * secret, base, exponent));
+    }
+
+    #[test]
+    fn test_wrong_public_share_rejected() {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let base = G1::random(&mut rng);
+        let exponent = base * secret;
+
+        let proof = DleqProof::prove(secret, base, exponent);
+
+        assert!(!proof.verify(G1::random(&mut rng), base, exponent));
+    }
+
+    #[test]
+    fn test_proof_serialization_roundtrip() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let base = G1::random(&mut rng);
+        let exponent = base * secret;
+
+        let proof = DleqProof::prove(secret, base, exponent);
+        let bytes = proof.to_bytes();
+
+        assert_eq!(bytes.len(), DleqProof::<Fr>::size());
+        assert_eq!(DleqProof::<Fr>::from_bytes(&bytes)?, proof);
+        assert!(DleqProof::<Fr>::from_bytes(&bytes[1..]).is_err());
+
+        Ok(())
+    }
+}
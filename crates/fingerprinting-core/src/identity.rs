@@ -0,0 +1,301 @@
+//!
+//! Long-term identity keys of agents authenticating messages between them.
+//!
+//! Agent `i` holds the identity key `x_i`, other agents know its public identity `X_i = [x_i] G`.
+//! Two agents agree on the channel key `K_ij = H(X_i, X_j, [x_i] X_j)` without any interaction,
+//! messages between them are authenticated with HMAC-SHA512 keyed with `K_ij` and secret values
+//! (e.g. dealings of the key generation) are encrypted with XChaCha20-Poly1305 keyed with a key derived
+//! from `K_ij`, each with a fresh random nonce and the context of the value as associated data.
+//!
+//! The channel key is the same in both directions, so authenticated messages must name their sender.
+use anyhow::{anyhow, Error};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use halo2_axiom::halo2curves::ff::Field;
+use hmac::{Hmac, Mac};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha512};
+
+use crate::curves::FingerprintCurve;
+
+type HmacSha512 = Hmac<Sha512>;
+
+// Domain separation of the channel key derivation
+const CHANNEL_DOMAIN: &[u8] = b"PSO_TX_FINGERPRINT_AGENT_CHANNEL_V1";
+
+// Labels of the keys derived from the channel key
+const AUTHENTICATION_LABEL: &[u8] = b"authentication";
+const ENCRYPTION_LABEL: &[u8] = b"encryption";
+
+/// Length of the nonce of encrypted values, long enough to be picked at random
+pub const NONCE_LENGTH: usize = 24;
+
+/// Identity key `x_i` of the agent together with its public identity `[x_i] G`
+#[derive(Clone)]
+pub struct AgentIdentity<G: FingerprintCurve> {
+    secret: G::ScalarExt,
+    public: G,
+}
+
+impl<G: FingerprintCurve> AgentIdentity<G> {
+    pub fn new(secret: G::ScalarExt) -> Result<Self, Error> {
+        if bool::from(secret.is_zero()) {
+            return Err(anyhow!("Identity key must not be zero"));
+        }
+
+        Ok(Self {
+            secret,
+            public: G::generator() * secret,
+        })
+    }
+
+    /// Random identity key of a new agent
+    pub fn generate() -> Self {
+        let secret = G::ScalarExt::random(OsRng);
+
+        Self {
+            secret,
+            public: G::generator() * secret,
+        }
+    }
+
+    pub fn secret(&self) -> &G::ScalarExt {
+        &self.secret
+    }
+
+    /// Public identity `[x_i] G` distributed to other agents
+    pub fn public(&self) -> G {
+        self.public
+    }
+
+    /// Channel with the agent of the public identity `peer`
+    pub fn channel(&self, peer: &G) -> Result<PeerChannel, Error> {
+        if bool::from(peer.is_identity()) || *peer == self.public {
+            return Err(anyhow!("Invalid public identity of the peer"));
+        }
+
+        // Both agents hash the public identities in the same order
+        let own = self.public.to_bytes();
+        let peer_bytes = peer.to_bytes();
+        let (first, second) = if own.as_ref() <= peer_bytes.as_ref() {
+            (own.as_ref(), peer_bytes.as_ref())
+        } else {
+            (peer_bytes.as_ref(), own.as_ref())
+        };
+
+        let mut hasher = Sha512::new();
+        hasher.update(CHANNEL_DOMAIN);
+        hasher.update(first);
+        hasher.update(second);
+        hasher.update((*peer * self.secret).to_bytes());
+
+        Ok(PeerChannel {
+            key: hasher.finalize().into(),
+        })
+    }
+}
+
+// The identity key is a secret, it is never printed
+impl<G: FingerprintCurve> std::fmt::Debug for AgentIdentity<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentIdentity")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Value encrypted within the channel, the ciphertext ends with the Poly1305 tag
+#[derive(Debug, Clone)]
+pub struct Encrypted {
+    pub nonce: [u8; NONCE_LENGTH],
+    pub ciphertext: Vec<u8>,
+}
+
+/// Symmetric channel between two agents keyed with their channel key `K_ij`
+#[derive(Clone)]
+pub struct PeerChannel {
+    key: [u8; 64],
+}
+
+impl PeerChannel {
+    /// Authentication tag of the message consisting of `fields`
+    pub fn authenticate<T: AsRef<[u8]>>(&self, fields: &[T]) -> Vec<u8> {
+        self.mac(AUTHENTICATION_LABEL, fields)
+            .finalize()
+            .into_bytes()
+            .to_vec()
+    }
+
+    /// Check the authentication `tag` of the message consisting of `fields` in constant time
+    pub fn verify<T: AsRef<[u8]>>(&self, fields: &[T], tag: &[u8]) -> bool {
+        self.mac(AUTHENTICATION_LABEL, fields)
+            .verify_slice(tag)
+            .is_ok()
+    }
+
+    /// Encrypt the `value` with a fresh random nonce, bound to the `context` fields (e.g. the ceremony,
+    /// sender and recipient) which must be the same for the decryption
+    pub fn encrypt<T: AsRef<[u8]>>(&self, context: &[T], value: &[u8]) -> Result<Encrypted, Error> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: value,
+                    aad: &associated_data(context),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to encrypt the value"))?;
+
+        Ok(Encrypted { nonce, ciphertext })
+    }
+
+    /// Decrypt the `ciphertext` encrypted with the `nonce` and the `context`, fails when it was
+    /// encrypted in another channel or context, or altered
+    pub fn decrypt<T: AsRef<[u8]>>(
+        &self,
+        context: &[T],
+        nonce: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        if nonce.len() != NONCE_LENGTH {
+            return Err(anyhow!(
+                "Nonce must be {} bytes long, got {}",
+                NONCE_LENGTH,
+                nonce.len()
+            ));
+        }
+
+        self.cipher()
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &associated_data(context),
+                },
+            )
+            .map_err(|_| anyhow!("Encrypted value is not authentic"))
+    }
+
+    // Encryption key derived from the channel key, independent of the authentication
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        let key = self
+            .mac(ENCRYPTION_LABEL, &[] as &[&[u8]])
+            .finalize()
+            .into_bytes();
+
+        XChaCha20Poly1305::new_from_slice(&key[..32])
+            .unwrap_or_else(|_| unreachable!("XChaCha20-Poly1305 key is 32 bytes long"))
+    }
+
+    // Fields are length-prefixed, so different splits of the same bytes are different messages
+    fn mac<T: AsRef<[u8]>>(&self, label: &[u8], fields: &[T]) -> HmacSha512 {
+        let mut mac = <HmacSha512 as Mac>::new_from_slice(&self.key)
+            .unwrap_or_else(|_| unreachable!("HMAC accepts keys of any length"));
+
+        mac.update(&(label.len() as u64).to_le_bytes());
+        mac.update(label);
+        for field in fields {
+            let field = field.as_ref();
+            mac.update(&(field.len() as u64).to_le_bytes());
+            mac.update(field);
+        }
+
+        mac
+    }
+}
+
+// Context fields are length-prefixed like the authenticated ones
+fn associated_data<T: AsRef<[u8]>>(context: &[T]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for field in context {
+        let field = field.as_ref();
+        data.extend_from_slice(&(field.len() as u64).to_le_bytes());
+        data.extend_from_slice(field);
+    }
+
+    data
+}
+
+// The channel key is a secret, it is never printed
+impl std::fmt::Debug for PeerChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PeerChannel(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_axiom::halo2curves::bls12_381;
+    use halo2_axiom::halo2curves::bn256::G1;
+
+    #[test]
+    fn test_channel_key_agreement() -> Result<(), Error> {
+        check_channel_key_agreement::<G1>()?;
+        check_channel_key_agreement::<bls12_381::G1>()
+    }
+
+    fn check_channel_key_agreement<G: FingerprintCurve>() -> Result<(), Error> {
+        let alice = AgentIdentity::<G>::generate();
+        let bob = AgentIdentity::<G>::generate();
+        let mallory = AgentIdentity::<G>::generate();
+
+        let alice_bob = alice.channel(&bob.public())?;
+        let bob_alice = bob.channel(&alice.public())?;
+        let mallory_bob = mallory.channel(&bob.public())?;
+
+        // Both sides agree on the key, nobody else does
+        let message = ["dealer", "1"];
+        let tag = alice_bob.authenticate(&message);
+        assert!(bob_alice.verify(&message, &tag));
+        assert!(!bob_alice.verify(&["dealer", "2"], &tag));
+        assert!(!bob_alice.verify(&["dealer1"], &tag));
+        assert!(!bob_alice.verify(&message, &mallory_bob.authenticate(&message)));
+
+        // Encrypted values are only readable within the channel and the context
+        let context = ["share", "1", "2"];
+        let value = [7u8; 100];
+        let encrypted = alice_bob.encrypt(&context, &value)?;
+        assert_ne!(encrypted.ciphertext[..value.len()], value);
+        assert_eq!(
+            bob_alice.decrypt(&context, &encrypted.nonce, &encrypted.ciphertext)?,
+            value.to_vec()
+        );
+        assert!(mallory_bob
+            .decrypt(&context, &encrypted.nonce, &encrypted.ciphertext)
+            .is_err());
+        assert!(bob_alice
+            .decrypt(
+                &["share", "1", "3"],
+                &encrypted.nonce,
+                &encrypted.ciphertext
+            )
+            .is_err());
+        assert!(bob_alice
+            .decrypt(&context, &encrypted.nonce[..12], &encrypted.ciphertext)
+            .is_err());
+
+        // Altered ciphertexts are rejected
+        let mut altered = encrypted.ciphertext.clone();
+        altered[0] ^= 1;
+        assert!(bob_alice
+            .decrypt(&context, &encrypted.nonce, &altered)
+            .is_err());
+
+        // Every encryption uses a new nonce
+        let again = alice_bob.encrypt(&context, &value)?;
+        assert_ne!(again.nonce, encrypted.nonce);
+        assert_ne!(again.ciphertext, encrypted.ciphertext);
+
+        // Channel with itself or the identity point is refused
+        assert!(alice.channel(&alice.public()).is_err());
+        assert!(alice.channel(&G::identity()).is_err());
+        assert!(AgentIdentity::<G>::new(G::ScalarExt::ZERO).is_err());
+
+        Ok(())
+    }
+}
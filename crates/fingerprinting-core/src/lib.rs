@@ -1,20 +1,34 @@
 mod components;
+pub mod curves;
+pub mod dkg;
+pub mod dleq;
+pub mod identity;
 mod protocols;
+pub mod resharing;
+pub mod schema;
 pub mod secret_sharing;
+pub mod tenant;
+pub mod vectors;
+pub mod verification;
 
-use crate::components::{DateTimeRaw, ScalarComponent, SqueezeComponent};
+use crate::components::{DateTimeRaw, ScalarComponent};
+use crate::curves::{decode_point, FingerprintCurve};
 pub use crate::protocols::{
-    AgentsTopology, CollaborativeProtocol, FingerprintProtocol, NaiveProtocol,
+    AgentsTopology, BlindedEvaluation, BlindedProtocol, CollaborativeProtocol, FingerprintProtocol,
+    NaiveProtocol, PeerSelection, TenantEvaluation, TenantEvaluator,
 };
+use crate::schema::{FingerprintSchema, SchemaComponent};
+use crate::secret_sharing::ShareCommitments;
 use anyhow::{anyhow, Error};
 use bytes::{BufMut, Bytes, BytesMut};
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use components::{
     AmountComponent, BankIdentifierComponent, CurrencyComponent, DateTimeComponent,
-    FingerprintComponent,
+    DirectionComponent, FingerprintComponent, IbanComponent, ReferenceComponent,
 };
-use fingerprinting_types::RawTransaction;
-use halo2_axiom::halo2curves::bn256::{Fr, G1};
+use fingerprinting_types::{Direction, RawTransaction};
+use halo2_axiom::halo2curves::bls12_381;
+use halo2_axiom::halo2curves::bn256::{Fr, G1, G2};
 use halo2_axiom::halo2curves::ff::PrimeField as PF;
 use halo2_axiom::halo2curves::group::GroupEncoding;
 use pso_poseidon::{Poseidon, PoseidonHasher};
@@ -27,8 +41,59 @@ pub(crate) static EPOCH: NaiveDateTime = NaiveDateTime::new(
     NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
 );
 
+/// Default domain separation tag of the hash to curve
 pub const HASH_TO_CURVE_PREFIX: &str = "TX_FINGERPRINT";
 
+/// Maximum length of the domain separation tag, the hash to curve appends its own suffix to it
+pub const MAX_DST_LENGTH: usize = 128;
+
+///
+/// Domain separation tag of the hash to curve. Networks with different tags compute unrelated fingerprints
+/// even with the same secret, so the tag scopes the fingerprints of a deployment.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DomainSeparationTag(String);
+
+impl DomainSeparationTag {
+    /// Tag of printable ASCII characters, at most `MAX_DST_LENGTH` of them
+    pub fn new(tag: &str) -> Result<Self, Error> {
+        if tag.is_empty() || tag.len() > MAX_DST_LENGTH {
+            return Err(anyhow!(
+                "Domain separation tag must be 1 to {} characters long, got {}",
+                MAX_DST_LENGTH,
+                tag.len()
+            ));
+        }
+
+        if !tag.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(anyhow!(
+                "Domain separation tag {:?} must consist of printable ASCII characters without spaces",
+                tag
+            ));
+        }
+
+        Ok(Self(tag.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for DomainSeparationTag {
+    fn default() -> Self {
+        Self(HASH_TO_CURVE_PREFIX.to_string())
+    }
+}
+
+impl std::fmt::Display for DomainSeparationTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Maximum number of days between the value date and the date of the transaction
+pub const MAX_VALUE_DATE_DISTANCE_DAYS: i64 = 14;
+
 pub trait HashSqueeze<F: PF> {
     fn squeeze(&self) -> Result<F, Error>;
 }
@@ -61,16 +126,30 @@ impl HashSqueeze<Fr> for G1 {
     }
 }
 
+impl HashSqueeze<Fr> for bls12_381::G1 {
+    fn squeeze(&self) -> Result<Fr, Error> {
+        // 48 bytes of the compressed point don't fit into 2 limbs, they are squeezed as serialized data
+        Bytes::copy_from_slice(self.to_bytes().as_ref()).squeeze()
+    }
+}
+
+///
+/// Size of the limbs the serialized data of `len` bytes is squeezed in. Four limbs, unless they don't fit
+/// into Fr, then as many 31 bytes limbs as needed. Each limb is read as a little-endian scalar and the limbs
+/// are chained with `Poseidon(limb, last_hash)` starting from zero.
+pub fn squeeze_limb_size(len: usize) -> usize {
+    len.div_ceil(4).clamp(1, 31)
+}
+
 impl HashSqueeze<Fr> for Bytes {
     fn squeeze(&self) -> Result<Fr, Error> {
-        // TODO make more generic
         let mut poseidon = Poseidon::<Fr>::new_circom(2)?;
-        let limb_size = self.len() / 4;
+        let limb_size = squeeze_limb_size(self.len());
 
         let mut limbs = Vec::with_capacity(4);
-        for offset in (0..self.len()).step_by(limb_size) {
+        for chunk in self.chunks(limb_size) {
             let mut buffer_32 = [0u8; 32];
-            buffer_32[0..limb_size].copy_from_slice(&self[offset..offset + limb_size]);
+            buffer_32[0..chunk.len()].copy_from_slice(chunk);
 
             limbs.push(Fr::from_bytes(&buffer_32).unwrap_or(Fr::zero()));
         }
@@ -83,16 +162,59 @@ impl HashSqueeze<Fr> for Bytes {
     }
 }
 
+/// Verifiable neighbour fingerprints `(offset, (fingerprint, point))` ordered by the offset
+pub type VerifiableNeighbours<F, Point> = Vec<(i64, (F, Point))>;
+
 pub trait Fingerprint<F: PF, P: FingerprintProtocol<F>> {
     /// perform Fingerprint computation
     fn complete_fingerprint(
         &self,
         via_protocol: &P,
     ) -> impl std::future::Future<Output = Result<F, Error>> + Send;
+    /// perform Fingerprint computation keeping the point the date time component is squeezed from,
+    /// it allows to verify the fingerprint against the group key
+    fn complete_verifiable_fingerprint(
+        &self,
+        via_protocol: &P,
+    ) -> impl std::future::Future<Output = Result<(F, P::Point), Error>> + Send;
     fn datetime_fingerprint(
         &self,
         via_protocol: &P,
     ) -> impl std::future::Future<Output = Result<F, Error>> + Send;
+    /// perform Fingerprint computation for the batch with one protocol round,
+    /// results are in the order of `batch`
+    fn complete_fingerprints(
+        batch: &[Self],
+        via_protocol: &P,
+    ) -> impl std::future::Future<Output = Vec<Result<F, Error>>> + Send
+    where
+        Self: Sized;
+    /// perform verifiable Fingerprint computation for the batch with one protocol round,
+    /// results are in the order of `batch`
+    fn complete_verifiable_fingerprints(
+        batch: &[Self],
+        via_protocol: &P,
+    ) -> impl std::future::Future<Output = Vec<Result<(F, P::Point), Error>>> + Send
+    where
+        Self: Sized;
+
+    /// perform Fingerprint computation for the date time bucket of the transaction and `distance` buckets
+    /// before and after it with one protocol round, results are `(offset, fingerprint)` ordered by the offset.
+    /// Transactions recorded with slightly different date times match on one of the neighbours.
+    /// Buckets before the Epoch are skipped.
+    fn complete_neighbour_fingerprints(
+        &self,
+        via_protocol: &P,
+        distance: u32,
+    ) -> impl std::future::Future<Output = Result<Vec<(i64, F)>, Error>> + Send;
+    /// perform verifiable Fingerprint computation for the date time bucket of the transaction and `distance`
+    /// buckets before and after it with one protocol round, results are `(offset, (fingerprint, point))`
+    /// ordered by the offset. Buckets before the Epoch are skipped.
+    fn complete_verifiable_neighbour_fingerprints(
+        &self,
+        via_protocol: &P,
+        distance: u32,
+    ) -> impl std::future::Future<Output = Result<VerifiableNeighbours<F, P::Point>, Error>> + Send;
 
     fn fingerprint(&self, date_time: F, _: PhantomData<P>) -> Result<F, Error>;
 }
@@ -113,38 +235,127 @@ impl<P: FingerprintProtocol<Fr> + Sync> Fingerprint<Fr, P> for TransactionFinger
         self.fingerprint(date_time, PhantomData::<P>)
     }
 
+    async fn complete_verifiable_fingerprint(
+        &self,
+        via_protocol: &P,
+    ) -> Result<(Fr, P::Point), Error> {
+        let squeezed = self.protocol_input(via_protocol, 0)?;
+        let point = via_protocol.process_point(squeezed).await?;
+
+        let fingerprint = self.fingerprint(point.squeeze()?, PhantomData::<P>)?;
+
+        Ok((fingerprint, point))
+    }
+
     async fn datetime_fingerprint(&self, via_protocol: &P) -> Result<Fr, Error> {
-        let date_time = &self.date_time;
-        let squeezed = date_time.squeeze()?;
+        let squeezed = self.protocol_input(via_protocol, 0)?;
 
         via_protocol.process(squeezed).await
     }
 
-    fn fingerprint(&self, date_time: Fr, _: PhantomData<P>) -> Result<Fr, Error> {
-        let fingerprint_size = TransactionFingerprintData::<Fr>::fingerprint_size();
-        let buffer = BytesMut::with_capacity(fingerprint_size);
-        let mut writer = buffer.writer();
-        writer.write_all(&[0xFF, 0xFE, 0xED, 0xDD, 0xCC, 0x00, 0xDD, 0xEE])?; // Prefix for serialization
+    async fn complete_fingerprints(batch: &[Self], via_protocol: &P) -> Vec<Result<Fr, Error>> {
+        let squeezed = batch
+            .iter()
+            .map(|tx| tx.protocol_input(via_protocol, 0))
+            .collect::<Vec<_>>();
 
-        let date_time = ScalarComponent::<Fr, 32>::new(date_time);
-        let bic = &self.bic;
-        let amount = &self.amount;
-        let currency = &self.currency;
+        let values = squeezed
+            .iter()
+            .filter_map(|value| value.as_ref().ok().copied())
+            .collect();
+        let mut processed = via_protocol.process_batch(values).await.into_iter();
+
+        batch
+            .iter()
+            .zip(squeezed)
+            .map(|(tx, squeezed)| {
+                squeezed?;
+                let date_time = processed
+                    .next()
+                    .ok_or(anyhow!("Protocol returned less results than requested"))??;
+
+                tx.fingerprint(date_time, PhantomData::<P>)
+            })
+            .collect()
+    }
 
-        bic.serialize(&mut writer)?;
-        amount.serialize(&mut writer)?;
-        currency.serialize(&mut writer)?;
-        date_time.serialize(&mut writer)?;
+    async fn complete_verifiable_fingerprints(
+        batch: &[Self],
+        via_protocol: &P,
+    ) -> Vec<Result<(Fr, P::Point), Error>> {
+        let squeezed = batch
+            .iter()
+            .map(|tx| tx.protocol_input(via_protocol, 0))
+            .collect::<Vec<_>>();
+
+        let values = squeezed
+            .iter()
+            .filter_map(|value| value.as_ref().ok().copied())
+            .collect();
+        let mut processed = via_protocol.process_points_batch(values).await.into_iter();
+
+        batch
+            .iter()
+            .zip(squeezed)
+            .map(|(tx, squeezed)| {
+                squeezed?;
+                let point = processed
+                    .next()
+                    .ok_or(anyhow!("Protocol returned less results than requested"))??;
+                let fingerprint = tx.fingerprint(point.squeeze()?, PhantomData::<P>)?;
+
+                Ok((fingerprint, point))
+            })
+            .collect()
+    }
 
-        let buffer = writer.into_inner().freeze();
-        let fingerprint = buffer.squeeze()?;
+    async fn complete_neighbour_fingerprints(
+        &self,
+        via_protocol: &P,
+        distance: u32,
+    ) -> Result<Vec<(i64, Fr)>, Error> {
+        let (offsets, values) = self.neighbour_inputs(via_protocol, distance)?;
 
-        log::info!(
-            "Transaction fingerprint generated successfully: {}",
-            fingerprint.compact()
-        );
+        let processed = via_protocol.process_batch(values).await;
+        if processed.len() != offsets.len() {
+            return Err(anyhow!("Protocol returned less results than requested"));
+        }
 
-        Ok(fingerprint)
+        offsets
+            .into_iter()
+            .zip(processed)
+            .map(|(offset, date_time)| {
+                Ok((offset, self.fingerprint(date_time?, PhantomData::<P>)?))
+            })
+            .collect()
+    }
+
+    async fn complete_verifiable_neighbour_fingerprints(
+        &self,
+        via_protocol: &P,
+        distance: u32,
+    ) -> Result<VerifiableNeighbours<Fr, P::Point>, Error> {
+        let (offsets, values) = self.neighbour_inputs(via_protocol, distance)?;
+
+        let processed = via_protocol.process_points_batch(values).await;
+        if processed.len() != offsets.len() {
+            return Err(anyhow!("Protocol returned less results than requested"));
+        }
+
+        offsets
+            .into_iter()
+            .zip(processed)
+            .map(|(offset, point)| {
+                let point = point?;
+                let fingerprint = self.fingerprint(point.squeeze()?, PhantomData::<P>)?;
+
+                Ok((offset, (fingerprint, point)))
+            })
+            .collect()
+    }
+
+    fn fingerprint(&self, date_time: Fr, _: PhantomData<P>) -> Result<Fr, Error> {
+        self.fingerprint_with_date_time(date_time)
     }
 }
 
@@ -161,19 +372,118 @@ impl Compact for Bytes {
 }
 
 impl Compact for Fr {
+    fn compact(&self) -> String {
+        compact_scalar(self)
+    }
+
+    fn unwrap(compacted: &str) -> Result<Self, Error> {
+        unwrap_scalar(compacted)
+    }
+}
+
+impl Compact for bls12_381::Fr {
+    fn compact(&self) -> String {
+        compact_scalar(self)
+    }
+
+    fn unwrap(compacted: &str) -> Result<Self, Error> {
+        unwrap_scalar(compacted)
+    }
+}
+
+fn compact_scalar<F: PF>(scalar: &F) -> String {
+    bs58::encode(scalar.to_repr().as_ref()).into_string()
+}
+
+fn unwrap_scalar<F: PF>(compacted: &str) -> Result<F, Error> {
+    let bytes = bs58::decode(compacted).into_vec()?;
+    let mut repr = F::Repr::default();
+
+    let fixed_bytes = bytes.get(..repr.as_ref().len()).ok_or(anyhow!(
+        "failed to decode Fr from compacted string, given array is less than {} bytes long",
+        repr.as_ref().len()
+    ))?;
+    repr.as_mut().copy_from_slice(fixed_bytes);
+
+    F::from_repr(repr).into_option().ok_or(anyhow!(
+        "failed to decode Fr from compacted string, value does not represent Fr"
+    ))
+}
+
+impl<G: FingerprintCurve> Compact for G {
+    fn compact(&self) -> String {
+        bs58::encode(&self.to_bytes()).into_string()
+    }
+
+    fn unwrap(compacted: &str) -> Result<Self, Error> {
+        let bytes = bs58::decode(compacted).into_vec()?;
+
+        decode_point(&bytes).map_err(|e| {
+            anyhow!(
+                "failed to decode G1 of {} from compacted string, {}",
+                G::CURVE.name(),
+                e
+            )
+        })
+    }
+}
+
+impl Compact for G2 {
+    fn compact(&self) -> String {
+        bs58::encode(&self.to_bytes()).into_string()
+    }
+
+    fn unwrap(compacted: &str) -> Result<Self, Error> {
+        let bytes = bs58::decode(compacted).into_vec()?;
+
+        verification::decode_g2::<G1>(&bytes)
+    }
+}
+
+impl Compact for bls12_381::G2 {
     fn compact(&self) -> String {
         bs58::encode(&self.to_bytes()).into_string()
     }
 
     fn unwrap(compacted: &str) -> Result<Self, Error> {
         let bytes = bs58::decode(compacted).into_vec()?;
-        let fixed_bytes = bytes.first_chunk::<32>().ok_or(anyhow!(
-            "failed to decode Fr from compacted string, given array is less than 32 bytes long"
-        ))?;
 
-        Fr::from_bytes(fixed_bytes).into_option().ok_or(anyhow!(
-            "failed to decode Fr from compacted string, value does not represent Fr"
-        ))
+        verification::decode_g2::<bls12_381::G1>(&bytes)
+    }
+}
+
+impl<G: FingerprintCurve> Compact for ShareCommitments<G> {
+    fn compact(&self) -> String {
+        let bytes = self
+            .commitments()
+            .iter()
+            .flat_map(|commitment| commitment.to_bytes().as_ref().to_vec())
+            .collect::<Vec<u8>>();
+
+        bs58::encode(&bytes).into_string()
+    }
+
+    fn unwrap(compacted: &str) -> Result<Self, Error> {
+        let bytes = bs58::decode(compacted).into_vec()?;
+        let point_size = G::Repr::default().as_ref().len();
+
+        if bytes.is_empty() || bytes.len() % point_size != 0 {
+            return Err(anyhow!(
+                "failed to decode commitments from compacted string, expected multiple of {} bytes",
+                point_size
+            ));
+        }
+
+        let commitments = bytes
+            .chunks(point_size)
+            .map(|chunk| {
+                decode_point(chunk).map_err(|e| {
+                    anyhow!("failed to decode commitments from compacted string, {}", e)
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(ShareCommitments::new(commitments))
     }
 }
 
@@ -183,16 +493,18 @@ pub struct TransactionFingerprintData<F> {
     amount: AmountComponent,
     currency: CurrencyComponent,
     date_time: DateTimeComponent,
+    iban: IbanComponent,
+    reference: ReferenceComponent,
+    direction: DirectionComponent,
 
+    schema: FingerprintSchema,
     _p: PhantomData<F>,
 }
 
 impl<F> TransactionFingerprintData<F> {
+    /// Size of the data serialized with the default schema
     pub fn fingerprint_size() -> usize {
-        8 + BankIdentifierComponent::size()
-            + AmountComponent::size()
-            + CurrencyComponent::size()
-            + DateTimeComponent::size()
+        FingerprintSchema::default().size()
     }
 }
 impl<F: PF> TransactionFingerprintData<F> {
@@ -207,10 +519,24 @@ impl<F: PF> TransactionFingerprintData<F> {
             amount,
             currency,
             date_time,
+            iban: IbanComponent::new(None),
+            reference: ReferenceComponent::new(None),
+            direction: DirectionComponent::new(None),
+            schema: FingerprintSchema::default(),
             _p: PhantomData,
         }
     }
 
+    /// Fingerprint the data with the `schema` instead of the default one
+    pub fn with_schema(mut self, schema: FingerprintSchema) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn schema(&self) -> &FingerprintSchema {
+        &self.schema
+    }
+
     pub fn bic(&self) -> &str {
         self.bic.raw()
     }
@@ -223,20 +549,128 @@ impl<F: PF> TransactionFingerprintData<F> {
         *self.currency.raw()
     }
 
+    pub fn iban(&self) -> Option<&str> {
+        self.iban.raw().as_deref()
+    }
+
+    /// Normalized UETR or EndToEndId
+    pub fn reference(&self) -> Option<&str> {
+        self.reference.raw().as_deref()
+    }
+
+    pub fn direction(&self) -> Option<Direction> {
+        *self.direction.raw()
+    }
+
     pub fn date_time(&self) -> &DateTime<Utc> {
         self.date_time_component().raw().date_time()
     }
 
+    /// Value date the fingerprint is bound to, the date of the transaction unless given explicitly
+    pub fn value_date(&self) -> NaiveDate {
+        self.date_time_component().raw().wwd()
+    }
+
     pub fn date_time_component(&self) -> &DateTimeComponent {
         &self.date_time
     }
 }
 
+impl TransactionFingerprintData<Fr> {
+    /// Squeezed date time bucket of the schema granularity shifted by `offset` buckets, the input of the protocol
+    pub(crate) fn squeeze_date_time(&self, offset: i64) -> Result<Fr, Error> {
+        self.date_time
+            .squeeze_bucket(self.schema.time_granularity(), offset)
+    }
+
+    /// Squeezed date time bucket processed by the protocol, which must hash with the tag of the schema
+    fn protocol_input<P: FingerprintProtocol<Fr>>(
+        &self,
+        protocol: &P,
+        offset: i64,
+    ) -> Result<Fr, Error> {
+        if protocol.dst() != self.schema.dst() {
+            return Err(anyhow!(
+                "Schema {} uses the domain separation tag {}, the protocol hashes with {}",
+                self.schema.version(),
+                self.schema.dst(),
+                protocol.dst()
+            ));
+        }
+
+        self.squeeze_date_time(offset)
+    }
+
+    /// Protocol inputs of the date time bucket of the transaction and `distance` buckets before and after it
+    /// by their offsets. The bucket of the transaction itself must be valid, only neighbours before the Epoch
+    /// are skipped, other errors are returned.
+    fn neighbour_inputs<P: FingerprintProtocol<Fr>>(
+        &self,
+        protocol: &P,
+        distance: u32,
+    ) -> Result<(Vec<i64>, Vec<Fr>), Error> {
+        let distance = i64::from(distance);
+        let granularity = self.schema.time_granularity();
+
+        let mut offsets = Vec::new();
+        let mut values = Vec::new();
+        for offset in -distance..=distance {
+            if offset != 0 && self.date_time.is_before_epoch(granularity, offset)? {
+                continue;
+            }
+
+            offsets.push(offset);
+            values.push(self.protocol_input(protocol, offset)?);
+        }
+
+        Ok((offsets, values))
+    }
+
+    ///
+    /// Fingerprint of the transaction with the date time component `date_time` already processed by the protocol
+    pub fn fingerprint_with_date_time(&self, date_time: Fr) -> Result<Fr, Error> {
+        let fingerprint = self.serialize_with_date_time(date_time)?.squeeze()?;
+
+        log::info!(
+            "Transaction fingerprint generated successfully: {}",
+            fingerprint.compact()
+        );
+
+        Ok(fingerprint)
+    }
+
+    ///
+    /// Canonical serialization squeezed into the fingerprint: the schema prefix followed by the serialized
+    /// components, the date time component `date_time` already processed by the protocol
+    pub fn serialize_with_date_time(&self, date_time: Fr) -> Result<Bytes, Error> {
+        let buffer = BytesMut::with_capacity(self.schema.size());
+        let mut writer = buffer.writer();
+        writer.write_all(self.schema.prefix())?; // Prefix for serialization
+
+        let date_time = ScalarComponent::<Fr, 32>::new(date_time);
+
+        for component in self.schema.components() {
+            match component {
+                SchemaComponent::BankIdentifier => self.bic.serialize(&mut writer)?,
+                SchemaComponent::Amount => self.amount.serialize(&mut writer)?,
+                SchemaComponent::Currency => self.currency.serialize(&mut writer)?,
+                SchemaComponent::Iban => self.iban.serialize(&mut writer)?,
+                SchemaComponent::Reference => self.reference.serialize(&mut writer)?,
+                SchemaComponent::Direction => self.direction.serialize(&mut writer)?,
+                SchemaComponent::DateTime => date_time.serialize(&mut writer)?,
+            }
+        }
+
+        Ok(writer.into_inner().freeze())
+    }
+}
+
 impl<F: PF> TryFrom<RawTransaction> for TransactionFingerprintData<F> {
     type Error = Error;
 
     fn try_from(tx: RawTransaction) -> Result<Self, Self::Error> {
         let money = tx.amount;
+        money.validate()?;
 
         // Since the currency enum is repr(u16) it's safe to cast here
         let iso_currency_code = money.currency as u16;
@@ -244,15 +678,42 @@ impl<F: PF> TryFrom<RawTransaction> for TransactionFingerprintData<F> {
         let bic = BankIdentifierComponent::new(tx.bic.to_string());
         let amount = AmountComponent::new((money.amount_base, money.amount_atto));
         let currency = CurrencyComponent::new(iso_currency_code);
-
-        // We are not using WWD anymore
-        let transaction_date = tx.date_time.date_naive();
-
-        let dt_raw_data = DateTimeRaw::new(
-            tx.date_time,
-            transaction_date,
-            (money.amount_base, money.amount_atto),
+        let iban = IbanComponent::new(
+            tx.iban
+                .as_deref()
+                .map(IbanComponent::normalize)
+                .transpose()?,
+        );
+        let reference = ReferenceComponent::new(
+            tx.reference
+                .as_deref()
+                .map(ReferenceComponent::normalize)
+                .transpose()?
+                .flatten(),
         );
+        let direction = DirectionComponent::new(tx.direction);
+
+        // World Wide Date is the value date when given, otherwise the date of the transaction
+        let amount_raw = (money.amount_base, money.amount_atto);
+        let dt_raw_data = match tx.value_date {
+            Some(value_date) => {
+                let distance = value_date
+                    .signed_duration_since(tx.date_time.date_naive())
+                    .num_days();
+
+                if distance.abs() > MAX_VALUE_DATE_DISTANCE_DAYS {
+                    return Err(anyhow!(
+                        "Value date {} is more than {} days away from the transaction date time {}",
+                        value_date,
+                        MAX_VALUE_DATE_DISTANCE_DAYS,
+                        tx.date_time
+                    ));
+                }
+
+                DateTimeRaw::new(tx.date_time, value_date, amount_raw)
+            }
+            None => DateTimeRaw::from_date_time(tx.date_time, amount_raw),
+        };
 
         let date_time = DateTimeComponent::new(dt_raw_data);
 
@@ -261,6 +722,10 @@ impl<F: PF> TryFrom<RawTransaction> for TransactionFingerprintData<F> {
             amount,
             currency,
             date_time,
+            iban,
+            reference,
+            direction,
+            schema: FingerprintSchema::default(),
             _p: Default::default(),
         })
     }
@@ -282,10 +747,12 @@ mod tests {
     use std::cmp::PartialEq;
 
     use crate::protocols::NaiveProtocol;
+    use crate::schema::TimeGranularity;
+    use crate::secret_sharing::SecretSharing;
     use chrono::{TimeZone, Utc};
-    use fingerprinting_types::currencies::Currency;
-    use fingerprinting_types::{MoneyBuilder, RawTransactionBuilder};
+    use fingerprinting_types::test_utils::{euros, sample_transaction};
     use halo2_axiom::arithmetic::Field;
+    use halo2_axiom::halo2curves::group::Group;
     use rand_core::OsRng;
 
     impl PartialEq for &TransactionFingerprintData<Fr> {
@@ -302,16 +769,12 @@ mod tests {
         let mut rng = rand::rng();
 
         // Init naive protocol for testing
-        let protocol = NaiveProtocol::new(Fr::from(42));
+        let protocol = NaiveProtocol::<G1>::new(Fr::from(42));
 
         let mut tx_fingerprint_set = Vec::new();
         let mut tx_data_set = Vec::new();
 
         let n = 100usize;
-        println!("Phase 1 (Generate Test Data): {}", Utc::now());
-
-        let mut money_builder = MoneyBuilder::default();
-        let money_builder = money_builder.currency(Currency::Euro).amount_atto(0u64);
 
         for _i in 0..n {
             let tx_date = Utc
@@ -327,9 +790,8 @@ mod tests {
 
             let amount = rng.random_range(1..1000u64);
 
-            let tx: TransactionFingerprintData<Fr> = RawTransactionBuilder::default()
-                .bic("BCEELU21")
-                .amount(money_builder.amount_base(amount).build().unwrap())
+            let tx: TransactionFingerprintData<Fr> = sample_transaction()
+                .amount(euros(amount))
                 .date_time(tx_date)
                 .build()?
                 .try_into()?;
@@ -337,16 +799,21 @@ mod tests {
             tx_data_set.push(tx);
         }
 
-        println!("Phase 2 (Build Fingerprints): {}", Utc::now());
-
-        for i in 0..n {
-            let tx = &tx_data_set[i];
+        for tx in tx_data_set.iter() {
             let tx_fingerprint = tx.complete_fingerprint(&protocol).await?;
 
             tx_fingerprint_set.push(tx_fingerprint);
         }
 
-        println!("Phase 3 (Validate no Collisions): {}", Utc::now());
+        // Batch computation gives the same fingerprints
+        let batch_fingerprints =
+            TransactionFingerprintData::complete_fingerprints(&tx_data_set, &protocol).await;
+        assert_eq!(batch_fingerprints.len(), n);
+        for (batch_fingerprint, tx_fingerprint) in
+            batch_fingerprints.into_iter().zip(&tx_fingerprint_set)
+        {
+            assert_eq!(batch_fingerprint?, *tx_fingerprint);
+        }
 
         for i in 0..(n - 1) {
             for j in i..n {
@@ -354,7 +821,7 @@ mod tests {
                 let tx_f_j = tx_fingerprint_set[j];
 
                 let tx_i = &tx_data_set[i];
-                let tx_j = &tx_data_set[i];
+                let tx_j = &tx_data_set[j];
 
                 if tx_f_i == tx_f_j && tx_i != tx_j {
                     panic!("Assertion Failed: different transactions have the same fingerprint: {:?} and {:?}", tx_i, tx_j)
@@ -362,7 +829,339 @@ mod tests {
             }
         }
 
-        println!("Done: {}", Utc::now());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fingerprint_schemas() -> Result<(), Error> {
+        let protocol = NaiveProtocol::<G1>::new(Fr::from(42u64));
+        let tx: TransactionFingerprintData<Fr> = sample_transaction().build()?.try_into()?;
+
+        // Fingerprints of the first schema are the ones computed before schemas were versioned
+        let v1_fingerprint = tx.complete_fingerprint(&protocol).await?;
+        assert_eq!(
+            v1_fingerprint.compact(),
+            "7LDd3mXxkEA9L3jmS5SkhJBgUBrUkePM1oEqpLoytnUj"
+        );
+
+        // Same components with another version give another fingerprint
+        let tx = tx.with_schema(FingerprintSchema::new(
+            2,
+            FingerprintSchema::v1().components().to_vec(),
+        )?);
+        assert_ne!(tx.complete_fingerprint(&protocol).await?, v1_fingerprint);
+
+        // Any subset of components can be squeezed
+        let tx = tx.with_schema(FingerprintSchema::new(
+            3,
+            vec![SchemaComponent::BankIdentifier, SchemaComponent::DateTime],
+        )?);
+        tx.complete_fingerprint(&protocol).await?;
+
+        // IBAN distinguishes transfers between the same banks, transactions without IBAN are still fingerprinted
+        let with_iban: TransactionFingerprintData<Fr> = sample_transaction()
+            .iban("lu28 0019 4006 4475 0000")
+            .build()?
+            .try_into()?;
+        assert_eq!(with_iban.iban(), Some("LU280019400644750000"));
+        assert_eq!(
+            with_iban.complete_fingerprint(&protocol).await?,
+            v1_fingerprint
+        );
+
+        let iban_schema = FingerprintSchema::builder(4)
+            .component(SchemaComponent::Iban)
+            .component(SchemaComponent::DateTime)
+            .build()?;
+        let with_iban = with_iban.with_schema(iban_schema.clone());
+        let without_iban = tx.with_schema(iban_schema);
+        assert_ne!(
+            with_iban.complete_fingerprint(&protocol).await?,
+            without_iban.complete_fingerprint(&protocol).await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reference() -> Result<(), Error> {
+        let protocol = NaiveProtocol::<G1>::new(Fr::from(42u64));
+        let tx: TransactionFingerprintData<Fr> = sample_transaction().build()?.try_into()?;
+        let v1_fingerprint = tx.complete_fingerprint(&protocol).await?;
+
+        // Reference distinguishes transfers between the same banks, transactions without it are still fingerprinted
+        let with_reference: TransactionFingerprintData<Fr> = sample_transaction()
+            .reference("EB6305C9-1F7F-49DE-AED0-16487C27B42D")
+            .build()?
+            .try_into()?;
+        assert_eq!(
+            with_reference.reference(),
+            Some("eb6305c9-1f7f-49de-aed0-16487c27b42d")
+        );
+        assert_eq!(
+            with_reference.complete_fingerprint(&protocol).await?,
+            v1_fingerprint
+        );
+
+        let reference_schema = FingerprintSchema::builder(5)
+            .component(SchemaComponent::Reference)
+            .component(SchemaComponent::DateTime)
+            .build()?;
+        let with_reference = with_reference.with_schema(reference_schema.clone());
+        let without_reference = tx.with_schema(reference_schema.clone());
+        assert_ne!(
+            with_reference.complete_fingerprint(&protocol).await?,
+            without_reference.complete_fingerprint(&protocol).await?
+        );
+
+        // The SEPA placeholder is no reference, invalid references are rejected
+        let not_provided: TransactionFingerprintData<Fr> = sample_transaction()
+            .reference("NOTPROVIDED")
+            .build()?
+            .try_into()?;
+        assert_eq!(not_provided.reference(), None);
+        assert_eq!(
+            not_provided
+                .with_schema(reference_schema)
+                .complete_fingerprint(&protocol)
+                .await?,
+            without_reference.complete_fingerprint(&protocol).await?
+        );
+        assert!(TransactionFingerprintData::<Fr>::try_from(
+            sample_transaction().reference("INV_2025").build()?
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_neighbour_fingerprints() -> Result<(), Error> {
+        let protocol = NaiveProtocol::<G1>::new(Fr::from(42u64));
+        let schema = FingerprintSchema::builder(2)
+            .component(SchemaComponent::BankIdentifier)
+            .component(SchemaComponent::DateTime)
+            .time_granularity(TimeGranularity::Minute)
+            .build()?;
+        let transaction =
+            |date_time: DateTime<Utc>| -> Result<TransactionFingerprintData<Fr>, Error> {
+                let tx: TransactionFingerprintData<Fr> = sample_transaction()
+                    .date_time(date_time)
+                    .build()?
+                    .try_into()?;
+
+                Ok(tx.with_schema(schema.clone()))
+            };
+
+        // Same bucket, same fingerprint
+        let sent = transaction(Utc.with_ymd_and_hms(2025, 9, 16, 23, 59, 5).unwrap())?;
+        let booked = transaction(Utc.with_ymd_and_hms(2025, 9, 16, 23, 59, 58).unwrap())?;
+        assert_eq!(
+            sent.complete_fingerprint(&protocol).await?,
+            booked.complete_fingerprint(&protocol).await?
+        );
+
+        // Adjacent buckets (across midnight) match on the neighbour fingerprints
+        let received = transaction(Utc.with_ymd_and_hms(2025, 9, 17, 0, 0, 3).unwrap())?;
+        let received_fingerprint = received.complete_fingerprint(&protocol).await?;
+        assert_ne!(
+            booked.complete_fingerprint(&protocol).await?,
+            received_fingerprint
+        );
+
+        let neighbours = booked.complete_neighbour_fingerprints(&protocol, 1).await?;
+        assert_eq!(
+            neighbours
+                .iter()
+                .map(|(offset, _)| *offset)
+                .collect::<Vec<_>>(),
+            vec![-1, 0, 1]
+        );
+        assert_eq!(
+            neighbours[1].1,
+            booked.complete_fingerprint(&protocol).await?
+        );
+        assert_eq!(neighbours[2].1, received_fingerprint);
+
+        // Buckets before the Epoch (and on its day) are skipped
+        let early = transaction(Utc.with_ymd_and_hms(2025, 1, 3, 0, 0, 30).unwrap())?.with_schema(
+            FingerprintSchema::builder(3)
+                .component(SchemaComponent::DateTime)
+                .time_granularity(TimeGranularity::Day)
+                .build()?,
+        );
+        let neighbours = early.complete_neighbour_fingerprints(&protocol, 2).await?;
+        assert_eq!(
+            neighbours
+                .iter()
+                .map(|(offset, _)| *offset)
+                .collect::<Vec<_>>(),
+            vec![-1, 0, 1, 2]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_value_date() -> Result<(), Error> {
+        let protocol = NaiveProtocol::<G1>::new(Fr::from(42u64));
+        let transaction =
+            |value_date: Option<NaiveDate>| -> Result<TransactionFingerprintData<Fr>, Error> {
+                let mut tx = sample_transaction();
+                tx.date_time(Utc.with_ymd_and_hms(2025, 9, 16, 23, 59, 58).unwrap());
+                if let Some(value_date) = value_date {
+                    tx.value_date(value_date);
+                }
+
+                tx.build()?.try_into()
+            };
+        let date = |day| NaiveDate::from_ymd_opt(2025, 9, day).unwrap();
+
+        // Value date of the transaction date is the default
+        let booked = transaction(None)?;
+        assert_eq!(booked.value_date(), date(16));
+        assert_eq!(
+            booked.complete_fingerprint(&protocol).await?,
+            transaction(Some(date(16)))?
+                .complete_fingerprint(&protocol)
+                .await?
+        );
+
+        // Fingerprint is bound to the value date
+        let settled = transaction(Some(date(18)))?;
+        assert_eq!(settled.value_date(), date(18));
+        assert_ne!(
+            booked.complete_fingerprint(&protocol).await?,
+            settled.complete_fingerprint(&protocol).await?
+        );
+
+        // Value date far from the transaction is rejected
+        assert!(transaction(Some(date(1))).is_err());
+
+        // Explicit value date stays for the neighbour buckets across midnight
+        let schema = FingerprintSchema::builder(2)
+            .component(SchemaComponent::DateTime)
+            .time_granularity(TimeGranularity::Minute)
+            .build()?;
+        let settled = settled.with_schema(schema.clone());
+        let next_day: TransactionFingerprintData<Fr> = sample_transaction()
+            .date_time(Utc.with_ymd_and_hms(2025, 9, 17, 0, 0, 2).unwrap())
+            .value_date(date(18))
+            .build()?
+            .try_into()?;
+        let next_day = next_day.with_schema(schema.clone());
+
+        let neighbours = settled
+            .complete_neighbour_fingerprints(&protocol, 1)
+            .await?;
+        assert_eq!(
+            neighbours[2].1,
+            next_day.complete_fingerprint(&protocol).await?
+        );
+
+        // Value date equal to the transaction date follows the neighbour buckets like no value date
+        let booked = booked.with_schema(schema.clone());
+        let booked_explicit = transaction(Some(date(16)))?.with_schema(schema.clone());
+        assert_eq!(
+            booked_explicit
+                .complete_neighbour_fingerprints(&protocol, 1)
+                .await?,
+            booked.complete_neighbour_fingerprints(&protocol, 1).await?
+        );
+        let received: TransactionFingerprintData<Fr> = sample_transaction()
+            .date_time(Utc.with_ymd_and_hms(2025, 9, 17, 0, 0, 2).unwrap())
+            .build()?
+            .try_into()?;
+        assert_eq!(
+            booked_explicit
+                .complete_neighbour_fingerprints(&protocol, 1)
+                .await?[2]
+                .1,
+            received
+                .with_schema(schema)
+                .complete_fingerprint(&protocol)
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_direction() -> Result<(), Error> {
+        let protocol = NaiveProtocol::<G1>::new(Fr::from(42u64));
+        let transaction = |direction: Direction| -> Result<TransactionFingerprintData<Fr>, Error> {
+            sample_transaction()
+                .direction(direction)
+                .build()?
+                .try_into()
+        };
+
+        let debit = transaction(Direction::Debit)?;
+        let reversal = transaction(Direction::Credit)?;
+        assert_eq!(reversal.direction(), Some(Direction::Credit));
+
+        // Schemas without the direction don't tell a debit from its reversal
+        assert_eq!(
+            debit.complete_fingerprint(&protocol).await?,
+            reversal.complete_fingerprint(&protocol).await?
+        );
+
+        let schema = FingerprintSchema::builder(2)
+            .component(SchemaComponent::Amount)
+            .component(SchemaComponent::Direction)
+            .component(SchemaComponent::DateTime)
+            .build()?;
+        let debit = debit.with_schema(schema.clone());
+        let reversal = reversal.with_schema(schema);
+        assert_ne!(
+            debit.complete_fingerprint(&protocol).await?,
+            reversal.complete_fingerprint(&protocol).await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_domain_separation_tag() -> Result<(), Error> {
+        assert_eq!(
+            DomainSeparationTag::default().as_str(),
+            HASH_TO_CURVE_PREFIX
+        );
+        assert!(DomainSeparationTag::new("").is_err());
+        assert!(DomainSeparationTag::new("ACME PAYMENTS").is_err());
+        assert!(DomainSeparationTag::new(&"A".repeat(MAX_DST_LENGTH + 1)).is_err());
+
+        let dst = DomainSeparationTag::new("ACME_PAYMENTS_V1")?;
+        let protocol = NaiveProtocol::<G1>::new(Fr::from(42u64));
+        let scoped_protocol = NaiveProtocol::<G1>::new(Fr::from(42u64)).with_dst(dst.clone());
+        let transaction =
+            |schema: FingerprintSchema| -> Result<TransactionFingerprintData<Fr>, Error> {
+                let tx: TransactionFingerprintData<Fr> =
+                    sample_transaction().build()?.try_into()?;
+
+                Ok(tx.with_schema(schema))
+            };
+
+        // The protocol hashes with the tag of the schema only
+        let tx = transaction(FingerprintSchema::v1())?;
+        let fingerprint = tx.complete_fingerprint(&protocol).await?;
+        assert!(tx.complete_fingerprint(&scoped_protocol).await.is_err());
+
+        let scoped = transaction(FingerprintSchema::v1().with_dst(dst))?;
+        assert!(scoped.complete_fingerprint(&protocol).await.is_err());
+        assert!(
+            TransactionFingerprintData::complete_fingerprints(&[scoped], &protocol)
+                .await
+                .iter()
+                .all(Result::is_err)
+        );
+
+        // Same transaction and secret in another deployment gives an unrelated fingerprint
+        let scoped = transaction(FingerprintSchema::v1().with_dst(scoped_protocol.dst().clone()))?;
+        assert_ne!(
+            scoped.complete_fingerprint(&scoped_protocol).await?,
+            fingerprint
+        );
 
         Ok(())
     }
@@ -377,4 +1176,63 @@ mod tests {
         assert_eq!(fr, back_to_fr);
         Ok(())
     }
+
+    #[test]
+    pub fn compact_commitments_test() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let sharing = SecretSharing::generate(Fr::random(&mut rng), 3, 5);
+        let commitments = sharing.commitments::<G1>();
+        let compact_commitments = commitments.compact();
+        let back_to_commitments: ShareCommitments<G1> = Compact::unwrap(&compact_commitments)?;
+
+        assert_eq!(commitments, back_to_commitments);
+        Ok(())
+    }
+
+    #[test]
+    pub fn compact_point_test() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let point = G1::random(&mut rng);
+        let compact_point = point.compact();
+        let back_to_point: G1 = Compact::unwrap(&compact_point)?;
+
+        assert_eq!(point, back_to_point);
+        Ok(())
+    }
+
+    #[test]
+    pub fn compact_g2_point_test() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let point = G2::random(&mut rng);
+        let compact_point = point.compact();
+        let back_to_point: G2 = Compact::unwrap(&compact_point)?;
+
+        assert_eq!(point, back_to_point);
+        assert!(<G2 as Compact>::unwrap(&G2::identity().compact()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn compact_bls12_381_test() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let scalar = bls12_381::Fr::random(&mut rng);
+        let point = bls12_381::G1::random(&mut rng);
+        let point_g2 = bls12_381::G2::random(&mut rng);
+
+        assert_eq!(bls12_381::Fr::unwrap(&scalar.compact())?, scalar);
+        assert_eq!(bls12_381::G1::unwrap(&point.compact())?, point);
+        assert_eq!(bls12_381::G2::unwrap(&point_g2.compact())?, point_g2);
+
+        let sss = SecretSharing::generate(scalar, 3, 5);
+        let commitments = sss.commitments::<bls12_381::G1>();
+        assert_eq!(
+            ShareCommitments::<bls12_381::G1>::unwrap(&commitments.compact())?,
+            commitments
+        );
+
+        // Points of one curve are not points of the other
+        assert!(G1::unwrap(&point.compact()).is_err());
+        assert!(bls12_381::G1::unwrap(&G1::random(&mut rng).compact()).is_err());
+        Ok(())
+    }
 }
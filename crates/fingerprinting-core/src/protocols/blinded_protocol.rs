@@ -0,0 +1,109 @@
+use anyhow::{anyhow, Error};
+use halo2_axiom::arithmetic::Field;
+use halo2_axiom::halo2curves::bn256::{Fr, G1};
+use rand_core::OsRng;
+use std::marker::PhantomData;
+
+use crate::curves::FingerprintCurve;
+use crate::protocols::{BlindedEvaluation, FingerprintProtocol};
+use crate::DomainSeparationTag;
+
+///
+/// Client side of the oblivious evaluation: the value is hashed to curve and blinded locally,
+/// only the blinded point `[r] P` is sent to the `evaluator` and its result `[k * r] P` is unblinded locally.
+/// Fingerprints are the same as computed by the evaluator itself.
+pub struct BlindedProtocol<E: BlindedEvaluation<G>, G: FingerprintCurve = G1> {
+    evaluator: E,
+    dst: DomainSeparationTag,
+    _phantom: PhantomData<G>,
+}
+
+impl<E: BlindedEvaluation<G>, G: FingerprintCurve> BlindedProtocol<E, G> {
+    pub fn new(evaluator: E) -> Self {
+        Self {
+            evaluator,
+            dst: DomainSeparationTag::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Hash values to curve with the `dst` of the evaluating network instead of the default one
+    pub fn with_dst(mut self, dst: DomainSeparationTag) -> Self {
+        self.dst = dst;
+        self
+    }
+
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+
+    /// Hash `unblinded` values to curve and blind each with its own factor
+    fn blind(&self, unblinded: &[Fr]) -> (Vec<G::ScalarExt>, Vec<G>) {
+        let mut rng = OsRng;
+        let hasher = G::hash_to_curve(self.dst.as_str());
+
+        unblinded
+            .iter()
+            .map(|value| {
+                let blinding_factor = G::ScalarExt::random(&mut rng);
+                (blinding_factor, hasher(&value.to_bytes()) * blinding_factor)
+            })
+            .unzip()
+    }
+
+    fn unblind(blinding_factor: &G::ScalarExt, evaluated: G) -> Result<G, Error> {
+        if bool::from(evaluated.is_identity()) {
+            return Err(anyhow!("Evaluated point is identity"));
+        }
+
+        let unblinding_factor = blinding_factor.invert().unwrap();
+
+        Ok(evaluated * unblinding_factor)
+    }
+}
+
+impl<E: BlindedEvaluation<G> + Sync, G: FingerprintCurve> FingerprintProtocol<Fr>
+    for BlindedProtocol<E, G>
+{
+    type Point = G;
+
+    fn dst(&self) -> &DomainSeparationTag {
+        &self.dst
+    }
+
+    async fn process_point(&self, unblinded: Fr) -> Result<G, Error> {
+        let (blinding_factor, blinded) = {
+            let (blinding_factors, blinded) = self.blind(&[unblinded]);
+            (blinding_factors[0], blinded[0])
+        };
+
+        let evaluated = self.evaluator.evaluate(blinded).await?;
+
+        Self::unblind(&blinding_factor, evaluated)
+    }
+
+    async fn process_points_batch(&self, unblinded: Vec<Fr>) -> Vec<Result<G, Error>> {
+        let (blinding_factors, blinded) = self.blind(&unblinded);
+
+        let evaluated = self.evaluator.evaluate_batch(blinded).await;
+
+        if evaluated.len() != blinding_factors.len() {
+            return unblinded
+                .iter()
+                .map(|_| {
+                    Err(anyhow!(
+                        "Evaluator returned {} results for batch of {}",
+                        evaluated.len(),
+                        blinding_factors.len()
+                    ))
+                })
+                .collect();
+        }
+
+        blinding_factors
+            .iter()
+            .zip(evaluated)
+            .map(|(blinding_factor, evaluated)| Self::unblind(blinding_factor, evaluated?))
+            .collect()
+    }
+}
@@ -1,17 +1,23 @@
 use anyhow::{anyhow, Error};
 use halo2_axiom::arithmetic::Field;
-use halo2_axiom::halo2curves::bn256::{Fr, G1};
+use halo2_axiom::halo2curves::bn256::Fr;
 use halo2_axiom::halo2curves::ff::PrimeField as PF;
 use halo2_axiom::halo2curves::group::Group;
-use halo2_axiom::halo2curves::CurveExt;
 
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::marker::PhantomData;
 
 use futures::future::ready;
-use futures::{StreamExt, TryFutureExt};
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt, TryFutureExt};
+use std::future::Future;
+use std::time::{Duration, Instant};
 
-use crate::protocols::FingerprintProtocol;
-use crate::{Compact, HashSqueeze, HASH_TO_CURVE_PREFIX};
+use crate::curves::FingerprintCurve;
+use crate::protocols::robust_reconstruction::RobustReconstruction;
+use crate::protocols::{BlindedEvaluation, FingerprintProtocol, TenantEvaluation};
+use crate::tenant::{self, Tenant, TenantKey, TenantScope};
+use crate::{Compact, DomainSeparationTag};
 
 use crate::secret_sharing::SecretSharing;
 use rand_core::OsRng;
@@ -30,19 +36,77 @@ pub trait AgentsTopology<F: PF, G: Group<Scalar = F>> {
     }
 
     ///
-    /// Send request and wait for response from the remote `agent`
+    /// Remote agents in the order they are asked by the `agent`,
+    /// the first `threshold - 1` of them are the preferred set
+    fn select_agents(&self, agent: usize) -> Vec<usize> {
+        (1..=self.count()).filter(|i| *i != agent).collect()
+    }
+
+    ///
+    /// Delay after which the next agent is asked while the preferred set hasn't responded yet,
+    /// `None` asks all agents at once
+    fn hedge_delay(&self) -> Option<Duration> {
+        None
+    }
+
+    ///
+    /// Outcome of the request to the remote `agent`, used for the selection of agents
+    fn report(&self, _agent: usize, _latency: Duration, _success: bool) {}
+
+    ///
+    /// Responses requested beyond the threshold to check their consistency and exclude faulty agents,
+    /// `0` trusts the first threshold responses
+    fn redundancy(&self) -> usize {
+        0
+    }
+
+    ///
+    /// The remote `agent` returned a shard inconsistent with the other agents
+    fn report_faulty(&self, _agent: usize) {}
+
+    ///
+    /// Send request and wait for response from the remote `agent`,
+    /// the agent evaluates with its shard `s_i * t` of the tenant `scope`
     fn obtain_shard(
         &self,
         agent: usize,
         generation: u64,
+        scope: &TenantScope<F>,
         blinded_value: G,
     ) -> impl ::std::future::Future<Output = Result<(usize, G), Error>> + Send;
+
+    ///
+    /// Send one request for all `blinded_values` and wait for the response from the remote `agent`,
+    /// returned shards are in the order of `blinded_values`
+    fn obtain_shards(
+        &self,
+        agent: usize,
+        generation: u64,
+        scope: &TenantScope<F>,
+        blinded_values: &[G],
+    ) -> impl ::std::future::Future<Output = Result<(usize, Vec<G>), Error>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let shards =
+                futures::future::try_join_all(blinded_values.iter().map(|blinded_value| {
+                    self.obtain_shard(agent, generation, scope, *blinded_value)
+                }))
+                .await?;
+
+            Ok((agent, shards.into_iter().map(|(_, shard)| shard).collect()))
+        }
+    }
 }
 
 pub struct CollaborativeProtocol<F: PF, G: Group<Scalar = F>, T: AgentsTopology<F, G>> {
-    agent: usize,    // agent number
-    secret_shard: F, // our own secret shard
+    agent: usize,                   // agent number
+    generation: u64,                // secret generation requested by default
+    secret_shards: HashMap<u64, F>, // our own secret shards by generation
     topology: T,
+    dst: DomainSeparationTag,
+    tenant_key: Option<TenantKey>,
     _phantom: PhantomData<G>,
 }
 
@@ -50,81 +114,203 @@ impl<F: PF, G: Group<Scalar = F>, T: AgentsTopology<F, G>> CollaborativeProtocol
     pub fn new(agent_info: (usize, F), topology: T) -> Self {
         Self {
             agent: agent_info.0,
-            secret_shard: agent_info.1,
+            generation: 0,
+            secret_shards: HashMap::from([(0, agent_info.1)]),
             topology,
+            dst: DomainSeparationTag::default(),
+            tenant_key: None,
             _phantom: Default::default(),
         }
     }
+
+    /// Protocol holding secret shards of several generations (e.g. during proactive refresh),
+    /// `generation` is the one requested from other agents by default
+    pub fn with_generations(
+        agent: usize,
+        generation: u64,
+        secret_shards: HashMap<u64, F>,
+        topology: T,
+    ) -> Result<Self, Error> {
+        if !secret_shards.contains_key(&generation) {
+            return Err(anyhow!("No secret shard for generation {}", generation));
+        }
+
+        Ok(Self {
+            agent,
+            generation,
+            secret_shards,
+            topology,
+            dst: DomainSeparationTag::default(),
+            tenant_key: None,
+            _phantom: Default::default(),
+        })
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Hash values to curve with the `dst` of the network instead of the default one
+    pub fn with_dst(mut self, dst: DomainSeparationTag) -> Self {
+        self.dst = dst;
+        self
+    }
+
+    /// Evaluate tenants with the secret `k * t` derived with the tenant `key` of the network,
+    /// only the default tenant is available without it
+    pub fn with_tenant_key(mut self, key: TenantKey) -> Self {
+        self.tenant_key = Some(key);
+        self
+    }
 }
 
-impl<T: AgentsTopology<Fr, G1> + Sync> FingerprintProtocol<Fr>
-    for CollaborativeProtocol<Fr, G1, T>
-{
-    async fn process(&self, unblinded: Fr) -> Result<Fr, Error> {
-        let mut rng = OsRng;
+impl<F: PF, G: Group<Scalar = F>, T: AgentsTopology<F, G>> CollaborativeProtocol<F, G, T> {
+    /// Ask remote agents (except `excluded` ones) in the order of the topology selection until `needed` of them respond.
+    /// Failed requests and (with hedging) slow requests are backed up by the next agents.
+    async fn collect_responses<R, Fut>(
+        &self,
+        needed: usize,
+        excluded: &[usize],
+        request: &impl Fn(usize) -> Fut,
+    ) -> Vec<(usize, R)>
+    where
+        Fut: Future<Output = Result<R, Error>>,
+    {
+        let hedge_delay = self.topology.hedge_delay();
+        let mut selected = self.topology.select_agents(self.agent);
+        selected.retain(|agent| !excluded.contains(agent));
+        let mut candidates = selected.iter().copied();
 
-        log::debug!("Processing unblinded value: {}", unblinded.compact());
+        let timed_request = |agent: usize| {
+            let started = Instant::now();
+            request(agent).map(move |result| (agent, started.elapsed(), result))
+        };
 
-        let curve_point = {
-            // Reflect unblinded Fr on curve via hash_to_curve Eligator2 function
-            let hasher = G1::hash_to_curve(HASH_TO_CURVE_PREFIX);
-            hasher(&unblinded.to_bytes())
+        let mut pending = FuturesUnordered::new();
+        let preferred = if hedge_delay.is_some() {
+            needed
+        } else {
+            usize::MAX
         };
+        pending.extend(candidates.by_ref().take(preferred).map(timed_request));
 
-        // Select the blinding factor `r`
-        let blinding_factor = Fr::random(&mut rng);
+        let mut responses = Vec::with_capacity(needed);
+        let mut completed_agents = HashSet::new();
 
-        // Compute the blinded_hash
-        let blinded_hash = curve_point * blinding_factor;
+        while responses.len() < needed {
+            let completed = match hedge_delay {
+                Some(delay) if candidates.len() > 0 => {
+                    tokio::time::timeout(delay, pending.next()).await.ok()
+                }
+                _ => Some(pending.next().await),
+            };
 
-        // Collect the threshold responses from agents
-        let mut responses = futures::stream::iter(1..=self.topology.count())
-            .filter(|agent| ready(*agent != self.agent))
-            .map(|i| {
-                let agent = i;
-                self.topology
-                    .obtain_shard(i, 0, blinded_hash)
-                    .map_err(move |e| {
-                        log::error!("Error while getting shard from agent {}: {}", agent, e);
-                        e
-                    })
-                    .map_ok_or_else(|_| (0, G1::generator()), |v| v) // Todo add logging here
-            })
-            .buffer_unordered(1024) // TODO parametrize concurrency
-            .filter(|(p, _)| ready(*p > 0))
-            .take(self.topology.threshold() - 1) // Since we already have one response from self.agent
-            .collect::<Vec<(usize, G1)>>()
-            .await;
+            let Some(completed) = completed else {
+                // Preferred agents are slow, hedge with the next one
+                log::debug!("No response within {:?}, asking next agent", hedge_delay);
+                pending.extend(candidates.next().map(timed_request));
+                continue;
+            };
 
-        responses.push((self.agent, blinded_hash * self.secret_shard));
+            let Some((agent, latency, result)) = completed else {
+                // Nothing is pending, ask the next agent if any left
+                match candidates.next() {
+                    Some(agent) => pending.push(timed_request(agent)),
+                    None => break,
+                }
+                continue;
+            };
 
-        if responses.len() < self.topology.threshold() {
-            return Err(anyhow!("Not enough responses from other agents"));
+            self.topology.report(agent, latency, result.is_ok());
+            completed_agents.insert(agent);
+
+            match result {
+                Ok(response) => responses.push((agent, response)),
+                Err(e) => {
+                    log::error!("Error while getting shard from agent {}: {}", agent, e);
+                    pending.extend(candidates.next().map(timed_request));
+                }
+            }
         }
 
-        // Precompute cooperative agents indexes
-        let indices = responses.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+        // Agents outrun by the hedged requests are reported as failed, so they are asked only as fallback
+        if let Some(delay) = hedge_delay {
+            let launched = &selected[..selected.len() - candidates.len()];
+            for agent in launched.iter().filter(|a| !completed_agents.contains(a)) {
+                self.topology.report(*agent, delay, false);
+            }
+        }
 
-        log::debug!(
-            "Got {} results from other agents: {:?}",
-            indices.len(),
-            indices
-        );
+        responses
+    }
 
-        let mut y: G1 = Default::default(); // zero point
+    /// Collect responses of `threshold + redundancy` agents in addition to our own `response`
+    /// and `reconstruct` the result from them. While the responses are inconsistent,
+    /// one more agent is asked to confirm them. Agents with inconsistent responses are excluded.
+    async fn collect_consistent<R, Fut, Out>(
+        &self,
+        response: R,
+        request: impl Fn(usize) -> Fut,
+        reconstruct: impl Fn(&[(usize, R)]) -> Result<(Out, Vec<usize>), Error>,
+    ) -> Result<Out, Error>
+    where
+        Fut: Future<Output = Result<R, Error>>,
+    {
+        let needed = self.topology.threshold() + self.topology.redundancy();
 
-        // Compute blinded version of [r * k] P
-        for (i, e_i) in responses {
-            let lambda_i = self.topology.compute_coefficient(i, &indices);
+        // Since we already have one response from self.agent, it goes first
+        let mut responses = vec![(self.agent, response)];
+        responses.extend(self.collect_responses(needed - 1, &[], &request).await);
 
-            y += e_i * lambda_i;
+        if responses.len() < needed {
+            return Err(anyhow!("Not enough responses from other agents"));
         }
 
-        // Unblind
-        let unblinding_factor = blinding_factor.invert().unwrap();
-        let hash_with_secret = y * unblinding_factor; // This is [k] P
+        loop {
+            log::debug!(
+                "Got results from {} agents: {:?}",
+                responses.len(),
+                responses.iter().map(|(p, _)| *p).collect::<Vec<_>>()
+            );
+
+            match reconstruct(&responses) {
+                Ok((result, faulty)) => {
+                    for agent in faulty {
+                        log::error!(
+                            "Agent {} returned shard inconsistent with other agents, excluded",
+                            agent
+                        );
+                        self.topology.report_faulty(agent);
+                    }
+
+                    return Ok(result);
+                }
+                Err(e) => {
+                    let asked = responses.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+                    let more = self.collect_responses(1, &asked, &request).await;
+
+                    if more.is_empty() {
+                        return Err(e);
+                    }
+
+                    log::warn!("{}, asked agent {:?} to confirm", e, more[0].0);
+                    responses.extend(more);
+                }
+            }
+        }
+    }
+}
 
-        let fingerprint = hash_with_secret.squeeze();
+impl<G: FingerprintCurve, T: AgentsTopology<G::ScalarExt, G> + Sync>
+    CollaborativeProtocol<G::ScalarExt, G, T>
+{
+    /// Compute the fingerprint with the secret shards of the specific `generation`.
+    /// Since every generation shares the same secret, result does not depend on it.
+    pub async fn process_generation(&self, unblinded: Fr, generation: u64) -> Result<Fr, Error> {
+        let fingerprint = self
+            .process_point_generation(unblinded, generation)
+            .await
+            .and_then(|hash_with_secret| hash_with_secret.squeeze());
 
         if log::log_enabled!(log::Level::Debug) {
             match &fingerprint {
@@ -139,4 +325,335 @@ impl<T: AgentsTopology<Fr, G1> + Sync> FingerprintProtocol<Fr>
 
         fingerprint
     }
+
+    /// Compute the point `[k] P` with the secret shards of the specific `generation`
+    pub async fn process_point_generation(
+        &self,
+        unblinded: Fr,
+        generation: u64,
+    ) -> Result<G, Error> {
+        let mut rng = OsRng;
+
+        log::debug!(
+            "Processing unblinded value: {} (generation {})",
+            unblinded.compact(),
+            generation
+        );
+
+        let curve_point = {
+            // Reflect unblinded Fr on curve via hash_to_curve Eligator2 function
+            let hasher = G::hash_to_curve(self.dst.as_str());
+            hasher(&unblinded.to_bytes())
+        };
+
+        // Select the blinding factor `r`
+        let blinding_factor = G::ScalarExt::random(&mut rng);
+
+        // Compute the blinded_hash
+        let blinded_hash = curve_point * blinding_factor;
+
+        // Compute blinded version of [r * k] P
+        let y = self.evaluate_generation(blinded_hash, generation).await?;
+
+        // Unblind
+        let unblinding_factor = blinding_factor.invert().unwrap();
+
+        Ok(y * unblinding_factor) // This is [k] P
+    }
+
+    /// Compute `[k] B` for the `blinded` point with the secret shards of the specific `generation`
+    pub async fn evaluate_generation(&self, blinded: G, generation: u64) -> Result<G, Error> {
+        self.evaluate_generation_for(&TenantScope::default(), blinded, generation)
+            .await
+    }
+
+    /// Compute `[k * t] B` of the tenant `scope` for the `blinded` point with the secret shards of the specific `generation`
+    pub async fn evaluate_generation_for(
+        &self,
+        scope: &TenantScope<G::ScalarExt>,
+        blinded: G,
+        generation: u64,
+    ) -> Result<G, Error> {
+        let secret_shard = *self
+            .secret_shards
+            .get(&generation)
+            .ok_or(anyhow!("No secret shard for generation {}", generation))?;
+
+        // Collect the threshold responses from agents and interpolate them
+        self.collect_consistent(
+            blinded * (secret_shard * scope.scalar()),
+            |agent| {
+                self.topology
+                    .obtain_shard(agent, generation, scope, blinded)
+                    .map_ok(|(_, shard)| shard)
+            },
+            |responses| {
+                let indices = responses.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+
+                if self.topology.redundancy() > 0 {
+                    let shards = responses.iter().map(|(_, e_i)| *e_i).collect::<Vec<_>>();
+
+                    return RobustReconstruction::new(
+                        self.topology.threshold(),
+                        self.topology.redundancy(),
+                        indices,
+                    )?
+                    .reconstruct(&shards);
+                }
+
+                let mut y = G::identity(); // zero point
+
+                for (i, e_i) in responses {
+                    let lambda_i = self.topology.compute_coefficient(*i, &indices);
+
+                    y += *e_i * lambda_i;
+                }
+
+                Ok((y, Vec::new()))
+            },
+        )
+        .await
+    }
+}
+
+impl<G: FingerprintCurve, T: AgentsTopology<G::ScalarExt, G> + Sync>
+    CollaborativeProtocol<G::ScalarExt, G, T>
+{
+    /// Compute fingerprints of all `unblinded` values with one request per cooperating agent.
+    /// Results are in the order of `unblinded`, the batch fails as a whole when not enough agents respond.
+    pub async fn process_batch_generation(
+        &self,
+        unblinded: &[Fr],
+        generation: u64,
+    ) -> Result<Vec<Result<Fr, Error>>, Error> {
+        Ok(self
+            .process_points_batch_generation(unblinded, generation)
+            .await?
+            .into_iter()
+            .map(|hash_with_secret| hash_with_secret.squeeze())
+            .collect())
+    }
+
+    /// Compute points `[k] P` of all `unblinded` values with one request per cooperating agent
+    pub async fn process_points_batch_generation(
+        &self,
+        unblinded: &[Fr],
+        generation: u64,
+    ) -> Result<Vec<G>, Error> {
+        let mut rng = OsRng;
+
+        log::debug!(
+            "Processing batch of {} unblinded values (generation {})",
+            unblinded.len(),
+            generation
+        );
+
+        // Reflect unblinded values on curve and blind each with its own factor
+        let blinding_factors = unblinded
+            .iter()
+            .map(|_| G::ScalarExt::random(&mut rng))
+            .collect::<Vec<_>>();
+        let blinded_hashes = {
+            let hasher = G::hash_to_curve(self.dst.as_str());
+            unblinded
+                .iter()
+                .zip(&blinding_factors)
+                .map(|(value, blinding_factor)| hasher(&value.to_bytes()) * blinding_factor)
+                .collect::<Vec<_>>()
+        };
+
+        // Compute blinded versions of [r * k] P
+        let blinded_results = self
+            .evaluate_batch_generation(&blinded_hashes, generation)
+            .await?;
+
+        // Unblind
+        Ok(blinded_results
+            .into_iter()
+            .zip(&blinding_factors)
+            .map(|(y, blinding_factor)| y * blinding_factor.invert().unwrap())
+            .collect())
+    }
+
+    /// Compute `[k] B` for all `blinded` points with one request per cooperating agent.
+    /// Results are in the order of `blinded`, the batch fails as a whole when not enough agents respond.
+    pub async fn evaluate_batch_generation(
+        &self,
+        blinded: &[G],
+        generation: u64,
+    ) -> Result<Vec<G>, Error> {
+        self.evaluate_batch_generation_for(&TenantScope::default(), blinded, generation)
+            .await
+    }
+
+    /// Compute `[k * t] B` of the tenant `scope` for all `blinded` points with one request per cooperating agent
+    pub async fn evaluate_batch_generation_for(
+        &self,
+        scope: &TenantScope<G::ScalarExt>,
+        blinded: &[G],
+        generation: u64,
+    ) -> Result<Vec<G>, Error> {
+        let secret_shard = *self
+            .secret_shards
+            .get(&generation)
+            .ok_or(anyhow!("No secret shard for generation {}", generation))?
+            * scope.scalar();
+
+        // Collect the threshold batch responses from agents and interpolate them
+        let batch_size = blinded.len();
+        self.collect_consistent(
+            blinded
+                .iter()
+                .map(|b| *b * secret_shard)
+                .collect::<Vec<_>>(),
+            |agent| {
+                self.topology
+                    .obtain_shards(agent, generation, scope, blinded)
+                    .and_then(move |(_, shards)| {
+                        ready(if shards.len() == batch_size {
+                            Ok(shards)
+                        } else {
+                            Err(anyhow!(
+                                "Agent {} returned {} shards for batch of {}",
+                                agent,
+                                shards.len(),
+                                batch_size
+                            ))
+                        })
+                    })
+            },
+            |responses| {
+                let indices = responses.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+
+                if self.topology.redundancy() > 0 {
+                    let reconstruction = RobustReconstruction::new(
+                        self.topology.threshold(),
+                        self.topology.redundancy(),
+                        indices,
+                    )?;
+                    let mut faulty = BTreeSet::new();
+                    let mut results = Vec::with_capacity(batch_size);
+
+                    for item in 0..batch_size {
+                        let shards = responses
+                            .iter()
+                            .map(|(_, shards)| shards[item])
+                            .collect::<Vec<_>>();
+                        let (y, item_faulty) = reconstruction.reconstruct(&shards)?;
+
+                        faulty.extend(item_faulty);
+                        results.push(y);
+                    }
+
+                    return Ok((results, faulty.into_iter().collect()));
+                }
+
+                let coefficients = indices
+                    .iter()
+                    .map(|i| self.topology.compute_coefficient(*i, &indices))
+                    .collect::<Vec<_>>();
+
+                let results = (0..batch_size)
+                    .map(|item| {
+                        responses
+                            .iter()
+                            .zip(&coefficients)
+                            .fold(G::identity(), |y, ((_, shards), lambda_i)| {
+                                y + shards[item] * lambda_i
+                            })
+                    })
+                    .collect();
+
+                Ok((results, Vec::new()))
+            },
+        )
+        .await
+    }
+}
+
+impl<G: FingerprintCurve, T: AgentsTopology<G::ScalarExt, G> + Sync> FingerprintProtocol<Fr>
+    for CollaborativeProtocol<G::ScalarExt, G, T>
+{
+    type Point = G;
+
+    fn dst(&self) -> &DomainSeparationTag {
+        &self.dst
+    }
+
+    async fn process_point(&self, unblinded: Fr) -> Result<G, Error> {
+        self.process_point_generation(unblinded, self.generation)
+            .await
+    }
+
+    async fn process(&self, unblinded: Fr) -> Result<Fr, Error> {
+        self.process_generation(unblinded, self.generation).await
+    }
+
+    async fn process_points_batch(&self, unblinded: Vec<Fr>) -> Vec<Result<G, Error>> {
+        match self
+            .process_points_batch_generation(&unblinded, self.generation)
+            .await
+        {
+            Ok(points) => points.into_iter().map(Ok).collect(),
+            Err(e) => unblinded
+                .iter()
+                .map(|_| Err(anyhow!("Batch computation failed: {}", e)))
+                .collect(),
+        }
+    }
+}
+
+impl<G: FingerprintCurve, T: AgentsTopology<G::ScalarExt, G> + Sync> BlindedEvaluation<G>
+    for CollaborativeProtocol<G::ScalarExt, G, T>
+{
+    async fn evaluate(&self, blinded: G) -> Result<G, Error> {
+        self.evaluate_generation(blinded, self.generation).await
+    }
+
+    async fn evaluate_batch(&self, blinded: Vec<G>) -> Vec<Result<G, Error>> {
+        match self
+            .evaluate_batch_generation(&blinded, self.generation)
+            .await
+        {
+            Ok(evaluated) => evaluated.into_iter().map(Ok).collect(),
+            Err(e) => blinded
+                .iter()
+                .map(|_| Err(anyhow!("Batch evaluation failed: {}", e)))
+                .collect(),
+        }
+    }
+}
+
+impl<G: FingerprintCurve, T: AgentsTopology<G::ScalarExt, G> + Sync> TenantEvaluation<G>
+    for CollaborativeProtocol<G::ScalarExt, G, T>
+{
+    fn scope(&self, tenant: &Tenant) -> Result<TenantScope<G::ScalarExt>, Error> {
+        tenant::scope(self.tenant_key.as_ref(), tenant)
+    }
+
+    async fn evaluate_for(
+        &self,
+        scope: &TenantScope<G::ScalarExt>,
+        blinded: G,
+    ) -> Result<G, Error> {
+        self.evaluate_generation_for(scope, blinded, self.generation)
+            .await
+    }
+
+    async fn evaluate_batch_for(
+        &self,
+        scope: &TenantScope<G::ScalarExt>,
+        blinded: Vec<G>,
+    ) -> Vec<Result<G, Error>> {
+        match self
+            .evaluate_batch_generation_for(scope, &blinded, self.generation)
+            .await
+        {
+            Ok(evaluated) => evaluated.into_iter().map(Ok).collect(),
+            Err(e) => blinded
+                .iter()
+                .map(|_| Err(anyhow!("Batch evaluation failed: {}", e)))
+                .collect(),
+        }
+    }
 }
@@ -1,34 +1,186 @@
+mod blinded_protocol;
 mod collaborative_protocol;
 mod naive_protocol;
+mod peer_selection;
+mod robust_reconstruction;
 
 use anyhow::Error;
 use halo2_axiom::halo2curves::ff::PrimeField as PF;
+use halo2_axiom::halo2curves::group::Group;
 
+use crate::tenant::{Tenant, TenantScope};
+use crate::{DomainSeparationTag, HashSqueeze};
+
+pub use blinded_protocol::BlindedProtocol;
 pub use collaborative_protocol::AgentsTopology;
 pub use collaborative_protocol::CollaborativeProtocol;
 pub use naive_protocol::NaiveProtocol;
+pub use peer_selection::PeerSelection;
 
 pub trait FingerprintProtocol<F: PF> {
-    fn process(&self, unblinded: F)
-        -> impl ::std::future::Future<Output = Result<F, Error>> + Send;
+    /// Point `[k] H(m)` the fingerprint is squeezed from
+    type Point: HashSqueeze<F> + Send;
+
+    /// Domain separation tag the values are hashed to curve with
+    fn dst(&self) -> &DomainSeparationTag;
+
+    ///
+    /// Compute the point `[k] H(m)` for the value, it can be verified against the group public key
+    fn process_point(
+        &self,
+        unblinded: F,
+    ) -> impl ::std::future::Future<Output = Result<Self::Point, Error>> + Send;
+
+    fn process(
+        &self,
+        unblinded: F,
+    ) -> impl ::std::future::Future<Output = Result<F, Error>> + Send {
+        let point = self.process_point(unblinded);
+
+        async move { point.await?.squeeze() }
+    }
+
+    ///
+    /// Compute points of many values at once, results are in the order of `unblinded`.
+    /// Protocols with remote round trips override it to amortize them over the batch.
+    fn process_points_batch(
+        &self,
+        unblinded: Vec<F>,
+    ) -> impl ::std::future::Future<Output = Vec<Result<Self::Point, Error>>> + Send
+    where
+        Self: Sync,
+    {
+        futures::future::join_all(unblinded.into_iter().map(|value| self.process_point(value)))
+    }
+
+    ///
+    /// Process many values at once, results are in the order of `unblinded`.
+    fn process_batch(
+        &self,
+        unblinded: Vec<F>,
+    ) -> impl ::std::future::Future<Output = Vec<Result<F, Error>>> + Send
+    where
+        Self: Sync,
+    {
+        let points = self.process_points_batch(unblinded);
+
+        async move {
+            points
+                .await
+                .into_iter()
+                .map(|point| point?.squeeze())
+                .collect()
+        }
+    }
+}
+
+///
+/// Evaluation `[k] B` of the secret on the point `B` blinded by the client,
+/// the evaluator learns neither the hashed value nor the fingerprint
+pub trait BlindedEvaluation<G: Group> {
+    fn evaluate(&self, blinded: G) -> impl ::std::future::Future<Output = Result<G, Error>> + Send;
+
+    ///
+    /// Evaluate many points at once, results are in the order of `blinded`
+    fn evaluate_batch(
+        &self,
+        blinded: Vec<G>,
+    ) -> impl ::std::future::Future<Output = Vec<Result<G, Error>>> + Send
+    where
+        Self: Sync,
+    {
+        futures::future::join_all(blinded.into_iter().map(|value| self.evaluate(value)))
+    }
+}
+
+///
+/// Evaluation `[k * t] B` with the secret of the tenant, see `crate::tenant`.
+/// The default scope evaluates with the secret `k` itself.
+pub trait TenantEvaluation<G: Group> {
+    /// Scope of the `tenant`, it fails when the evaluator can't derive tenant scalars
+    fn scope(&self, tenant: &Tenant) -> Result<TenantScope<G::Scalar>, Error>;
+
+    fn evaluate_for(
+        &self,
+        scope: &TenantScope<G::Scalar>,
+        blinded: G,
+    ) -> impl ::std::future::Future<Output = Result<G, Error>> + Send;
+
+    ///
+    /// Evaluate many points of the tenant at once, results are in the order of `blinded`
+    fn evaluate_batch_for(
+        &self,
+        scope: &TenantScope<G::Scalar>,
+        blinded: Vec<G>,
+    ) -> impl ::std::future::Future<Output = Vec<Result<G, Error>>> + Send
+    where
+        Self: Sync,
+    {
+        futures::future::join_all(
+            blinded
+                .into_iter()
+                .map(|value| self.evaluate_for(scope, value)),
+        )
+    }
+}
+
+///
+/// Blinded evaluation within the tenant `scope`, e.g. to compute fingerprints of the tenant
+/// with `BlindedProtocol`
+pub struct TenantEvaluator<'a, E, G: Group> {
+    evaluator: &'a E,
+    scope: TenantScope<G::Scalar>,
+}
+
+impl<'a, E: TenantEvaluation<G>, G: Group> TenantEvaluator<'a, E, G> {
+    pub fn new(evaluator: &'a E, scope: TenantScope<G::Scalar>) -> Self {
+        Self { evaluator, scope }
+    }
+}
+
+impl<E: TenantEvaluation<G> + Sync, G: Group> BlindedEvaluation<G> for TenantEvaluator<'_, E, G> {
+    fn evaluate(&self, blinded: G) -> impl ::std::future::Future<Output = Result<G, Error>> + Send {
+        self.evaluator.evaluate_for(&self.scope, blinded)
+    }
+
+    fn evaluate_batch(
+        &self,
+        blinded: Vec<G>,
+    ) -> impl ::std::future::Future<Output = Vec<Result<G, Error>>> + Send
+    where
+        Self: Sync,
+    {
+        self.evaluator.evaluate_batch_for(&self.scope, blinded)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use halo2_axiom::halo2curves::bn256::{Fr, G1};
+    use halo2_axiom::halo2curves::bls12_381;
+    use halo2_axiom::halo2curves::bn256::{Fr, G1, G2};
     use halo2_axiom::halo2curves::ff::Field;
     use rand_core::OsRng;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     use crate::secret_sharing::SecretSharing;
+    use crate::tenant::TenantKey;
+    use crate::verification::{derive_group_key, verify_point};
+    use crate::DomainSeparationTag;
 
     use crate::protocols::AgentsTopology;
+    use crate::protocols::BlindedProtocol;
     use crate::protocols::CollaborativeProtocol;
     use crate::protocols::NaiveProtocol;
+    use crate::protocols::PeerSelection;
+    use crate::protocols::TenantEvaluator;
 
     struct LocalAgentsTopology {
         sss: SecretSharing<Fr>,
+        refreshed: Option<SecretSharing<Fr>>,
     }
 
     impl AgentsTopology<Fr, G1> for LocalAgentsTopology {
@@ -47,10 +199,143 @@ mod tests {
         async fn obtain_shard(
             &self,
             agent: usize,
-            _: u64,
+            generation: u64,
+            scope: &TenantScope<Fr>,
+            blinded_value: G1,
+        ) -> Result<(usize, G1), Error> {
+            let sss = match (generation, &self.refreshed) {
+                (0, _) => &self.sss,
+                (1, Some(refreshed)) => refreshed,
+                _ => return Err(anyhow::anyhow!("Unknown generation {}", generation)),
+            };
+
+            Ok(sss.compute_exponent(agent, blinded_value * scope.scalar()))
+        }
+    }
+
+    struct Bls12381AgentsTopology {
+        sss: SecretSharing<bls12_381::Fr>,
+    }
+
+    impl AgentsTopology<bls12_381::Fr, bls12_381::G1> for Bls12381AgentsTopology {
+        fn count(&self) -> usize {
+            10
+        }
+
+        fn threshold(&self) -> usize {
+            self.sss.threshold
+        }
+
+        fn compute_coefficient(&self, agent: usize, cooperative_agents: &[usize]) -> bls12_381::Fr {
+            SecretSharing::lagrange_coefficient(agent, cooperative_agents)
+        }
+
+        async fn obtain_shard(
+            &self,
+            agent: usize,
+            _generation: u64,
+            scope: &TenantScope<bls12_381::Fr>,
+            blinded_value: bls12_381::G1,
+        ) -> Result<(usize, bls12_381::G1), Error> {
+            Ok(self
+                .sss
+                .compute_exponent(agent, blinded_value * scope.scalar()))
+        }
+    }
+
+    struct HedgedAgentsTopology {
+        sss: SecretSharing<Fr>,
+        unresponsive: HashSet<usize>,
+        selection: PeerSelection,
+        requested: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl AgentsTopology<Fr, G1> for HedgedAgentsTopology {
+        fn count(&self) -> usize {
+            10
+        }
+
+        fn threshold(&self) -> usize {
+            self.sss.threshold
+        }
+
+        fn select_agents(&self, agent: usize) -> Vec<usize> {
+            self.selection
+                .order((1..=self.count()).filter(|i| *i != agent))
+        }
+
+        fn hedge_delay(&self) -> Option<Duration> {
+            Some(Duration::from_millis(20))
+        }
+
+        fn report(&self, agent: usize, latency: Duration, success: bool) {
+            self.selection.record(agent, latency, success);
+        }
+
+        async fn obtain_shard(
+            &self,
+            agent: usize,
+            _generation: u64,
+            scope: &TenantScope<Fr>,
             blinded_value: G1,
         ) -> Result<(usize, G1), Error> {
-            Ok(self.sss.compute_exponent(agent, blinded_value))
+            self.requested.lock().unwrap().push(agent);
+
+            if self.unresponsive.contains(&agent) {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+
+            Ok(self
+                .sss
+                .compute_exponent(agent, blinded_value * scope.scalar()))
+        }
+    }
+
+    struct FaultyAgentsTopology {
+        sss: Arc<SecretSharing<Fr>>,
+        faulty: HashSet<usize>,
+        redundancy: usize,
+        reported: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl AgentsTopology<Fr, G1> for FaultyAgentsTopology {
+        fn count(&self) -> usize {
+            6
+        }
+
+        fn threshold(&self) -> usize {
+            self.sss.threshold
+        }
+
+        fn hedge_delay(&self) -> Option<Duration> {
+            // Ask agents one by one in the order of their numbers
+            Some(Duration::from_secs(10))
+        }
+
+        fn redundancy(&self) -> usize {
+            self.redundancy
+        }
+
+        fn report_faulty(&self, agent: usize) {
+            self.reported.lock().unwrap().push(agent);
+        }
+
+        async fn obtain_shard(
+            &self,
+            agent: usize,
+            _generation: u64,
+            scope: &TenantScope<Fr>,
+            blinded_value: G1,
+        ) -> Result<(usize, G1), Error> {
+            let (agent, shard) = self
+                .sss
+                .compute_exponent(agent, blinded_value * scope.scalar());
+
+            if self.faulty.contains(&agent) {
+                return Ok((agent, shard + G1::generator()));
+            }
+
+            Ok((agent, shard))
         }
     }
 
@@ -65,10 +350,13 @@ mod tests {
         // We are the 1st agent
         let current_share = sss.get_share(1).unwrap();
 
-        let topology = LocalAgentsTopology { sss };
+        let topology = LocalAgentsTopology {
+            sss,
+            refreshed: None,
+        };
 
         let coop_protocol = CollaborativeProtocol::new((1, current_share), topology);
-        let naive_protocol = NaiveProtocol::new(secret);
+        let naive_protocol = NaiveProtocol::<G1>::new(secret);
 
         let processed = coop_protocol.process(origin).await?;
         let naive_processed = naive_protocol.process(origin).await?;
@@ -81,6 +369,389 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cooperative_protocol_generations() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+
+        // Refreshed generation shares the same secret with a new polynomial
+        let sss = SecretSharing::generate(secret, 4, 10);
+        let refreshed = SecretSharing::generate(secret, 4, 10);
+
+        let origin = Fr::from(42u64);
+        let shards = HashMap::from([
+            (0, sss.get_share(1).unwrap()),
+            (1, refreshed.get_share(1).unwrap()),
+        ]);
+
+        let topology = LocalAgentsTopology {
+            sss,
+            refreshed: Some(refreshed),
+        };
+
+        let coop_protocol = CollaborativeProtocol::with_generations(1, 1, shards, topology)?;
+        let naive_protocol = NaiveProtocol::<G1>::new(secret);
+
+        let processed = coop_protocol.process(origin).await?;
+        let previous_generation = coop_protocol.process_generation(origin, 0).await?;
+        let naive_processed = naive_protocol.process(origin).await?;
+
+        assert_eq!(coop_protocol.generation(), 1);
+        assert_eq!(processed, naive_processed);
+        assert_eq!(previous_generation, naive_processed);
+        assert!(coop_protocol.process_generation(origin, 2).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cooperative_protocol_batch() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sss = SecretSharing::generate(secret, 4, 10);
+        let current_share = sss.get_share(1).unwrap();
+
+        let topology = LocalAgentsTopology {
+            sss,
+            refreshed: None,
+        };
+
+        let coop_protocol = CollaborativeProtocol::new((1, current_share), topology);
+        let naive_protocol = NaiveProtocol::<G1>::new(secret);
+
+        let origins = (0..20u64).map(Fr::from).collect::<Vec<_>>();
+
+        let processed = coop_protocol.process_batch(origins.clone()).await;
+        let naive_processed = naive_protocol.process_batch(origins).await;
+
+        assert_eq!(processed.len(), 20);
+        for (processed, naive_processed) in processed.into_iter().zip(naive_processed) {
+            assert_eq!(processed?, naive_processed?);
+        }
+
+        assert!(coop_protocol
+            .process_batch_generation(&[Fr::one()], 1)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cooperative_protocol_verifiable_points() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sss = SecretSharing::generate(secret, 4, 10);
+        let current_share = sss.get_share(1).unwrap();
+
+        // Group key in G2 from public shares of the agents
+        let public_shares = (1..=4)
+            .map(|i| (i, G2::generator() * sss.get_share(i).unwrap()))
+            .collect::<Vec<_>>();
+        let group_key = derive_group_key(&sss.commitments::<G1>(), &public_shares)?;
+
+        let topology = LocalAgentsTopology {
+            sss,
+            refreshed: None,
+        };
+        let coop_protocol = CollaborativeProtocol::new((1, current_share), topology);
+
+        let origins = (0..5u64).map(Fr::from).collect::<Vec<_>>();
+        let points = coop_protocol.process_points_batch(origins.clone()).await;
+
+        for (origin, point) in origins.into_iter().zip(points) {
+            let point = point?;
+
+            assert!(verify_point(
+                &origin,
+                &point,
+                &group_key,
+                coop_protocol.dst()
+            ));
+            assert_eq!(point, coop_protocol.process_point(origin).await?);
+            assert_eq!(point.squeeze()?, coop_protocol.process(origin).await?);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cooperative_protocol_hedged() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sss = SecretSharing::generate(secret, 4, 10);
+        let current_share = sss.get_share(1).unwrap();
+
+        let requested = Arc::new(Mutex::new(Vec::new()));
+
+        let topology = HedgedAgentsTopology {
+            sss,
+            unresponsive: HashSet::from([2, 3]),
+            selection: PeerSelection::new(),
+            requested: Arc::clone(&requested),
+        };
+
+        let coop_protocol = CollaborativeProtocol::new((1, current_share), topology);
+        let naive_protocol = NaiveProtocol::<G1>::new(secret);
+        let origin = Fr::from(42u64);
+
+        // Unresponsive agents 2 and 3 are preferred at first and backed up by the next agents
+        let processed = coop_protocol.process(origin).await?;
+        assert_eq!(processed, naive_protocol.process(origin).await?);
+        assert_eq!(requested.lock().unwrap().len(), 5);
+
+        // Then they are asked only as a fallback
+        requested.lock().unwrap().clear();
+        let processed = coop_protocol.process(origin).await?;
+        assert_eq!(processed, naive_protocol.process(origin).await?);
+
+        let requested = requested.lock().unwrap();
+        assert_eq!(requested.len(), 3);
+        assert!(!requested.contains(&2) && !requested.contains(&3));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cooperative_protocol_robust() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sss = Arc::new(SecretSharing::generate(secret, 3, 6));
+        let current_share = sss.get_share(1).unwrap();
+        let naive_protocol = NaiveProtocol::<G1>::new(secret);
+
+        let origin = Fr::from(42u64);
+        let origins = (0..5u64).map(Fr::from).collect::<Vec<_>>();
+        let expected = naive_protocol.process(origin).await?;
+
+        let protocol = |faulty: &[usize]| {
+            let reported = Arc::new(Mutex::new(Vec::new()));
+            let topology = FaultyAgentsTopology {
+                sss: Arc::clone(&sss),
+                faulty: faulty.iter().copied().collect(),
+                redundancy: 2,
+                reported: Arc::clone(&reported),
+            };
+
+            (
+                CollaborativeProtocol::new((1, current_share), topology),
+                reported,
+            )
+        };
+
+        // Responses of agents 2 to 5 are inconsistent, agent 6 confirms all but the faulty one
+        let (coop_protocol, reported) = protocol(&[2]);
+        assert_eq!(coop_protocol.process(origin).await?, expected);
+        assert_eq!(*reported.lock().unwrap(), vec![2]);
+
+        let processed = coop_protocol.process_batch(origins.clone()).await;
+        let naive_processed = naive_protocol.process_batch(origins.clone()).await;
+        for (processed, naive_processed) in processed.into_iter().zip(naive_processed) {
+            assert_eq!(processed?, naive_processed?);
+        }
+        assert_eq!(*reported.lock().unwrap(), vec![2, 2]);
+
+        // Too many faulty agents fail the computation instead of returning a wrong fingerprint
+        let (coop_protocol, _) = protocol(&[2, 3]);
+        assert!(coop_protocol.process(origin).await.is_err());
+        assert!(coop_protocol
+            .process_batch(origins)
+            .await
+            .iter()
+            .all(Result::is_err));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_blinded_protocol() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sss = SecretSharing::generate(secret, 4, 10);
+        let current_share = sss.get_share(1).unwrap();
+
+        let topology = LocalAgentsTopology {
+            sss,
+            refreshed: None,
+        };
+
+        let naive_protocol = NaiveProtocol::<G1>::new(secret);
+        let client_naive = BlindedProtocol::new(NaiveProtocol::<G1>::new(secret));
+        let client_coop =
+            BlindedProtocol::new(CollaborativeProtocol::new((1, current_share), topology));
+
+        let origin = Fr::from(42u64);
+        let expected = naive_protocol.process(origin).await?;
+
+        assert_eq!(client_naive.process(origin).await?, expected);
+        assert_eq!(client_coop.process(origin).await?, expected);
+
+        let origins = (0..10u64).map(Fr::from).collect::<Vec<_>>();
+        let naive_processed = naive_protocol.process_batch(origins.clone()).await;
+        let processed = client_coop.process_batch(origins).await;
+
+        assert_eq!(processed.len(), 10);
+        for (processed, naive_processed) in processed.into_iter().zip(naive_processed) {
+            assert_eq!(processed?, naive_processed?);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_domain_separation_tag() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sss = SecretSharing::generate(secret, 4, 10);
+        let current_share = sss.get_share(1).unwrap();
+        let dst = DomainSeparationTag::new("ACME_PAYMENTS")?;
+
+        let topology = LocalAgentsTopology {
+            sss,
+            refreshed: None,
+        };
+
+        let naive_protocol = NaiveProtocol::<G1>::new(secret).with_dst(dst.clone());
+        let coop_protocol =
+            CollaborativeProtocol::new((1, current_share), topology).with_dst(dst.clone());
+        let client = BlindedProtocol::new(NaiveProtocol::<G1>::new(secret)).with_dst(dst.clone());
+
+        // Same secret with another tag gives unrelated points
+        let origin = Fr::from(42u64);
+        let expected = naive_protocol.process_point(origin).await?;
+        assert_ne!(
+            NaiveProtocol::<G1>::new(secret)
+                .process_point(origin)
+                .await?,
+            expected
+        );
+
+        assert_eq!(coop_protocol.process_point(origin).await?, expected);
+        assert_eq!(client.process_point(origin).await?, expected);
+
+        let group_key = G2::generator() * secret;
+        assert!(verify_point(&origin, &expected, &group_key, &dst));
+        assert!(!verify_point(
+            &origin,
+            &expected,
+            &group_key,
+            &DomainSeparationTag::default()
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tenant_scoped_protocols() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sss = SecretSharing::generate(secret, 4, 10);
+        let current_share = sss.get_share(1).unwrap();
+        let key = TenantKey::generate();
+        let retail = Tenant::new("retail")?;
+        let corporate = Tenant::new("corporate")?;
+
+        let topology = LocalAgentsTopology {
+            sss,
+            refreshed: None,
+        };
+        let naive_protocol = NaiveProtocol::<G1>::new(secret).with_tenant_key(key.clone());
+        let coop_protocol =
+            CollaborativeProtocol::new((1, current_share), topology).with_tenant_key(key.clone());
+
+        let origin = Fr::from(42u64);
+        let blinded = G1::generator() * origin;
+        let retail_scope = naive_protocol.scope(&retail)?;
+        let scalar = retail_scope.scalar();
+
+        // Agents evaluate with `s_i * t`, the reconstruction gives `[k * t] B`
+        let expected = blinded * (secret * scalar);
+        assert_eq!(
+            naive_protocol.evaluate_for(&retail_scope, blinded).await?,
+            expected
+        );
+        assert_eq!(
+            coop_protocol.evaluate_for(&retail_scope, blinded).await?,
+            expected
+        );
+        assert_eq!(
+            coop_protocol
+                .evaluate_batch_for(&retail_scope, vec![blinded, blinded])
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?,
+            vec![expected, expected]
+        );
+
+        // Fingerprints of the tenant verify against the group key of the tenant only
+        let client = BlindedProtocol::new(TenantEvaluator::new(&coop_protocol, retail_scope));
+        let point = client.process_point(origin).await?;
+        let group_key = G2::generator() * secret;
+        let dst = DomainSeparationTag::default();
+        assert!(verify_point(&origin, &point, &(group_key * scalar), &dst));
+        assert!(!verify_point(&origin, &point, &group_key, &dst));
+
+        // Tenants get unrelated fingerprints, the default tenant keeps the fingerprints of the network
+        let corporate_client = BlindedProtocol::new(TenantEvaluator::new(
+            &naive_protocol,
+            naive_protocol.scope(&corporate)?,
+        ));
+        assert_ne!(corporate_client.process_point(origin).await?, point);
+
+        let default_client = BlindedProtocol::new(TenantEvaluator::new(
+            &coop_protocol,
+            coop_protocol.scope(&Tenant::default())?,
+        ));
+        assert_eq!(
+            default_client.process(origin).await?,
+            NaiveProtocol::<G1>::new(secret).process(origin).await?
+        );
+
+        // Tenants need the tenant key
+        assert!(NaiveProtocol::<G1>::new(secret).scope(&retail).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bls12_381_protocols() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = bls12_381::Fr::random(&mut rng);
+        let sss = SecretSharing::generate(secret, 4, 10);
+        let current_share = sss.get_share(1).unwrap();
+
+        let naive_protocol = NaiveProtocol::<bls12_381::G1>::new(secret);
+        let coop_protocol =
+            CollaborativeProtocol::new((1, current_share), Bls12381AgentsTopology { sss });
+        let client = BlindedProtocol::new(NaiveProtocol::<bls12_381::G1>::new(secret));
+
+        let origin = Fr::from(42u64);
+        let expected = naive_protocol.process_point(origin).await?;
+
+        assert_eq!(coop_protocol.process_point(origin).await?, expected);
+        assert_eq!(client.process_point(origin).await?, expected);
+        assert_eq!(
+            coop_protocol.process(origin).await?,
+            naive_protocol.process(origin).await?
+        );
+
+        let group_key = bls12_381::G2::generator() * secret;
+        assert!(verify_point(
+            &origin,
+            &expected,
+            &group_key,
+            &DomainSeparationTag::default()
+        ));
+        assert!(!verify_point(
+            &origin,
+            &(expected + bls12_381::G1::generator()),
+            &group_key,
+            &DomainSeparationTag::default()
+        ));
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_fingerprint_protocol() -> Result<(), Error> {
         let mut rng = OsRng;
@@ -88,7 +759,7 @@ mod tests {
         let secret = Fr::random(&mut rng);
         let origin = Fr::from(42u64);
 
-        let fingerprint_protocol = NaiveProtocol::new(secret);
+        let fingerprint_protocol = NaiveProtocol::<G1>::new(secret);
 
         let processed = fingerprint_protocol.process(origin).await?;
 
@@ -1,28 +1,71 @@
 use anyhow::Error;
 use halo2_axiom::halo2curves::bn256::{Fr, G1};
-use halo2_axiom::halo2curves::CurveExt;
 
-use crate::protocols::FingerprintProtocol;
-use crate::{HashSqueeze, HASH_TO_CURVE_PREFIX};
+use crate::curves::FingerprintCurve;
+use crate::protocols::{BlindedEvaluation, FingerprintProtocol, TenantEvaluation};
+use crate::tenant::{self, Tenant, TenantKey, TenantScope};
+use crate::DomainSeparationTag;
 
 // Computes the [k] P without split and reconstruct from by cooperating with other agents
-pub struct NaiveProtocol {
-    secret: Fr,
+pub struct NaiveProtocol<G: FingerprintCurve = G1> {
+    secret: G::ScalarExt,
+    dst: DomainSeparationTag,
+    tenant_key: Option<TenantKey>,
 }
 
-impl NaiveProtocol {
-    pub fn new(secret: Fr) -> Self {
-        Self { secret }
+impl<G: FingerprintCurve> NaiveProtocol<G> {
+    pub fn new(secret: G::ScalarExt) -> Self {
+        Self {
+            secret,
+            dst: DomainSeparationTag::default(),
+            tenant_key: None,
+        }
+    }
+
+    /// Hash values to curve with the `dst` instead of the default one
+    pub fn with_dst(mut self, dst: DomainSeparationTag) -> Self {
+        self.dst = dst;
+        self
+    }
+
+    /// Evaluate tenants with the secret `k * t` derived with the tenant `key`, only the default tenant is available without it
+    pub fn with_tenant_key(mut self, key: TenantKey) -> Self {
+        self.tenant_key = Some(key);
+        self
     }
 }
 
-impl FingerprintProtocol<Fr> for NaiveProtocol {
-    async fn process(&self, unblinded: Fr) -> Result<Fr, Error> {
-        let hasher = G1::hash_to_curve(HASH_TO_CURVE_PREFIX);
+impl<G: FingerprintCurve> FingerprintProtocol<Fr> for NaiveProtocol<G> {
+    type Point = G;
+
+    fn dst(&self) -> &DomainSeparationTag {
+        &self.dst
+    }
+
+    async fn process_point(&self, unblinded: Fr) -> Result<G, Error> {
+        let hasher = G::hash_to_curve(self.dst.as_str());
         let curve_point = hasher(&unblinded.to_bytes());
 
-        let hash_with_secret = curve_point * self.secret;
+        Ok(curve_point * self.secret)
+    }
+}
+
+impl<G: FingerprintCurve> BlindedEvaluation<G> for NaiveProtocol<G> {
+    async fn evaluate(&self, blinded: G) -> Result<G, Error> {
+        Ok(blinded * self.secret)
+    }
+}
+
+impl<G: FingerprintCurve> TenantEvaluation<G> for NaiveProtocol<G> {
+    fn scope(&self, tenant: &Tenant) -> Result<TenantScope<G::ScalarExt>, Error> {
+        tenant::scope(self.tenant_key.as_ref(), tenant)
+    }
 
-        hash_with_secret.squeeze() // Use default compress for G1
+    async fn evaluate_for(
+        &self,
+        scope: &TenantScope<G::ScalarExt>,
+        blinded: G,
+    ) -> Result<G, Error> {
+        Ok(blinded * (self.secret * scope.scalar()))
     }
 }
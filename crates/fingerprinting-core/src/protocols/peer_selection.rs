@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+///
+/// Latency-tracked ordering of remote agents: the fastest healthy agents are asked first,
+/// agents with failed requests are asked only as a fallback until they respond again,
+/// agents excluded for faulty responses are asked last
+#[derive(Default)]
+pub struct PeerSelection {
+    stats: Mutex<HashMap<usize, PeerStats>>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct PeerStats {
+    latency: Duration, // moving average of successful requests
+    failures: u32,     // consecutive failures
+    excluded: bool,    // returned faulty response
+}
+
+impl PeerSelection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Order `agents` by preference, agents without statistics are tried first
+    pub fn order(&self, agents: impl Iterator<Item = usize>) -> Vec<usize> {
+        let mut agents = agents.collect::<Vec<_>>();
+
+        if let Ok(stats) = self.stats.lock() {
+            agents.sort_by_key(|agent| {
+                let peer = stats.get(agent).copied().unwrap_or_default();
+                (peer.excluded, peer.failures, peer.latency)
+            });
+        }
+
+        agents
+    }
+
+    ///
+    /// Record the outcome of the request to the `agent`
+    pub fn record(&self, agent: usize, latency: Duration, success: bool) {
+        let Ok(mut stats) = self.stats.lock() else {
+            return;
+        };
+
+        let peer = stats.entry(agent).or_default();
+
+        if success {
+            // Exponential moving average with 1/5 weight of the new sample
+            peer.latency = if peer.latency.is_zero() {
+                latency
+            } else {
+                (peer.latency * 4 + latency) / 5
+            };
+            peer.failures = 0;
+        } else {
+            peer.failures = peer.failures.saturating_add(1);
+        }
+    }
+
+    ///
+    /// Exclude the `agent` that returned a faulty response, it stays excluded regardless of latency
+    pub fn exclude(&self, agent: usize) {
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.entry(agent).or_default().excluded = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_selection_order() {
+        let selection = PeerSelection::new();
+
+        selection.record(2, Duration::from_millis(30), true);
+        selection.record(3, Duration::from_millis(10), true);
+        selection.record(4, Duration::from_millis(5), true);
+        selection.record(4, Duration::from_millis(5), false);
+
+        // Unknown agent 5 first, failed agent 4 last
+        assert_eq!(selection.order(2..=5), vec![5, 3, 2, 4]);
+
+        // Recovered agent is ordered by latency again
+        selection.record(4, Duration::from_millis(5), true);
+        assert_eq!(selection.order(2..=4), vec![4, 3, 2]);
+
+        // Latency is averaged over requests
+        for _ in 0..10 {
+            selection.record(3, Duration::from_millis(50), true);
+        }
+        assert_eq!(selection.order(2..=4), vec![4, 2, 3]);
+
+        // Excluded agent goes last even when responding fast
+        selection.exclude(4);
+        selection.record(4, Duration::from_millis(1), true);
+        assert_eq!(selection.order(2..=4), vec![2, 3, 4]);
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::{anyhow, Error};
+use halo2_axiom::halo2curves::ff::PrimeField as PF;
+use halo2_axiom::halo2curves::group::Group;
+use std::collections::HashSet;
+
+use crate::secret_sharing::SecretSharing;
+
+///
+/// Interpolation in the exponent confirmed by `threshold + redundancy` consistent responses.
+///
+/// Responses `[s_i] B` of honest agents lie on one polynomial of degree `threshold - 1`,
+/// so every response beyond the first `threshold` can be predicted from them and checked.
+/// A subset of `threshold + redundancy` responses (always including the trusted own one) is accepted
+/// only when all its responses are consistent, which can't happen for a wrong polynomial
+/// unless more than `redundancy` agents are faulty. Responses outside the accepted subset
+/// that differ from the prediction are reported as faulty.
+pub(crate) struct RobustReconstruction<F: PF> {
+    threshold: usize,
+    size: usize,        // responses confirming the interpolation
+    agents: Vec<usize>, // responding agents, the first one (own agent) is trusted
+    interpolation: Interpolation<F>,
+}
+
+/// Lagrange coefficients over a subset of responses (given by positions in the agents)
+struct Interpolation<F: PF> {
+    base: Vec<usize>,                  // positions of the responses interpolated
+    base_agents: Vec<usize>,           // agents of the base responses
+    at_zero: Vec<F>,                   // coefficients of the base for the value at zero
+    predictions: Vec<(usize, Vec<F>)>, // coefficients of the base for the rest of the subset
+}
+
+impl<F: PF> Interpolation<F> {
+    fn new(threshold: usize, agents: &[usize], positions: &[usize]) -> Self {
+        let (base, rest) = positions.split_at(threshold);
+        let base_agents = base.iter().map(|p| agents[*p]).collect::<Vec<_>>();
+
+        let mut interpolation = Self {
+            base: base.to_vec(),
+            base_agents,
+            at_zero: Vec::new(),
+            predictions: Vec::new(),
+        };
+
+        interpolation.at_zero = interpolation.coefficients_at(0);
+        interpolation.predictions = rest
+            .iter()
+            .map(|p| (*p, interpolation.coefficients_at(agents[*p])))
+            .collect();
+
+        interpolation
+    }
+
+    fn coefficients_at(&self, x: usize) -> Vec<F> {
+        self.base_agents
+            .iter()
+            .map(|i| SecretSharing::lagrange_coefficient_at(*i, x, &self.base_agents))
+            .collect()
+    }
+
+    fn evaluate<G: Group<Scalar = F>>(&self, responses: &[G], coefficients: &[F]) -> G {
+        self.base
+            .iter()
+            .zip(coefficients)
+            .fold(G::identity(), |y, (p, lambda)| y + responses[*p] * lambda)
+    }
+
+    fn is_consistent<G: Group<Scalar = F>>(&self, responses: &[G]) -> bool {
+        self.predictions
+            .iter()
+            .all(|(p, coefficients)| self.evaluate(responses, coefficients) == responses[*p])
+    }
+
+    /// Agents outside of the interpolated subset whose responses differ from the prediction
+    fn inconsistent_agents<G: Group<Scalar = F>>(
+        &self,
+        agents: &[usize],
+        responses: &[G],
+    ) -> Vec<usize> {
+        let subset = self
+            .base
+            .iter()
+            .chain(self.predictions.iter().map(|(p, _)| p))
+            .copied()
+            .collect::<HashSet<_>>();
+
+        (0..agents.len())
+            .filter(|p| !subset.contains(p))
+            .filter(|p| {
+                self.evaluate(responses, &self.coefficients_at(agents[*p])) != responses[*p]
+            })
+            .map(|p| agents[p])
+            .collect()
+    }
+}
+
+impl<F: PF> RobustReconstruction<F> {
+    pub(crate) fn new(
+        threshold: usize,
+        redundancy: usize,
+        agents: Vec<usize>,
+    ) -> Result<Self, Error> {
+        let size = threshold + redundancy;
+
+        if threshold == 0 || agents.len() < size {
+            return Err(anyhow!(
+                "Robust reconstruction needs {} responses, got {}",
+                size,
+                agents.len()
+            ));
+        }
+
+        let interpolation = Interpolation::new(threshold, &agents, &(0..size).collect::<Vec<_>>());
+
+        Ok(Self {
+            threshold,
+            size,
+            agents,
+            interpolation,
+        })
+    }
+
+    ///
+    /// Value at zero interpolated from `responses` (in the order of agents)
+    /// and the agents with responses inconsistent with it
+    pub(crate) fn reconstruct<G: Group<Scalar = F>>(
+        &self,
+        responses: &[G],
+    ) -> Result<(G, Vec<usize>), Error> {
+        if responses.len() != self.agents.len() {
+            return Err(anyhow!(
+                "Got {} responses for {} agents",
+                responses.len(),
+                self.agents.len()
+            ));
+        }
+
+        // Usually the first responses are consistent, otherwise search for a consistent subset
+        let searched;
+        let interpolation = if self.interpolation.is_consistent(responses) {
+            &self.interpolation
+        } else {
+            searched = combinations(1..self.agents.len(), self.size - 1)
+                .skip(1) // the first subset is already checked
+                .map(|subset| {
+                    let positions = std::iter::once(0).chain(subset).collect::<Vec<_>>();
+                    Interpolation::new(self.threshold, &self.agents, &positions)
+                })
+                .find(|interpolation| interpolation.is_consistent(responses))
+                .ok_or(anyhow!(
+                    "Responses of agents {:?} are inconsistent, no {} of them agree",
+                    self.agents,
+                    self.size
+                ))?;
+            &searched
+        };
+
+        let value = interpolation.evaluate(responses, &interpolation.at_zero);
+        let faulty = interpolation.inconsistent_agents(&self.agents, responses);
+
+        Ok((value, faulty))
+    }
+}
+
+/// All subsets of `size` elements of the `range` in lexicographic order
+fn combinations(range: std::ops::Range<usize>, size: usize) -> impl Iterator<Item = Vec<usize>> {
+    let mut next = (size <= range.len()).then(|| (range.start..range.start + size).collect());
+
+    std::iter::from_fn(move || {
+        let current: Vec<usize> = next.take()?;
+
+        // Advance the rightmost element that is not at its maximum
+        let mut advanced = current.clone();
+        for k in (0..size).rev() {
+            if advanced[k] < range.end - (size - k) {
+                advanced[k] += 1;
+                for l in k + 1..size {
+                    advanced[l] = advanced[l - 1] + 1;
+                }
+                next = Some(advanced);
+                break;
+            }
+        }
+
+        Some(current)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_axiom::halo2curves::bn256::{Fr, G1};
+    use halo2_axiom::halo2curves::ff::Field;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(
+            combinations(1..5, 2).collect::<Vec<_>>(),
+            vec![
+                vec![1, 2],
+                vec![1, 3],
+                vec![1, 4],
+                vec![2, 3],
+                vec![2, 4],
+                vec![3, 4]
+            ]
+        );
+        assert_eq!(combinations(1..3, 3).count(), 0);
+    }
+
+    #[test]
+    fn test_robust_reconstruction_excludes_faulty_agents() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sss = SecretSharing::generate(secret, 3, 7);
+        let blinded = G1::random(&mut rng);
+
+        let agents = vec![1, 2, 3, 4, 5, 6, 7];
+        let mut responses = agents
+            .iter()
+            .map(|i| blinded * sss.get_share(*i).unwrap())
+            .collect::<Vec<_>>();
+
+        let reconstruction = RobustReconstruction::new(3, 2, agents)?;
+
+        // All responses are consistent
+        assert_eq!(
+            reconstruction.reconstruct(&responses)?,
+            (blinded * secret, vec![])
+        );
+
+        // Two faulty agents are identified by the five consistent ones
+        responses[2] = G1::random(&mut rng);
+        responses[5] += blinded;
+        assert_eq!(
+            reconstruction.reconstruct(&responses)?,
+            (blinded * secret, vec![3, 6])
+        );
+
+        // Three faulty agents leave only four consistent responses
+        responses[6] = G1::random(&mut rng);
+        assert!(reconstruction.reconstruct(&responses).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_robust_reconstruction_colluding_agents() -> Result<(), Error> {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sss = SecretSharing::generate(secret, 3, 5);
+        let blinded = G1::random(&mut rng);
+
+        // Agents 2 and 3 shift their responses, so that 1, 2, 3 and 4 lie on another polynomial
+        let agents = vec![1, 2, 3, 4, 5];
+        let mut responses = agents
+            .iter()
+            .map(|i| blinded * sss.get_share(*i).unwrap())
+            .collect::<Vec<_>>();
+        responses[1] += blinded;
+        responses[2] += blinded;
+
+        // The wrong value is never confirmed by all five responses
+        assert!(RobustReconstruction::new(3, 2, agents.clone())?
+            .reconstruct(&responses)
+            .is_err());
+
+        // While without redundancy it goes unnoticed
+        let (value, _) = RobustReconstruction::new(3, 0, agents)?.reconstruct(&responses)?;
+        assert_ne!(value, blinded * secret);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_robust_reconstruction_needs_redundancy() {
+        assert!(RobustReconstruction::<Fr>::new(3, 2, vec![1, 2, 3, 4]).is_err());
+        assert!(RobustReconstruction::<Fr>::new(3, 2, vec![1, 2, 3, 4, 5]).is_ok());
+    }
+}
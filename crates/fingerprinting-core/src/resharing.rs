@@ -0,0 +1,481 @@
+use anyhow::{anyhow, Error};
+use halo2_axiom::halo2curves::ff::PrimeField;
+use halo2_axiom::halo2curves::group::{Group, GroupEncoding};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::dkg::transcript_digest;
+use crate::secret_sharing::{SecretSharing, ShareCommitments};
+
+// Resharing of the existing secret from the old (t, n) committee to a new (t', n') committee
+//
+// A set `D` of at least `t` old agents act as dealers, every dealer `i` in `D`:
+// - shares its old secret shard `s_i` with a random polynomial `g_i` of degree `t' - 1`, `g_i(0) = s_i`
+// - publishes Feldman commitments `[b_ik] G`, where `[b_i0] G` must be the old public share of `i`
+// - privately sends `g_i(j)` to every new agent `j`
+//
+// Every new agent verifies received evaluations against the dealer commitments and the old group
+// commitments, then interpolates over the dealers with Lagrange coefficients `l_i` of `D`:
+// - new secret shard `s_j' = sum(l_i * g_i(j))`
+// - new group commitments `C_k' = sum(l_i * [b_ik] G)`
+//
+// The new group key `C_0' = sum(l_i * [s_i] G)` equals the old one, so fingerprints do not change,
+// while neither the master secret nor any old shard is ever reconstructed.
+//
+// A dealer sending different commitments to different new agents would leave them with shards of
+// different polynomials, new agents therefore compare digests of the received commitments before finalizing.
+
+/// Old agent redistributing its secret shard to the new committee
+pub struct ResharingDealer<F: PrimeField, G: Group<Scalar = F>> {
+    index: usize,
+    dealing: SecretSharing<F>,
+    commitments: ShareCommitments<G>,
+}
+
+impl<F: PrimeField, G: Group<Scalar = F>> ResharingDealer<F, G> {
+    /// Dealer `index` of the old committee sharing its `secret_shard` for the `threshold`-of-`participants`
+    /// new committee
+    pub fn new(
+        index: usize,
+        secret_shard: F,
+        old_commitments: &ShareCommitments<G>,
+        threshold: usize,
+        participants: usize,
+    ) -> Result<Self, Error> {
+        if index == 0 || !SecretSharing::verify_share(index, secret_shard, old_commitments) {
+            return Err(anyhow!(
+                "Secret shard of agent {} does not match current commitments",
+                index
+            ));
+        }
+
+        validate_committee(threshold, participants)?;
+
+        let dealing = SecretSharing::generate(secret_shard, threshold, participants);
+        let commitments = dealing.commitments();
+
+        Ok(Self {
+            index,
+            dealing,
+            commitments,
+        })
+    }
+
+    /// Agent number of the dealer in the old committee
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Number of agents in the new committee
+    pub fn participants(&self) -> usize {
+        self.dealing.get_shares().len()
+    }
+
+    /// Commitments of the dealing, broadcast to every new agent
+    pub fn commitments(&self) -> &ShareCommitments<G> {
+        &self.commitments
+    }
+
+    /// Evaluation of the dealing for the new agent `participant`, must be sent privately
+    pub fn share_for(&self, participant: usize) -> Result<F, Error> {
+        self.dealing
+            .get_shares()
+            .get(&participant)
+            .copied()
+            .ok_or(anyhow!("Unknown participant {}", participant))
+    }
+}
+
+/// New agent receiving its secret shard from the dealers of the old committee
+pub struct ResharingReceiver<F: PrimeField, G: Group<Scalar = F>> {
+    index: usize,
+    threshold: usize,
+    participants: usize,
+    old_commitments: ShareCommitments<G>,
+    dealers: Vec<usize>,
+    received: BTreeMap<usize, (ShareCommitments<G>, F)>,
+}
+
+impl<F: PrimeField, G: Group<Scalar = F>> ResharingReceiver<F, G> {
+    /// Agent `index` of the new `threshold`-of-`participants` committee expecting dealings from
+    /// the old agents `dealers`; all new agents must use the same dealers
+    pub fn new(
+        index: usize,
+        threshold: usize,
+        participants: usize,
+        old_commitments: ShareCommitments<G>,
+        dealers: &[usize],
+    ) -> Result<Self, Error> {
+        validate_committee(threshold, participants)?;
+
+        if index == 0 || index > participants {
+            return Err(anyhow!(
+                "Invalid agent number {}, should be in range 1 to {}",
+                index,
+                participants
+            ));
+        }
+
+        let dealers = dealers.iter().copied().collect::<BTreeSet<_>>();
+        if dealers.contains(&0) {
+            return Err(anyhow!("Invalid dealer 0, agents are numbered from 1"));
+        }
+        if dealers.len() < old_commitments.threshold() {
+            return Err(anyhow!(
+                "At least {} distinct dealers of the old committee are required, got {}",
+                old_commitments.threshold(),
+                dealers.len()
+            ));
+        }
+
+        Ok(Self {
+            index,
+            threshold,
+            participants,
+            old_commitments,
+            dealers: dealers.into_iter().collect(),
+            received: BTreeMap::new(),
+        })
+    }
+
+    /// Agent number in the new committee
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn participants(&self) -> usize {
+        self.participants
+    }
+
+    /// Old agents dealing their shards
+    pub fn dealers(&self) -> &[usize] {
+        &self.dealers
+    }
+
+    /// Accept the dealing of an old agent after verification against its commitments and its
+    /// old public share
+    pub fn receive(
+        &mut self,
+        dealer: usize,
+        commitments: ShareCommitments<G>,
+        share: F,
+    ) -> Result<(), Error> {
+        if !self.dealers.contains(&dealer) {
+            return Err(anyhow!(
+                "Agent {} is not a dealer of the resharing, expected one of {:?}",
+                dealer,
+                self.dealers
+            ));
+        }
+
+        if commitments.threshold() != self.threshold {
+            return Err(anyhow!(
+                "Dealer {} committed to polynomial for threshold {}, expected {}",
+                dealer,
+                commitments.threshold(),
+                self.threshold
+            ));
+        }
+
+        if commitments.group_key() != self.old_commitments.public_share(dealer) {
+            return Err(anyhow!(
+                "Dealer {} does not share its current secret shard",
+                dealer
+            ));
+        }
+
+        if !SecretSharing::verify_share(self.index, share, &commitments) {
+            return Err(anyhow!(
+                "Share from dealer {} does not match its commitments",
+                dealer
+            ));
+        }
+
+        if let Some((known_commitments, known_share)) = self.received.get(&dealer) {
+            // Retransmission of the same dealing is fine, conflicting dealings are not
+            if *known_commitments != commitments || *known_share != share {
+                return Err(anyhow!("Dealer {} sent conflicting dealings", dealer));
+            }
+            return Ok(());
+        }
+
+        self.received.insert(dealer, (commitments, share));
+
+        Ok(())
+    }
+
+    /// Dealers we still wait for
+    pub fn missing(&self) -> Vec<usize> {
+        self.dealers
+            .iter()
+            .copied()
+            .filter(|dealer| !self.received.contains_key(dealer))
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.received.len() == self.dealers.len()
+    }
+
+    /// Interpolate all dealings into our new secret shard and the new group commitments
+    pub fn finalize(&self) -> Result<(F, ShareCommitments<G>), Error> {
+        if !self.is_complete() {
+            return Err(anyhow!(
+                "Resharing is not complete, missing dealings from {:?}",
+                self.missing()
+            ));
+        }
+
+        let mut secret_shard = F::ZERO;
+        let mut commitments = vec![G::identity(); self.threshold];
+
+        for (dealer, (dealer_commitments, share)) in &self.received {
+            let lambda: F = SecretSharing::lagrange_coefficient(*dealer, &self.dealers);
+
+            secret_shard += *share * lambda;
+
+            for (combined, commitment) in
+                commitments.iter_mut().zip(dealer_commitments.commitments())
+            {
+                *combined += *commitment * lambda;
+            }
+        }
+
+        let commitments = ShareCommitments::new(commitments);
+
+        if commitments.group_key() != self.old_commitments.group_key() {
+            return Err(anyhow!("Resharing changed the group key"));
+        }
+
+        Ok((secret_shard, commitments))
+    }
+}
+
+impl<F: PrimeField, G: Group<Scalar = F> + GroupEncoding> ResharingReceiver<F, G> {
+    /// Digest of the commitments of all dealings, new agents compare it before finalizing
+    pub fn transcript_digest(&self) -> Result<[u8; 64], Error> {
+        if !self.is_complete() {
+            return Err(anyhow!(
+                "Resharing is not complete, missing dealings from {:?}",
+                self.missing()
+            ));
+        }
+
+        Ok(transcript_digest(
+            self.received
+                .iter()
+                .map(|(dealer, (commitments, _))| (*dealer, commitments)),
+        ))
+    }
+}
+
+fn validate_committee(threshold: usize, participants: usize) -> Result<(), Error> {
+    if threshold == 0 || threshold > participants {
+        return Err(anyhow!(
+            "Invalid threshold {}, should be in range 1 to {}",
+            threshold,
+            participants
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_axiom::halo2curves::bn256::{Fr, G1};
+
+    fn reshare(
+        old: &SecretSharing<Fr>,
+        dealers: &[usize],
+        t: usize,
+        n: usize,
+    ) -> Result<Vec<(Fr, ShareCommitments<G1>)>, Error> {
+        let old_commitments = old.commitments::<G1>();
+
+        let mut receivers = (1..=n)
+            .map(|j| ResharingReceiver::<Fr, G1>::new(j, t, n, old_commitments.clone(), dealers))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        for &dealer in dealers {
+            let dealing = ResharingDealer::<Fr, G1>::new(
+                dealer,
+                old.get_shares()[&dealer],
+                &old_commitments,
+                t,
+                n,
+            )?;
+
+            for receiver in &mut receivers {
+                let share = dealing.share_for(receiver.index())?;
+                receiver.receive(dealer, dealing.commitments().clone(), share)?;
+            }
+        }
+
+        receivers.iter().map(|r| r.finalize()).collect()
+    }
+
+    #[test]
+    fn test_reshare_to_larger_committee() -> Result<(), Error> {
+        let secret = Fr::from(1234567u64);
+        let old = SecretSharing::generate(secret, 3, 5);
+
+        let results = reshare(&old, &[2, 4, 5], 4, 7)?;
+
+        let commitments = results[0].1.clone();
+        assert!(results.iter().all(|(_, c)| *c == commitments));
+        assert_eq!(commitments.threshold(), 4);
+        assert_eq!(commitments.group_key(), old.commitments::<G1>().group_key());
+
+        for (i, (share, _)) in results.iter().enumerate() {
+            assert!(SecretSharing::verify_share(i + 1, *share, &commitments));
+        }
+
+        // Any 4 of 7 new shards give the same secret
+        for indices in [vec![1, 2, 3, 4], vec![2, 4, 6, 7]] {
+            let recovered = indices
+                .iter()
+                .map(|&i| results[i - 1].0 * SecretSharing::<Fr>::lagrange_coefficient(i, &indices))
+                .fold(Fr::zero(), |acc, v| acc + v);
+
+            assert_eq!(recovered, secret);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reshare_to_smaller_committee() -> Result<(), Error> {
+        let secret = Fr::from(42u64);
+        let old = SecretSharing::generate(secret, 3, 5);
+
+        let results = reshare(&old, &[1, 2, 3, 4, 5], 2, 3)?;
+
+        let indices = vec![1, 3];
+        let recovered = indices
+            .iter()
+            .map(|&i| results[i - 1].0 * SecretSharing::<Fr>::lagrange_coefficient(i, &indices))
+            .fold(Fr::zero(), |acc, v| acc + v);
+
+        assert_eq!(recovered, secret);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reshare_rejects_foreign_secret() -> Result<(), Error> {
+        let old = SecretSharing::generate(Fr::from(7u64), 2, 3);
+        let old_commitments = old.commitments::<G1>();
+
+        let mut receiver =
+            ResharingReceiver::<Fr, G1>::new(1, 2, 4, old_commitments.clone(), &[1, 2])?;
+
+        // Dealer not sharing its old shard
+        let another = SecretSharing::generate(Fr::from(8u64), 2, 3);
+        let another_commitments = another.commitments::<G1>();
+        let dealing = ResharingDealer::<Fr, G1>::new(
+            1,
+            another.get_shares()[&1],
+            &another_commitments,
+            2,
+            4,
+        )?;
+        assert!(receiver
+            .receive(1, dealing.commitments().clone(), dealing.share_for(1)?)
+            .is_err());
+
+        // Agent which is not among the dealers
+        let dealing =
+            ResharingDealer::<Fr, G1>::new(3, old.get_shares()[&3], &old_commitments, 2, 4)?;
+        assert!(receiver
+            .receive(3, dealing.commitments().clone(), dealing.share_for(1)?)
+            .is_err());
+
+        // Tampered share
+        let dealing =
+            ResharingDealer::<Fr, G1>::new(2, old.get_shares()[&2], &old_commitments, 2, 4)?;
+        assert!(receiver
+            .receive(
+                2,
+                dealing.commitments().clone(),
+                dealing.share_for(1)? + Fr::one()
+            )
+            .is_err());
+
+        assert_eq!(receiver.missing(), vec![1, 2]);
+        assert!(receiver.finalize().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reshare_transcript_digest_detects_equivocation() -> Result<(), Error> {
+        let old = SecretSharing::generate(Fr::from(7u64), 2, 3);
+        let old_commitments = old.commitments::<G1>();
+
+        let mut receivers = (1..=2)
+            .map(|j| ResharingReceiver::<Fr, G1>::new(j, 2, 2, old_commitments.clone(), &[1, 2]))
+            .collect::<Result<Vec<_>, Error>>()?;
+        assert!(receivers[0].transcript_digest().is_err());
+
+        let dealing = |dealer: usize| {
+            ResharingDealer::<Fr, G1>::new(
+                dealer,
+                old.get_shares()[&dealer],
+                &old_commitments,
+                2,
+                2,
+            )
+        };
+
+        // Dealer 1 sends dealings of two different polynomials, each valid on its own
+        let honest = dealing(2)?;
+        for receiver in &mut receivers {
+            let equivocating = dealing(1)?;
+            receiver.receive(
+                1,
+                equivocating.commitments().clone(),
+                equivocating.share_for(receiver.index())?,
+            )?;
+            receiver.receive(
+                2,
+                honest.commitments().clone(),
+                honest.share_for(receiver.index())?,
+            )?;
+        }
+
+        assert_ne!(
+            receivers[0].transcript_digest()?,
+            receivers[1].transcript_digest()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reshare_invalid_parameters() {
+        let old = SecretSharing::generate(Fr::from(7u64), 3, 5);
+        let old_commitments = old.commitments::<G1>();
+
+        // Not enough dealers to interpolate the old secret
+        assert!(
+            ResharingReceiver::<Fr, G1>::new(1, 2, 3, old_commitments.clone(), &[1, 2, 2]).is_err()
+        );
+        assert!(
+            ResharingReceiver::<Fr, G1>::new(4, 2, 3, old_commitments.clone(), &[1, 2, 3]).is_err()
+        );
+        assert!(
+            ResharingReceiver::<Fr, G1>::new(1, 4, 3, old_commitments.clone(), &[1, 2, 3]).is_err()
+        );
+
+        // Dealer with wrong shard
+        assert!(
+            ResharingDealer::<Fr, G1>::new(1, old.get_shares()[&2], &old_commitments, 2, 3)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//!
+//! Versioned layouts of the fingerprinted data.
+//!
+//! The fingerprint is the squeeze of the schema prefix followed by the serialized components in the schema order,
+//! so fingerprints of different schemas never collide and the schema can evolve while fingerprints
+//! of historical data are still computed with the schema they were recorded with. The domain separation
+//! tag of the hash to curve is part of the schema identity, the protocol must hash with the same tag.
+use anyhow::{anyhow, Error};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::components::{
+    AmountComponent, BankIdentifierComponent, CurrencyComponent, DateTimeComponent,
+    DirectionComponent, FingerprintComponent, IbanComponent, ReferenceComponent,
+};
+use crate::DomainSeparationTag;
+
+/// First bytes of every schema prefix, the rest identifies the schema version
+const PREFIX_MAGIC: [u8; 5] = [0xFF, 0xFE, 0xED, 0xDD, 0xCC];
+
+/// Components of the transaction a schema can include
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaComponent {
+    BankIdentifier,
+    Amount,
+    Currency,
+    /// Account of the transaction, zeros when the transaction has no IBAN
+    Iban,
+    /// End-to-end reference (UETR or EndToEndId), zeros when the transaction has no reference
+    Reference,
+    /// Credit-debit indicator, zero when the direction is unknown
+    Direction,
+    /// Date time processed by the protocol, it is the only component carrying the secret
+    DateTime,
+}
+
+impl SchemaComponent {
+    /// Size of the serialized component
+    pub fn size(&self) -> usize {
+        match self {
+            SchemaComponent::BankIdentifier => BankIdentifierComponent::size(),
+            SchemaComponent::Amount => AmountComponent::size(),
+            SchemaComponent::Currency => CurrencyComponent::size(),
+            SchemaComponent::Iban => IbanComponent::size(),
+            SchemaComponent::Reference => ReferenceComponent::size(),
+            SchemaComponent::Direction => DirectionComponent::size(),
+            SchemaComponent::DateTime => DateTimeComponent::size(),
+        }
+    }
+
+    /// Name of the component in configurations, the inverse of `from_str`
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemaComponent::BankIdentifier => "bank_identifier",
+            SchemaComponent::Amount => "amount",
+            SchemaComponent::Currency => "currency",
+            SchemaComponent::Iban => "iban",
+            SchemaComponent::Reference => "reference",
+            SchemaComponent::Direction => "direction",
+            SchemaComponent::DateTime => "date_time",
+        }
+    }
+}
+
+impl FromStr for SchemaComponent {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "bank_identifier" => Ok(SchemaComponent::BankIdentifier),
+            "amount" => Ok(SchemaComponent::Amount),
+            "currency" => Ok(SchemaComponent::Currency),
+            "iban" => Ok(SchemaComponent::Iban),
+            "reference" => Ok(SchemaComponent::Reference),
+            "direction" => Ok(SchemaComponent::Direction),
+            "date_time" => Ok(SchemaComponent::DateTime),
+            _ => Err(anyhow!(
+                "Unknown schema component {}, expected one of bank_identifier, amount, currency, iban, reference, direction, date_time",
+                name
+            )),
+        }
+    }
+}
+
+///
+/// Granularity of the date time processed by the protocol. The date time is truncated to the start
+/// of its bucket, so transactions recorded a few seconds apart (e.g. by the banks of both sides) share
+/// the fingerprint unless they fall into adjacent buckets, see `Fingerprint::complete_neighbour_fingerprints`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeGranularity {
+    /// Exact seconds, the granularity of the first schema
+    #[default]
+    Exact,
+    Minute,
+    Hour,
+    Day,
+}
+
+impl TimeGranularity {
+    /// Length of the bucket in seconds
+    pub fn seconds(&self) -> i64 {
+        match self {
+            TimeGranularity::Exact => 1,
+            TimeGranularity::Minute => 60,
+            TimeGranularity::Hour => 60 * 60,
+            TimeGranularity::Day => 24 * 60 * 60,
+        }
+    }
+
+    /// Name of the granularity in configurations, the inverse of `from_str`
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeGranularity::Exact => "exact",
+            TimeGranularity::Minute => "minute",
+            TimeGranularity::Hour => "hour",
+            TimeGranularity::Day => "day",
+        }
+    }
+}
+
+impl FromStr for TimeGranularity {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "exact" => Ok(TimeGranularity::Exact),
+            "minute" => Ok(TimeGranularity::Minute),
+            "hour" => Ok(TimeGranularity::Hour),
+            "day" => Ok(TimeGranularity::Day),
+            _ => Err(anyhow!(
+                "Unknown time granularity {}, expected one of exact, minute, hour, day",
+                name
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FingerprintSchema {
+    version: u32,
+    prefix: [u8; 8],
+    components: Vec<SchemaComponent>,
+    time_granularity: TimeGranularity,
+    dst: DomainSeparationTag,
+}
+
+impl FingerprintSchema {
+    /// Layout used before schemas were versioned, it keeps its original prefix
+    pub fn v1() -> Self {
+        Self {
+            version: 1,
+            prefix: [0xFF, 0xFE, 0xED, 0xDD, 0xCC, 0x00, 0xDD, 0xEE],
+            components: vec![
+                SchemaComponent::BankIdentifier,
+                SchemaComponent::Amount,
+                SchemaComponent::Currency,
+                SchemaComponent::DateTime,
+            ],
+            time_granularity: TimeGranularity::Exact,
+            dst: DomainSeparationTag::default(),
+        }
+    }
+
+    ///
+    /// Schema of the `version` with the prefix `FF FE ED DD CC` followed by the version (3 bytes, big-endian)
+    pub fn new(version: u32, components: Vec<SchemaComponent>) -> Result<Self, Error> {
+        let [high, a, b, c] = version.to_be_bytes();
+
+        if version <= 1 || high != 0 {
+            return Err(anyhow!(
+                "Schema version must be in range 2 to {}, got {}",
+                0x00FF_FFFF,
+                version
+            ));
+        }
+
+        if !components.contains(&SchemaComponent::DateTime) {
+            return Err(anyhow!(
+                "Schema {} must include the date time component",
+                version
+            ));
+        }
+
+        if let Some(duplicate) = components
+            .iter()
+            .enumerate()
+            .find_map(|(i, component)| components[..i].contains(component).then_some(component))
+        {
+            return Err(anyhow!(
+                "Schema {} includes the component {:?} twice",
+                version,
+                duplicate
+            ));
+        }
+
+        let mut prefix = [0u8; 8];
+        prefix[..5].copy_from_slice(&PREFIX_MAGIC);
+        prefix[5..].copy_from_slice(&[a, b, c]);
+
+        Ok(Self {
+            version,
+            prefix,
+            components,
+            time_granularity: TimeGranularity::Exact,
+            dst: DomainSeparationTag::default(),
+        })
+    }
+
+    /// Compose the schema of the `version` component by component
+    pub fn builder(version: u32) -> FingerprintSchemaBuilder {
+        FingerprintSchemaBuilder {
+            version,
+            components: Vec::new(),
+            time_granularity: TimeGranularity::Exact,
+            dst: DomainSeparationTag::default(),
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Domain separation prefix of the serialized data
+    pub fn prefix(&self) -> &[u8; 8] {
+        &self.prefix
+    }
+
+    /// Components in the order of serialization
+    pub fn components(&self) -> &[SchemaComponent] {
+        &self.components
+    }
+
+    /// Granularity of the date time processed by the protocol
+    pub fn time_granularity(&self) -> TimeGranularity {
+        self.time_granularity
+    }
+
+    /// Domain separation tag of the hash to curve
+    pub fn dst(&self) -> &DomainSeparationTag {
+        &self.dst
+    }
+
+    /// Same layout hashed to curve with the `dst` of the deployment
+    pub fn with_dst(mut self, dst: DomainSeparationTag) -> Self {
+        self.dst = dst;
+        self
+    }
+
+    /// Size of the serialized data including the prefix
+    pub fn size(&self) -> usize {
+        self.prefix.len()
+            + self
+                .components
+                .iter()
+                .map(SchemaComponent::size)
+                .sum::<usize>()
+    }
+
+    /// Position of the serialized `component` in the serialized data, `None` when the schema doesn't include it
+    pub fn offset(&self, component: SchemaComponent) -> Option<usize> {
+        let index = self.components.iter().position(|c| *c == component)?;
+
+        Some(
+            self.prefix.len()
+                + self.components[..index]
+                    .iter()
+                    .map(SchemaComponent::size)
+                    .sum::<usize>(),
+        )
+    }
+}
+
+impl Default for FingerprintSchema {
+    fn default() -> Self {
+        Self::v1()
+    }
+}
+
+///
+/// Builder of custom schemas, components are serialized in the order they are added
+pub struct FingerprintSchemaBuilder {
+    version: u32,
+    components: Vec<SchemaComponent>,
+    time_granularity: TimeGranularity,
+    dst: DomainSeparationTag,
+}
+
+impl FingerprintSchemaBuilder {
+    pub fn component(mut self, component: SchemaComponent) -> Self {
+        self.components.push(component);
+        self
+    }
+
+    /// Bucket the date time with the `time_granularity`, exact seconds by default
+    pub fn time_granularity(mut self, time_granularity: TimeGranularity) -> Self {
+        self.time_granularity = time_granularity;
+        self
+    }
+
+    /// Hash the date time to curve with the `dst`, `HASH_TO_CURVE_PREFIX` by default
+    pub fn dst(mut self, dst: DomainSeparationTag) -> Self {
+        self.dst = dst;
+        self
+    }
+
+    pub fn build(self) -> Result<FingerprintSchema, Error> {
+        let schema = FingerprintSchema::new(self.version, self.components)?;
+
+        Ok(FingerprintSchema {
+            time_granularity: self.time_granularity,
+            dst: self.dst,
+            ..schema
+        })
+    }
+}
+
+///
+/// Supported schema versions, fingerprints are computed with the `current` one unless requested otherwise
+#[derive(Debug, Clone)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<u32, FingerprintSchema>,
+    current: u32,
+}
+
+impl SchemaRegistry {
+    /// Register the `schema`, versions and prefixes of registered schemas are unique
+    /// and all of them share the domain separation tag
+    pub fn register(&mut self, schema: FingerprintSchema) -> Result<(), Error> {
+        if schema.dst != self.current().dst {
+            return Err(anyhow!(
+                "Schema {} uses the domain separation tag {}, registered schemas use {}",
+                schema.version,
+                schema.dst,
+                self.current().dst
+            ));
+        }
+
+        if let Some(registered) = self.schemas.values().find(|registered| {
+            registered.version == schema.version || registered.prefix == schema.prefix
+        }) {
+            return Err(anyhow!(
+                "Schema {} conflicts with registered schema {}",
+                schema.version,
+                registered.version
+            ));
+        }
+
+        self.schemas.insert(schema.version, schema);
+        Ok(())
+    }
+
+    /// Compute new fingerprints with the registered schema of the `version`
+    pub fn with_current(mut self, version: u32) -> Result<Self, Error> {
+        if !self.schemas.contains_key(&version) {
+            return Err(anyhow!("Schema {} is not registered", version));
+        }
+
+        self.current = version;
+        Ok(self)
+    }
+
+    /// Hash all registered schemas to curve with the `dst`, schemas registered later must use it as well
+    pub fn with_dst(mut self, dst: DomainSeparationTag) -> Self {
+        for schema in self.schemas.values_mut() {
+            schema.dst = dst.clone();
+        }
+
+        self
+    }
+
+    pub fn get(&self, version: u32) -> Option<&FingerprintSchema> {
+        self.schemas.get(&version)
+    }
+
+    pub fn current(&self) -> &FingerprintSchema {
+        &self.schemas[&self.current]
+    }
+
+    /// Versions of all registered schemas in ascending order
+    pub fn versions(&self) -> impl Iterator<Item = u32> + '_ {
+        self.schemas.keys().copied()
+    }
+}
+
+impl Default for SchemaRegistry {
+    fn default() -> Self {
+        let v1 = FingerprintSchema::v1();
+
+        Self {
+            current: v1.version,
+            schemas: BTreeMap::from([(v1.version, v1)]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_prefix() -> Result<(), Error> {
+        assert_eq!(
+            FingerprintSchema::v1().prefix(),
+            &[0xFF, 0xFE, 0xED, 0xDD, 0xCC, 0x00, 0xDD, 0xEE]
+        );
+        assert_eq!(
+            FingerprintSchema::new(2, FingerprintSchema::v1().components().to_vec())?.prefix(),
+            &[0xFF, 0xFE, 0xED, 0xDD, 0xCC, 0x00, 0x00, 0x02]
+        );
+
+        assert!(FingerprintSchema::new(1, vec![SchemaComponent::DateTime]).is_err());
+        assert!(FingerprintSchema::new(0x0100_0000, vec![SchemaComponent::DateTime]).is_err());
+        assert!(FingerprintSchema::new(2, vec![SchemaComponent::Amount]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_schema_builder() -> Result<(), Error> {
+        let schema = FingerprintSchema::builder(2)
+            .component("amount".parse()?)
+            .component(SchemaComponent::Currency)
+            .component(SchemaComponent::DateTime)
+            .build()?;
+
+        assert_eq!(
+            schema.components(),
+            &[
+                SchemaComponent::Amount,
+                SchemaComponent::Currency,
+                SchemaComponent::DateTime
+            ]
+        );
+        assert_eq!(schema.size(), 8 + 32 + 2 + 32);
+        assert_eq!(schema.offset(SchemaComponent::Currency), Some(8 + 32));
+        assert_eq!(schema.offset(SchemaComponent::BankIdentifier), None);
+
+        // Components are unique
+        assert!(FingerprintSchema::builder(3)
+            .component(SchemaComponent::DateTime)
+            .component(SchemaComponent::DateTime)
+            .build()
+            .is_err());
+        assert!("account".parse::<SchemaComponent>().is_err());
+        for component in schema.components() {
+            assert_eq!(component.name().parse::<SchemaComponent>()?, *component);
+        }
+
+        let schema = FingerprintSchema::builder(4)
+            .component(SchemaComponent::DateTime)
+            .time_granularity("hour".parse()?)
+            .build()?;
+        assert_eq!(schema.time_granularity(), TimeGranularity::Hour);
+        assert_eq!(
+            FingerprintSchema::v1().time_granularity(),
+            TimeGranularity::Exact
+        );
+        assert!("week".parse::<TimeGranularity>().is_err());
+        assert_eq!(
+            TimeGranularity::Minute.name().parse::<TimeGranularity>()?,
+            TimeGranularity::Minute
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_schema_registry() -> Result<(), Error> {
+        let mut registry = SchemaRegistry::default();
+        assert_eq!(registry.current().version(), 1);
+
+        registry.register(FingerprintSchema::new(
+            2,
+            vec![SchemaComponent::Amount, SchemaComponent::DateTime],
+        )?)?;
+        assert!(registry
+            .register(FingerprintSchema::new(2, vec![SchemaComponent::DateTime])?)
+            .is_err());
+        assert!(registry.register(FingerprintSchema::v1()).is_err());
+
+        let registry = registry.with_current(2)?;
+        assert_eq!(registry.current().version(), 2);
+        assert_eq!(registry.versions().collect::<Vec<_>>(), vec![1, 2]);
+        assert!(registry.clone().with_current(3).is_err());
+
+        // All schemas share the domain separation tag of the deployment
+        let dst = DomainSeparationTag::new("ACME_PAYMENTS")?;
+        let mut registry = registry.with_dst(dst.clone());
+        assert_eq!(registry.get(1).map(FingerprintSchema::dst), Some(&dst));
+        assert_ne!(registry.get(1), Some(&FingerprintSchema::v1()));
+        assert!(registry
+            .register(FingerprintSchema::new(3, vec![SchemaComponent::DateTime])?)
+            .is_err());
+        registry.register(
+            FingerprintSchema::builder(3)
+                .component(SchemaComponent::DateTime)
+                .dst(dst)
+                .build()?,
+        )?;
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
-use halo2_axiom::halo2curves::ff::PrimeField;
+use halo2_axiom::halo2curves::ff::{Field, PrimeField};
+use halo2_axiom::halo2curves::group::Group;
 use rand_core::OsRng;
 use std::collections::HashMap;
 
@@ -7,9 +8,50 @@ use halo2_axiom::halo2curves::group;
 
 pub struct SecretSharing<F: PrimeField> {
     pub threshold: usize,
+    coefficients: Vec<F>,
     shares: HashMap<usize, F>,
 }
 
+/// Feldman commitments `[a_j] G` to the coefficients of the sharing polynomial.
+///
+/// The first commitment is the group public key `[k] G`, public share of agent `i` is `[P(i)] G`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareCommitments<G: Group>(Vec<G>);
+
+impl<G: Group> ShareCommitments<G> {
+    pub fn new(commitments: Vec<G>) -> Self {
+        Self(commitments)
+    }
+
+    /// Threshold of the sharing equals the number of polynomial coefficients
+    pub fn threshold(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn commitments(&self) -> &[G] {
+        &self.0
+    }
+
+    /// Group public key `[k] G`
+    pub fn group_key(&self) -> G {
+        self.0.first().copied().unwrap_or(G::identity())
+    }
+
+    /// Public share of the agent `i` computed as `sum([a_j] G * i^j)`
+    pub fn public_share(&self, i: usize) -> G {
+        let x = G::Scalar::from(i as u64);
+        let mut x_power = G::Scalar::ONE;
+        let mut public_share = G::identity();
+
+        for commitment in &self.0 {
+            public_share += *commitment * x_power;
+            x_power *= x;
+        }
+
+        public_share
+    }
+}
+
 impl<F: PrimeField> SecretSharing<F> {
     pub fn generate(k: F, t: usize, n: usize) -> Self {
         assert!(t <= n, "Threshold must be <= total shares");
@@ -37,18 +79,44 @@ impl<F: PrimeField> SecretSharing<F> {
 
         SecretSharing {
             threshold: t,
+            coefficients,
             shares,
         }
     }
 
+    /// Feldman commitments to the polynomial coefficients, safe to publish alongside the shares
+    pub fn commitments<G: Group<Scalar = F>>(&self) -> ShareCommitments<G> {
+        ShareCommitments(
+            self.coefficients
+                .iter()
+                .map(|coeff| G::generator() * *coeff)
+                .collect(),
+        )
+    }
+
+    /// Check that `share` of the agent `i` is consistent with published commitments: `[share] G == [P(i)] G`
+    pub fn verify_share<G: Group<Scalar = F>>(
+        i: usize,
+        share: F,
+        commitments: &ShareCommitments<G>,
+    ) -> bool {
+        i > 0 && G::generator() * share == commitments.public_share(i)
+    }
+
     pub fn lagrange_coefficient(i: usize, indices: &[usize]) -> F {
+        Self::lagrange_coefficient_at(i, 0, indices)
+    }
+
+    /// Lagrange coefficient of the share `i` for the polynomial evaluation at `x` (instead of zero)
+    pub fn lagrange_coefficient_at(i: usize, x: usize, indices: &[usize]) -> F {
         let i_fr = F::from(i as u64);
+        let x_fr = F::from(x as u64);
         let mut result = F::from(1u64);
 
         for &j in indices {
             if i != j {
                 let j_fr = F::from(j as u64);
-                let numerator = -j_fr;
+                let numerator = x_fr - j_fr;
                 let denominator = i_fr - j_fr;
                 result *= numerator * denominator.invert().unwrap();
             }
@@ -64,7 +132,7 @@ impl<F: PrimeField> SecretSharing<F> {
         blinded_value: C,
     ) -> (usize, C) {
         let shard = self.shares.get(&i).unwrap();
-        let exponent_i = blinded_value * shard.clone();
+        let exponent_i = blinded_value * *shard;
 
         (i, exponent_i)
     }
@@ -82,7 +150,7 @@ impl<F: PrimeField> SecretSharing<F> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use halo2_axiom::halo2curves::bn256::Fr;
+    use halo2_axiom::halo2curves::bn256::{Fr, G1};
     use halo2_axiom::halo2curves::ff::Field;
 
     #[test]
@@ -212,6 +280,22 @@ mod tests {
         assert_eq!(lambda_1 + lambda_2 + lambda_3, Fr::one());
     }
 
+    #[test]
+    fn test_lagrange_coefficient_at_share() {
+        // Any threshold subset predicts the shares of other agents
+        let sharing = SecretSharing::generate(Fr::from(777u64), 3, 6);
+        let indices = vec![1, 4, 6];
+
+        for x in [2, 3, 5] {
+            let predicted = indices.iter().fold(Fr::zero(), |acc, &i| {
+                acc + SecretSharing::<Fr>::lagrange_coefficient_at(i, x, &indices)
+                    * sharing.get_share(i).unwrap()
+            });
+
+            assert_eq!(predicted, sharing.get_share(x).unwrap());
+        }
+    }
+
     #[test]
     fn test_deterministic_shares() {
         // Same secret and random seed should give same shares
@@ -328,6 +412,48 @@ mod tests {
         assert_eq!(Fr::zero(), reconstructed);
     }
 
+    #[test]
+    fn test_shares_match_commitments() {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sharing = SecretSharing::generate(secret, 3, 5);
+        let commitments = sharing.commitments::<G1>();
+
+        assert_eq!(commitments.threshold(), 3);
+        assert_eq!(commitments.group_key(), G1::generator() * secret);
+
+        for i in 1..=5 {
+            assert!(
+                SecretSharing::verify_share(i, sharing.shares[&i], &commitments),
+                "Share {} does not match commitments",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn test_tampered_share_rejected() {
+        let mut rng = OsRng;
+        let secret = Fr::random(&mut rng);
+        let sharing = SecretSharing::generate(secret, 3, 5);
+        let commitments = sharing.commitments::<G1>();
+
+        let tampered = sharing.shares[&2] + Fr::one();
+
+        assert!(!SecretSharing::verify_share(2, tampered, &commitments));
+        // Valid share presented for another agent
+        assert!(!SecretSharing::verify_share(
+            3,
+            sharing.shares[&2],
+            &commitments
+        ));
+        assert!(!SecretSharing::verify_share(
+            0,
+            sharing.shares[&2],
+            &commitments
+        ));
+    }
+
     #[test]
     fn test_polynomial_degree() {
         let secret = Fr::from(100u64);
@@ -0,0 +1,256 @@
+//!
+//! Tenant-scoped fingerprints of one agent network.
+//!
+//! Business lines sharing the network must not be able to correlate each other's fingerprints.
+//! The tenant `T` is evaluated with the secret `k * t`, where the tenant scalar `t = H(K, T)` is derived
+//! with the tenant key `K` shared by all agents. Secret shares are linear, so agents evaluate with `s_i * t`
+//! and the threshold reconstruction gives `[k * t] P` without a new key ceremony.
+//!
+//! The tenant key must stay secret: with a public `t` the holder of the point `[k * t_1] P` of one tenant
+//! computes the point `[k * t_2] P = [t_2 / t_1] [k * t_1] P` of any other tenant.
+//! The default tenant (empty name) is evaluated with `k` itself, its fingerprints are the ones
+//! computed before tenants were introduced.
+//!
+//! Every agent holds the tenant key, so the design trusts every agent: the operator of any single agent
+//! can derive `t` of all tenants and map the points of one tenant to another. Tenants are separated against
+//! callers of the service, who never learn the key. A tenant-scoped hash to curve (`dst || T`) would need
+//! no shared key, but agents evaluating blinded points can't tell which tenant the point was hashed for,
+//! so any caller could evaluate any tenant.
+use anyhow::{anyhow, Error};
+use halo2_axiom::halo2curves::ff::{Field, FromUniformBytes};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha512};
+
+// Domain separation of the tenant scalar derivation
+const TENANT_DOMAIN: &[u8] = b"PSO_TX_FINGERPRINT_TENANT_V1";
+
+// Domain separation of the public identifier of the tenant key
+const TENANT_KEY_ID_DOMAIN: &[u8] = b"PSO_TX_FINGERPRINT_TENANT_KEY_ID_V1";
+
+/// Maximum length of the tenant name
+pub const MAX_TENANT_LENGTH: usize = 64;
+
+/// Length of the tenant key in bytes
+pub const TENANT_KEY_LENGTH: usize = 32;
+
+/// Name of the tenant, the empty name stands for the default tenant
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Tenant(String);
+
+impl Tenant {
+    /// Name of ASCII letters, digits, `-`, `_` and `.`, at most `MAX_TENANT_LENGTH` of them
+    pub fn new(name: &str) -> Result<Self, Error> {
+        if name.len() > MAX_TENANT_LENGTH {
+            return Err(anyhow!(
+                "Tenant name must be at most {} characters long, got {}",
+                MAX_TENANT_LENGTH,
+                name.len()
+            ));
+        }
+
+        if !name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-_.".contains(&b))
+        {
+            return Err(anyhow!(
+                "Tenant name {:?} must consist of ASCII letters, digits, '-', '_' and '.'",
+                name
+            ));
+        }
+
+        Ok(Self(name.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::fmt::Display for Tenant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_default() {
+            return f.write_str("<default>");
+        }
+
+        f.write_str(&self.0)
+    }
+}
+
+///
+/// Secret the tenant scalars are derived with, all agents of the network must use the same one.
+/// Encoded in base58 in configurations.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TenantKey([u8; TENANT_KEY_LENGTH]);
+
+impl TenantKey {
+    pub fn new(bytes: [u8; TENANT_KEY_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    /// Random key for a new network
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; TENANT_KEY_LENGTH];
+        OsRng.fill_bytes(&mut bytes);
+
+        Self(bytes)
+    }
+
+    pub fn from_base58(encoded: &str) -> Result<Self, Error> {
+        let bytes = bs58::decode(encoded)
+            .into_vec()
+            .map_err(|e| anyhow!("Invalid tenant key, {}", e))?;
+
+        let bytes = bytes.try_into().map_err(|bytes: Vec<u8>| {
+            anyhow!(
+                "Invalid tenant key, expected {} bytes, got {}",
+                TENANT_KEY_LENGTH,
+                bytes.len()
+            )
+        })?;
+
+        Ok(Self(bytes))
+    }
+
+    pub fn to_base58(&self) -> String {
+        bs58::encode(&self.0).into_string()
+    }
+
+    /// Public identifier of the key, agents compare identifiers to check they use the same key without revealing it
+    pub fn id(&self) -> String {
+        let mut hasher = Sha512::new();
+        hasher.update(TENANT_KEY_ID_DOMAIN);
+        hasher.update(self.0);
+
+        hex::encode(&hasher.finalize()[..16])
+    }
+
+    /// Scope of the `tenant` with its scalar `t = H(K, T)`, the scalar of the default tenant is `1`
+    pub fn scope<F: FromUniformBytes<64>>(&self, tenant: &Tenant) -> TenantScope<F> {
+        if tenant.is_default() {
+            return TenantScope::default();
+        }
+
+        let mut hasher = Sha512::new();
+        hasher.update(TENANT_DOMAIN);
+        hasher.update(self.0);
+        hasher.update(tenant.as_str().as_bytes());
+
+        let digest: [u8; 64] = hasher.finalize().into();
+
+        TenantScope {
+            tenant: tenant.clone(),
+            scalar: F::from_uniform_bytes(&digest),
+        }
+    }
+}
+
+// The key is a secret, it is never printed
+impl std::fmt::Debug for TenantKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TenantKey(..)")
+    }
+}
+
+/// Tenant together with its scalar `t`, the tenant is evaluated with the secret `k * t`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantScope<F> {
+    tenant: Tenant,
+    scalar: F,
+}
+
+impl<F: Field> Default for TenantScope<F> {
+    fn default() -> Self {
+        Self {
+            tenant: Tenant::default(),
+            scalar: F::ONE,
+        }
+    }
+}
+
+impl<F: Copy> TenantScope<F> {
+    pub fn tenant(&self) -> &Tenant {
+        &self.tenant
+    }
+
+    pub fn scalar(&self) -> F {
+        self.scalar
+    }
+}
+
+///
+/// Scope of the `tenant` with the tenant `key` of the network,
+/// without the key only the default tenant can be evaluated
+pub fn scope<F: FromUniformBytes<64>>(
+    key: Option<&TenantKey>,
+    tenant: &Tenant,
+) -> Result<TenantScope<F>, Error> {
+    match key {
+        Some(key) => Ok(key.scope(tenant)),
+        None if tenant.is_default() => Ok(TenantScope::default()),
+        None => Err(anyhow!(
+            "Tenant {} can't be evaluated, no tenant key is configured",
+            tenant
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_axiom::halo2curves::bn256::Fr;
+
+    #[test]
+    fn test_tenant_names() -> Result<(), Error> {
+        assert!(Tenant::new("")?.is_default());
+        assert_eq!(Tenant::new("")?, Tenant::default());
+        assert_eq!(Tenant::new("retail.eu-1")?.as_str(), "retail.eu-1");
+
+        assert!(Tenant::new("retail banking").is_err());
+        assert!(Tenant::new("retail/eu").is_err());
+        assert!(Tenant::new(&"a".repeat(MAX_TENANT_LENGTH + 1)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tenant_scope() -> Result<(), Error> {
+        let key = TenantKey::generate();
+        let retail = Tenant::new("retail")?;
+        let corporate = Tenant::new("corporate")?;
+
+        // Default tenant keeps the secret of the network
+        assert_eq!(key.scope::<Fr>(&Tenant::default()).scalar(), Fr::ONE);
+
+        // Scalars are deterministic, distinct per tenant and per key
+        let scalar = key.scope::<Fr>(&retail).scalar();
+        assert_eq!(scalar, key.scope::<Fr>(&retail).scalar());
+        assert_ne!(scalar, key.scope::<Fr>(&corporate).scalar());
+        assert_ne!(scalar, TenantKey::generate().scope::<Fr>(&retail).scalar());
+        assert_ne!(scalar, Fr::ONE);
+
+        // Only the default tenant is available without a key
+        assert_eq!(scope::<Fr>(None, &Tenant::default())?.scalar(), Fr::ONE);
+        assert!(scope::<Fr>(None, &retail).is_err());
+        assert_eq!(scope::<Fr>(Some(&key), &retail)?.scalar(), scalar);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tenant_key_encoding() -> Result<(), Error> {
+        let key = TenantKey::generate();
+
+        assert_eq!(TenantKey::from_base58(&key.to_base58())?, key);
+        assert_eq!(key.id().len(), 32);
+        assert_ne!(key.id(), TenantKey::generate().id());
+        assert!(TenantKey::from_base58("3yZe7d").is_err());
+        assert!(TenantKey::from_base58("not base58 0OIl").is_err());
+        assert_eq!(format!("{:?}", key), "TenantKey(..)");
+
+        Ok(())
+    }
+}
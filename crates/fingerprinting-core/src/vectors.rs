@@ -0,0 +1,480 @@
+//!
+//! Known-answer test vectors of the canonical serialization and the fingerprint.
+//!
+//! Each vector holds the raw transaction, the schema and every intermediate value of the computation with
+//! the naive protocol of a fixed secret on BN256: the serialized components, the squeezed date time (the protocol input),
+//! the point `[k] H(d)`, the date time squeezed from it, the serialized data and the fingerprint. Other
+//! implementations of the client side reproduce the serialization and the squeezes from the same inputs.
+//!
+//! Field elements are hex of their 32 bytes little-endian representation, points hex of the compressed form.
+use anyhow::{anyhow, Error};
+use chrono::{DateTime, NaiveDate, Utc};
+use fingerprinting_types::currencies::Currency;
+use fingerprinting_types::{Direction, Money, RawTransactionBuilder};
+use halo2_axiom::halo2curves::bn256::{Fr, G1};
+use halo2_axiom::halo2curves::group::GroupEncoding;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::protocols::{FingerprintProtocol, NaiveProtocol};
+use crate::schema::{FingerprintSchema, SchemaComponent, TimeGranularity};
+use crate::{DomainSeparationTag, HashSqueeze, TransactionFingerprintData};
+
+/// Secret of the naive protocol the built-in vectors are computed with
+pub const VECTORS_SECRET: u64 = 42;
+
+/// Transaction as received by the client
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VectorInput {
+    pub bic: String,
+    /// Exact decimal amount, e.g. `1000.55`
+    pub amount: String,
+    /// ISO 4217 alphabetic code
+    pub currency: String,
+    /// RFC 3339 date time
+    pub date_time: String,
+    /// ISO 8601 date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iban: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// `debit` or `credit`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VectorSchema {
+    pub version: u32,
+    /// Components in the order of serialization, named as in the configuration
+    pub components: Vec<String>,
+    pub time_granularity: String,
+    /// Domain separation tag of the hash to curve
+    pub dst: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VectorComponent {
+    pub component: String,
+    pub bytes: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TestVector {
+    pub name: String,
+    pub input: VectorInput,
+    pub schema: VectorSchema,
+    /// Schema prefix of the serialized data
+    pub prefix: String,
+    /// Serialized components in the schema order, the date time one is the processed date time
+    pub components: Vec<VectorComponent>,
+    /// Date time bucket squeezed into the protocol input
+    pub squeezed_date_time: String,
+    /// `[k] H(squeezed_date_time)`
+    pub date_time_point: String,
+    /// Date time squeezed from the point, the output of the protocol
+    pub processed_date_time: String,
+    /// Prefix followed by the serialized components
+    pub serialized: String,
+    pub fingerprint: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TestVectorFile {
+    /// Secret of the naive protocol, a field element
+    pub secret: String,
+    pub vectors: Vec<TestVector>,
+}
+
+impl VectorInput {
+    fn transaction(&self) -> Result<TransactionFingerprintData<Fr>, Error> {
+        let currency = Currency::try_from(self.currency.as_str())
+            .map_err(|_| anyhow!("Unknown currency {}", self.currency))?;
+
+        let mut tx = RawTransactionBuilder::default();
+        tx.bic(self.bic.as_str())
+            .amount(Money::parse(&self.amount, currency)?)
+            .date_time(DateTime::parse_from_rfc3339(&self.date_time)?.with_timezone(&Utc));
+        if let Some(value_date) = &self.value_date {
+            tx.value_date(value_date.parse::<NaiveDate>()?);
+        }
+        if let Some(iban) = &self.iban {
+            tx.iban(iban.as_str());
+        }
+        if let Some(reference) = &self.reference {
+            tx.reference(reference.as_str());
+        }
+        if let Some(direction) = &self.direction {
+            tx.direction(direction.parse::<Direction>()?);
+        }
+
+        tx.build()?.try_into()
+    }
+}
+
+impl From<&FingerprintSchema> for VectorSchema {
+    fn from(schema: &FingerprintSchema) -> Self {
+        Self {
+            version: schema.version(),
+            components: schema
+                .components()
+                .iter()
+                .map(|component| component.name().to_string())
+                .collect(),
+            time_granularity: schema.time_granularity().name().to_string(),
+            dst: schema.dst().to_string(),
+        }
+    }
+}
+
+impl TryFrom<&VectorSchema> for FingerprintSchema {
+    type Error = Error;
+
+    fn try_from(schema: &VectorSchema) -> Result<Self, Self::Error> {
+        let dst = DomainSeparationTag::new(&schema.dst)?;
+
+        if schema.version == 1 {
+            let v1 = FingerprintSchema::v1().with_dst(dst);
+            if VectorSchema::from(&v1) != *schema {
+                return Err(anyhow!("Schema 1 doesn't match the built-in version 1"));
+            }
+
+            return Ok(v1);
+        }
+
+        schema
+            .components
+            .iter()
+            .try_fold(
+                FingerprintSchema::builder(schema.version),
+                |builder, component| Ok::<_, Error>(builder.component(component.parse()?)),
+            )?
+            .time_granularity(schema.time_granularity.parse()?)
+            .dst(dst)
+            .build()
+    }
+}
+
+impl TestVector {
+    /// Compute the vector of the transaction `input` fingerprinted with the `schema` via the naive protocol of the `secret`
+    pub async fn compute(
+        name: &str,
+        input: VectorInput,
+        schema: &FingerprintSchema,
+        secret: Fr,
+    ) -> Result<Self, Error> {
+        let protocol = NaiveProtocol::<G1>::new(secret).with_dst(schema.dst().clone());
+        let tx = input.transaction()?.with_schema(schema.clone());
+
+        let squeezed = tx.squeeze_date_time(0)?;
+        let point = protocol.process_point(squeezed).await?;
+        let processed = point.squeeze()?;
+        let serialized = tx.serialize_with_date_time(processed)?;
+
+        let components = schema
+            .components()
+            .iter()
+            .map(|component| {
+                let offset = schema
+                    .offset(*component)
+                    .expect("components of the schema have an offset");
+
+                VectorComponent {
+                    component: component.name().to_string(),
+                    bytes: hex::encode(&serialized[offset..offset + component.size()]),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            name: name.to_string(),
+            input,
+            schema: schema.into(),
+            prefix: hex::encode(schema.prefix()),
+            components,
+            squeezed_date_time: hex::encode(squeezed.to_bytes()),
+            date_time_point: hex::encode(point.to_bytes()),
+            processed_date_time: hex::encode(processed.to_bytes()),
+            serialized: hex::encode(&serialized),
+            fingerprint: hex::encode(serialized.squeeze()?.to_bytes()),
+        })
+    }
+
+    /// Recompute the vector via the naive protocol of the `secret`, the error names the first value that differs
+    pub async fn check(&self, secret: Fr) -> Result<(), Error> {
+        let schema = FingerprintSchema::try_from(&self.schema)?;
+        let expected = Self::compute(&self.name, self.input.clone(), &schema, secret).await?;
+
+        let mismatch = |field: &str, actual: &str, expected: &str| {
+            anyhow!(
+                "Vector {}: {} is {}, expected {}",
+                self.name,
+                field,
+                actual,
+                expected
+            )
+        };
+
+        if self.prefix != expected.prefix {
+            return Err(mismatch("prefix", &self.prefix, &expected.prefix));
+        }
+        if self.components.len() != expected.components.len() {
+            return Err(anyhow!(
+                "Vector {}: {} components, the schema has {}",
+                self.name,
+                self.components.len(),
+                expected.components.len()
+            ));
+        }
+        for (actual, expected) in self.components.iter().zip(&expected.components) {
+            if actual != expected {
+                return Err(mismatch(
+                    &format!("component {}", expected.component),
+                    &format!("{} {}", actual.component, actual.bytes),
+                    &expected.bytes,
+                ));
+            }
+        }
+
+        for (field, actual, expected) in [
+            (
+                "squeezed_date_time",
+                &self.squeezed_date_time,
+                &expected.squeezed_date_time,
+            ),
+            (
+                "date_time_point",
+                &self.date_time_point,
+                &expected.date_time_point,
+            ),
+            (
+                "processed_date_time",
+                &self.processed_date_time,
+                &expected.processed_date_time,
+            ),
+            ("serialized", &self.serialized, &expected.serialized),
+            ("fingerprint", &self.fingerprint, &expected.fingerprint),
+        ] {
+            if actual != expected {
+                return Err(mismatch(field, actual, expected));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TestVectorFile {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Secret of the naive protocol the vectors are computed with
+    pub fn secret(&self) -> Result<Fr, Error> {
+        let bytes: [u8; 32] = hex::decode(&self.secret)?
+            .try_into()
+            .map_err(|_| anyhow!("Secret must be 32 bytes"))?;
+        let secret = Fr::from_bytes(&bytes)
+            .into_option()
+            .ok_or(anyhow!("Secret {} is not a field element", self.secret))?;
+
+        Ok(secret)
+    }
+
+    /// Check all vectors, results are in the order of the vectors
+    pub async fn check(&self) -> Result<Vec<Result<(), Error>>, Error> {
+        let secret = self.secret()?;
+
+        let mut results = Vec::with_capacity(self.vectors.len());
+        for vector in &self.vectors {
+            results.push(vector.check(secret).await);
+        }
+
+        Ok(results)
+    }
+
+    /// Vectors of the built-in cases covering every component, granularity, minor unit and a custom tag
+    pub async fn generate() -> Result<Self, Error> {
+        let secret = Fr::from(VECTORS_SECRET);
+
+        let mut vectors = Vec::new();
+        for (name, input, schema) in cases()? {
+            vectors.push(TestVector::compute(name, input, &schema, secret).await?);
+        }
+
+        Ok(Self {
+            secret: hex::encode(secret.to_bytes()),
+            vectors,
+        })
+    }
+}
+
+fn input(bic: &str, amount: &str, currency: &str, date_time: &str) -> VectorInput {
+    VectorInput {
+        bic: bic.to_string(),
+        amount: amount.to_string(),
+        currency: currency.to_string(),
+        date_time: date_time.to_string(),
+        value_date: None,
+        iban: None,
+        reference: None,
+        direction: None,
+    }
+}
+
+fn cases() -> Result<Vec<(&'static str, VectorInput, FingerprintSchema)>, Error> {
+    let v1 = FingerprintSchema::v1();
+    let all_components = FingerprintSchema::builder(2)
+        .component(SchemaComponent::BankIdentifier)
+        .component(SchemaComponent::Amount)
+        .component(SchemaComponent::Currency)
+        .component(SchemaComponent::Iban)
+        .component(SchemaComponent::Reference)
+        .component(SchemaComponent::Direction)
+        .component(SchemaComponent::DateTime)
+        .build()?;
+    let minute = FingerprintSchema::builder(3)
+        .component(SchemaComponent::BankIdentifier)
+        .component(SchemaComponent::Amount)
+        .component(SchemaComponent::DateTime)
+        .time_granularity(TimeGranularity::Minute)
+        .build()?;
+    let day = FingerprintSchema::builder(4)
+        .component(SchemaComponent::DateTime)
+        .component(SchemaComponent::Currency)
+        .time_granularity(TimeGranularity::Day)
+        .build()?;
+
+    let identified = VectorInput {
+        iban: Some("lu28 0019 4006 4475 0000".to_string()),
+        reference: Some("EB6305C9-1F7F-49DE-AED0-16487C27B42D".to_string()),
+        direction: Some("debit".to_string()),
+        ..input("BCEELU21", "1000.55", "EUR", "2025-09-16T10:00:00Z")
+    };
+
+    Ok(vec![
+        (
+            "v1 whole amount",
+            input("BCEELU21", "100", "EUR", "2025-09-16T10:00:00Z"),
+            v1.clone(),
+        ),
+        (
+            "v1 branch code and cents",
+            input("DEUTDEFF500", "1000.55", "EUR", "2025-09-16T23:59:59Z"),
+            v1.clone(),
+        ),
+        (
+            "v1 currency without minor unit",
+            input("BOTKJPJT", "15000", "JPY", "2025-03-01T00:00:00Z"),
+            v1.clone(),
+        ),
+        (
+            "v1 three decimal places",
+            input("NBOBBHBM", "12.345", "BHD", "2025-06-30T12:34:56Z"),
+            v1.clone(),
+        ),
+        (
+            "v1 offset date time",
+            input("BCEELU21", "100", "EUR", "2025-09-17T01:30:00+02:00"),
+            v1.clone(),
+        ),
+        (
+            "v1 value date",
+            VectorInput {
+                value_date: Some("2025-09-18".to_string()),
+                ..input("BCEELU21", "100", "EUR", "2025-09-16T10:00:00Z")
+            },
+            v1,
+        ),
+        ("all components", identified.clone(), all_components.clone()),
+        (
+            "all components without account and reference",
+            VectorInput {
+                direction: Some("credit".to_string()),
+                ..input("BCEELU21", "1000.55", "EUR", "2025-09-16T10:00:00Z")
+            },
+            all_components,
+        ),
+        (
+            "minute granularity",
+            input("BCEELU21", "0.01", "USD", "2025-09-16T23:59:58Z"),
+            minute,
+        ),
+        (
+            "day granularity",
+            input("BCEELU21", "250", "CHF", "2025-12-31T18:45:00Z"),
+            day,
+        ),
+        (
+            "v1 custom domain separation tag",
+            input("BCEELU21", "100", "EUR", "2025-09-16T10:00:00Z"),
+            FingerprintSchema::v1().with_dst(DomainSeparationTag::new("ACME_PAYMENTS_V1")?),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Compact;
+
+    const GOLDEN_VECTORS: &str = include_str!("../test-vectors/fingerprints.json");
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_golden_vectors() -> Result<(), Error> {
+        let golden = TestVectorFile::from_json(GOLDEN_VECTORS)?;
+
+        for result in golden.check().await? {
+            result?;
+        }
+
+        // The file covers exactly the built-in cases
+        assert_eq!(golden, TestVectorFile::generate().await?);
+
+        // The first vector is the fingerprint of the first schema computed before the vectors existed
+        let fingerprint: [u8; 32] = hex::decode(&golden.vectors[0].fingerprint)?
+            .try_into()
+            .expect("fingerprint is 32 bytes");
+        assert_eq!(
+            Fr::from_bytes(&fingerprint).unwrap().compact(),
+            "7LDd3mXxkEA9L3jmS5SkhJBgUBrUkePM1oEqpLoytnUj"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_vector_mismatch() -> Result<(), Error> {
+        let mut golden = TestVectorFile::from_json(GOLDEN_VECTORS)?;
+
+        golden.vectors[0].fingerprint = golden.vectors[1].fingerprint.clone();
+        golden.vectors[1].components[0].bytes = hex::encode(b"DEUTLU");
+        golden.vectors[2].input.amount = "15001".to_string();
+        let last = golden.vectors.len() - 1;
+        golden.vectors[last].schema.dst = crate::HASH_TO_CURVE_PREFIX.to_string();
+
+        let results = golden.check().await?;
+        assert!(results[0]
+            .as_ref()
+            .is_err_and(|e| e.to_string().contains("fingerprint")));
+        assert!(results[1]
+            .as_ref()
+            .is_err_and(|e| e.to_string().contains("component bank_identifier")));
+        assert!(results[2].is_err());
+        assert!(results[3..last].iter().all(Result::is_ok));
+        assert!(results[last]
+            .as_ref()
+            .is_err_and(|e| e.to_string().contains("component date_time")));
+
+        // Another secret gives other points
+        golden.secret = hex::encode(Fr::from(43).to_bytes());
+        assert!(golden.check().await?[3].is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//!
+//! Public verification of fingerprints with pairings.
+//!
+//! The fingerprint is squeezed from the point `[k] H(d)`, where `k` is the group secret
+//! and `d` the squeezed date time component. Given the group key `[k] G2` anyone can check
+//! `e([k] H(d), G2) == e(H(d), [k] G2)` without learning `k`. The group key in G2 is derived
+//! from the public shares `[s_i] G2` of the agents, each checked against the share commitments in G1.
+use anyhow::{anyhow, Error};
+use halo2_axiom::halo2curves::bn256::Fr;
+use halo2_axiom::halo2curves::group::Group;
+
+use crate::curves::{decode_point, FingerprintCurve};
+use crate::secret_sharing::{SecretSharing, ShareCommitments};
+use crate::{DomainSeparationTag, TransactionFingerprintData};
+
+///
+/// Check that `point` is `[k] H(unblinded)` for the secret `k` of the `group_key` `[k] G2`,
+/// `H` hashes to curve with the `dst`
+pub fn verify_point<G: FingerprintCurve>(
+    unblinded: &Fr,
+    point: &G,
+    group_key: &G::G2,
+    dst: &DomainSeparationTag,
+) -> bool {
+    if !is_valid_g2::<G>(group_key) {
+        return false;
+    }
+
+    let curve_point = {
+        let hasher = G::hash_to_curve(dst.as_str());
+        hasher(&unblinded.to_bytes())
+    };
+
+    G::pairing_eq(point, &G::G2::generator(), &curve_point, group_key)
+}
+
+///
+/// Check that the public share `[s_i] G2` has the same exponent as `[s_i] G1`
+pub fn verify_public_share<G: FingerprintCurve>(public_share: &G, public_share_g2: &G::G2) -> bool {
+    is_valid_g2::<G>(public_share_g2)
+        && G::pairing_eq(
+            public_share,
+            &G::G2::generator(),
+            &G::generator(),
+            public_share_g2,
+        )
+}
+
+///
+/// Derive the group key `[k] G2` from the public shares `(i, [s_i] G2)` of at least `threshold` agents.
+/// Every share is checked against the `commitments`, so a single agent can't forge the group key.
+pub fn derive_group_key<G: FingerprintCurve>(
+    commitments: &ShareCommitments<G>,
+    public_shares: &[(usize, G::G2)],
+) -> Result<G::G2, Error> {
+    let threshold = commitments.threshold();
+
+    for (i, public_share_g2) in public_shares {
+        if !verify_public_share(&commitments.public_share(*i), public_share_g2) {
+            return Err(anyhow!(
+                "Public share of agent {} does not match the commitments",
+                i
+            ));
+        }
+    }
+
+    let mut indices = public_shares.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.len() != public_shares.len() || indices.len() < threshold {
+        return Err(anyhow!(
+            "Group key needs public shares of {} distinct agents, got {}",
+            threshold,
+            indices.len()
+        ));
+    }
+
+    let indices = &indices[..threshold];
+    let group_key = public_shares
+        .iter()
+        .filter(|(i, _)| indices.contains(i))
+        .fold(G::G2::identity(), |key, (i, public_share_g2)| {
+            key + *public_share_g2
+                * SecretSharing::<G::ScalarExt>::lagrange_coefficient(*i, indices)
+        });
+
+    // The shares are consistent with commitments, so this only fails for malformed commitments
+    if !verify_public_share(&commitments.group_key(), &group_key) {
+        return Err(anyhow!("Derived group key does not match the commitments"));
+    }
+
+    Ok(group_key)
+}
+
+///
+/// Check that `fingerprint` of the transaction was computed with the secret of the `group_key`,
+/// `point` is the unsqueezed `[k] H(d)` published alongside the fingerprint
+pub fn verify_fingerprint<G: FingerprintCurve>(
+    tx: &TransactionFingerprintData<Fr>,
+    fingerprint: &Fr,
+    point: &G,
+    group_key: &G::G2,
+) -> Result<(), Error> {
+    verify_neighbour_fingerprint(tx, 0, fingerprint, point, group_key)
+}
+
+///
+/// Check the `fingerprint` of the date time bucket of the transaction shifted by `offset` buckets,
+/// see `Fingerprint::complete_verifiable_neighbour_fingerprints`
+pub fn verify_neighbour_fingerprint<G: FingerprintCurve>(
+    tx: &TransactionFingerprintData<Fr>,
+    offset: i64,
+    fingerprint: &Fr,
+    point: &G,
+    group_key: &G::G2,
+) -> Result<(), Error> {
+    let unblinded = tx.squeeze_date_time(offset)?;
+
+    if !verify_point(&unblinded, point, group_key, tx.schema().dst()) {
+        return Err(anyhow!("Fingerprint point is not signed by the group key"));
+    }
+
+    let expected = tx.fingerprint_with_date_time(point.squeeze()?)?;
+
+    if expected != *fingerprint {
+        return Err(anyhow!("Fingerprint does not match the transaction"));
+    }
+
+    Ok(())
+}
+
+///
+/// Decode the compressed G2 point of the curve `G`, only points of the prime order subgroup (except identity) are accepted
+pub fn decode_g2<G: FingerprintCurve>(bytes: &[u8]) -> Result<G::G2, Error> {
+    decode_point::<G::G2>(bytes)
+        .map_err(|e| anyhow!("Invalid G2 point, {}", e))
+        .and_then(|point| {
+            Some(point).filter(is_valid_g2::<G>).ok_or(anyhow!(
+                "Invalid G2 point, value does not represent G2 point"
+            ))
+        })
+}
+
+/// Points received from the outside must be in the prime order subgroup of G2
+fn is_valid_g2<G: FingerprintCurve>(point: &G::G2) -> bool {
+    !bool::from(point.is_identity()) && G::is_torsion_free_g2(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fingerprint, NaiveProtocol};
+    use fingerprinting_types::test_utils::sample_transaction;
+    use halo2_axiom::arithmetic::Field;
+    use halo2_axiom::halo2curves::bls12_381;
+    use halo2_axiom::halo2curves::bn256::G1;
+    use rand_core::OsRng;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_fingerprint() -> Result<(), Error> {
+        check_verify_fingerprint::<G1>().await?;
+        check_verify_fingerprint::<bls12_381::G1>().await
+    }
+
+    async fn check_verify_fingerprint<G: FingerprintCurve>() -> Result<(), Error> {
+        let secret = G::ScalarExt::random(&mut OsRng);
+        let protocol = NaiveProtocol::<G>::new(secret);
+        let group_key = G::G2::generator() * secret;
+
+        let tx: TransactionFingerprintData<Fr> = sample_transaction().build()?.try_into()?;
+
+        let (fingerprint, point) = tx.complete_verifiable_fingerprint(&protocol).await?;
+        assert_eq!(fingerprint, tx.complete_fingerprint(&protocol).await?);
+
+        verify_fingerprint(&tx, &fingerprint, &point, &group_key)?;
+
+        // Another secret, another point or another fingerprint are rejected
+        let other_key = G::G2::generator() * G::ScalarExt::random(&mut OsRng);
+        assert!(verify_fingerprint(&tx, &fingerprint, &point, &other_key).is_err());
+        assert!(
+            verify_fingerprint(&tx, &fingerprint, &(point + G::generator()), &group_key).is_err()
+        );
+        assert!(verify_fingerprint(&tx, &(fingerprint + Fr::ONE), &point, &group_key).is_err());
+
+        // Neighbour fingerprints are verified against their own bucket
+        let neighbours = tx
+            .complete_verifiable_neighbour_fingerprints(&protocol, 1)
+            .await?;
+        assert_eq!(neighbours[1], (0, (fingerprint, point)));
+        for (offset, (fingerprint, point)) in &neighbours {
+            verify_neighbour_fingerprint(&tx, *offset, fingerprint, point, &group_key)?;
+        }
+        let (_, (next, next_point)) = &neighbours[2];
+        assert!(verify_fingerprint(&tx, next, next_point, &group_key).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_derive_group_key() -> Result<(), Error> {
+        check_derive_group_key::<G1>()?;
+        check_derive_group_key::<bls12_381::G1>()
+    }
+
+    fn check_derive_group_key<G: FingerprintCurve>() -> Result<(), Error> {
+        let secret = G::ScalarExt::random(&mut OsRng);
+        let sss = SecretSharing::generate(secret, 3, 5);
+        let commitments = sss.commitments::<G>();
+
+        let public_shares = (1..=5)
+            .map(|i| (i, G::G2::generator() * sss.get_share(i).unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            derive_group_key(&commitments, &public_shares[1..4])?,
+            G::G2::generator() * secret
+        );
+        assert_eq!(
+            derive_group_key(&commitments, &public_shares)?,
+            G::G2::generator() * secret
+        );
+
+        // Not enough shares
+        assert!(derive_group_key(&commitments, &public_shares[..2]).is_err());
+
+        // Forged share is detected
+        let mut forged = public_shares.clone();
+        forged[0].1 += G::G2::generator();
+        assert!(derive_group_key(&commitments, &forged).is_err());
+
+        // Duplicated agents do not count
+        let duplicated = vec![public_shares[0], public_shares[0], public_shares[1]];
+        assert!(derive_group_key(&commitments, &duplicated).is_err());
+
+        Ok(())
+    }
+}
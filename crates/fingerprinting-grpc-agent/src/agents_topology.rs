@@ -1,30 +1,75 @@
+use crate::ceremony::CeremonyChannels;
 use crate::net::pso::transaction_fingerprinting::fingerprint::agent::v1::{
-    CooperationRequest, CooperationServiceClient,
+    CooperationBatchRequest, CooperationRequest, CooperationServiceClient, ParametersRequest,
+    PublicShareRequest,
 };
+use crate::{cooperation_message, MAX_BATCH_SIZE};
 use anyhow::Error;
-use fingerprinting_core::AgentsTopology;
-use halo2_axiom::halo2curves::bn256::{Fr, G1Compressed, G1};
-use halo2_axiom::halo2curves::group::GroupEncoding;
+use fingerprinting_core::curves::{decode_point, FingerprintCurve};
+use fingerprinting_core::dleq::DleqProof;
+use fingerprinting_core::identity::AgentIdentity;
+use fingerprinting_core::tenant::TenantScope;
+use fingerprinting_core::verification::decode_g2;
+use fingerprinting_core::{AgentsTopology, PeerSelection};
+use futures::future::try_join_all;
+use halo2_axiom::halo2curves::bn256::G1;
 use pilota::Bytes;
 use rand::Rng;
 use std::collections::HashMap;
 use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::RwLock;
+use std::time::Duration;
 use volo::net::Address;
 
-pub struct GrpcAgentsTopology {
+pub struct GrpcAgentsTopology<G: FingerprintCurve = G1> {
     count: usize,
     threshold: usize,
-    members: HashMap<usize, Vec<CooperationServiceClient>>,
+    members: HashMap<usize, MemberClients>,
+    public_shares: HashMap<u64, HashMap<usize, G>>, // by generation, then by agent
+    selection: PeerSelection,
+    hedge_delay: Option<Duration>,
+    redundancy: usize,
+    channels: Option<CeremonyChannels>,
 }
 
-impl GrpcAgentsTopology {
+/// Clients of the resolved addresses of a member, resolved again while none is known
+struct MemberClients {
+    address: String,
+    clients: RwLock<Vec<CooperationServiceClient>>,
+}
+
+/// Protocol parameters an agent reports in `GetParameters`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentParameters {
+    pub dst: String,
+    pub curve: String,
+    /// Identifier of the tenant key, empty without one
+    pub tenant_key_id: String,
+}
+
+impl<G: FingerprintCurve> GrpcAgentsTopology<G> {
     pub fn new(count: usize, threshold: usize, members: Vec<(usize, String)>) -> Self {
-        let members: HashMap<usize, Vec<CooperationServiceClient>> = members
-            .iter()
-            .map(|(position, addr)| {
-                let clients_for_addr = GrpcAgentsTopology::build_client(addr).unwrap_or_default();
+        let members: HashMap<usize, MemberClients> = members
+            .into_iter()
+            .map(|(position, address)| {
+                // Members not resolvable yet (e.g. starting at the same time) are resolved on the next request
+                let clients = Self::build_client(&address).unwrap_or_else(|e| {
+                    log::warn!(
+                        "Address {} of agent {} is not resolvable yet: {}",
+                        address,
+                        position,
+                        e
+                    );
+                    Vec::new()
+                });
 
-                (*position, clients_for_addr)
+                (
+                    position,
+                    MemberClients {
+                        address,
+                        clients: RwLock::new(clients),
+                    },
+                )
             })
             .collect();
 
@@ -32,17 +77,61 @@ impl GrpcAgentsTopology {
             count,
             threshold,
             members,
+            public_shares: HashMap::new(),
+            selection: PeerSelection::new(),
+            hedge_delay: None,
+            redundancy: 0,
+            channels: None,
         }
     }
 
-    fn build_client(
-        remote_address: &String,
-    ) -> Result<Vec<CooperationServiceClient>, anyhow::Error> {
+    /// Register public share commitments `[s_i] G` of the members for the secret `generation`.
+    /// Responses are accepted only with a valid DLEQ proof, members without a public share can't be verified
+    /// and their responses are rejected.
+    pub fn with_public_shares(mut self, generation: u64, public_shares: HashMap<usize, G>) -> Self {
+        self.public_shares.insert(generation, public_shares);
+        self
+    }
+
+    /// Ask only `threshold - 1` fastest agents and the next one whenever no response arrives
+    /// within `hedge_delay`, instead of broadcasting every request to all agents.
+    pub fn with_hedge_delay(mut self, hedge_delay: Duration) -> Self {
+        self.hedge_delay = Some(hedge_delay);
+        self
+    }
+
+    /// Ask `redundancy` more agents than the threshold requires and check that all responses are consistent,
+    /// agents with inconsistent responses are excluded and fingerprints are never built from them.
+    pub fn with_redundancy(mut self, redundancy: usize) -> Self {
+        self.redundancy = redundancy;
+        self
+    }
+
+    /// Authenticate requests for tenants other than the default one as the agent `agent_id` with its `identity`
+    /// to the `members` of their public identities, members serve only the default tenant to others
+    pub fn with_identity(
+        mut self,
+        agent_id: usize,
+        identity: &AgentIdentity<G>,
+        members: impl IntoIterator<Item = (usize, G)>,
+    ) -> Result<Self, Error> {
+        self.channels = Some(CeremonyChannels::new(agent_id, identity, members)?);
+        Ok(self)
+    }
+
+    fn build_client(remote_address: &str) -> Result<Vec<CooperationServiceClient>, anyhow::Error> {
         let clients = remote_address
             .to_socket_addrs()?
-            .map(GrpcAgentsTopology::get_client)
+            .map(Self::get_client)
             .collect::<Vec<_>>();
 
+        if clients.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Address {} resolves to no socket address",
+                remote_address
+            ));
+        }
+
         Ok(clients)
     }
 
@@ -54,9 +143,192 @@ impl GrpcAgentsTopology {
         .address(Address::from(addr))
         .build()
     }
+
+    /// Client of a random address of the `agent`, the address is resolved again while it resolved to none,
+    /// so that members unreachable at the start are retried by the next request
+    fn client(&self, agent: usize) -> Result<CooperationServiceClient, Error> {
+        if agent == 0 || agent > self.count {
+            return Err(anyhow::anyhow!(
+                "Invalid agent number, should be in range 1 to {}",
+                self.count
+            ));
+        }
+
+        let member = self
+            .members
+            .get(&agent)
+            .ok_or(anyhow::anyhow!("No clients for agent {}", agent))?;
+        let poisoned = || anyhow::anyhow!("Clients of agent {} are not available", agent);
+
+        {
+            let clients = member.clients.read().map_err(|_| poisoned())?;
+            if !clients.is_empty() {
+                return Ok(clients[rand::thread_rng().gen_range(0..clients.len())].clone());
+            }
+        }
+
+        let clients = Self::build_client(&member.address)
+            .map_err(|e| anyhow::anyhow!("No clients for agent {}: {}", agent, e))?;
+        let client = clients[rand::thread_rng().gen_range(0..clients.len())].clone();
+        *member.clients.write().map_err(|_| poisoned())? = clients;
+
+        Ok(client)
+    }
+
+    /// Public shares `[s_i] G2` of the `generation` from all reachable members.
+    /// Shares are only checked to be valid points, see `fingerprinting_core::verification::derive_group_key`.
+    pub async fn obtain_public_shares_g2(&self, generation: u64) -> Vec<(usize, G::G2)> {
+        let mut members = self.members.keys().copied().collect::<Vec<_>>();
+        members.sort_unstable();
+
+        let requests = members.into_iter().map(|agent| async move {
+            self.obtain_public_share_g2(agent, generation)
+                .await
+                .inspect_err(|e| {
+                    log::warn!(
+                        "Failed to obtain public share of agent {} (generation {}): {}",
+                        agent,
+                        generation,
+                        e
+                    );
+                })
+                .ok()
+                .map(|public_share_g2| (agent, public_share_g2))
+        });
+
+        futures::future::join_all(requests)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    async fn obtain_public_share_g2(&self, agent: usize, generation: u64) -> Result<G::G2, Error> {
+        let response = self
+            .client(agent)?
+            .get_public_share(PublicShareRequest {
+                generation,
+                _unknown_fields: Default::default(),
+            })
+            .await?
+            .into_inner();
+
+        if response.generation != generation {
+            return Err(anyhow::anyhow!(
+                "Agent {} returned public share of generation {}, requested {}",
+                agent,
+                response.generation,
+                generation
+            ));
+        }
+
+        decode_g2::<G>(&response.public_share_g2)
+            .map_err(|e| anyhow::anyhow!("Invalid public share from agent {}: {}", agent, e))
+    }
+
+    /// Parameters of the members which responded, ordered by the agent number
+    pub async fn obtain_parameters(&self) -> Vec<(usize, AgentParameters)> {
+        let mut members = self.members.keys().copied().collect::<Vec<_>>();
+        members.sort_unstable();
+
+        let requests = members.into_iter().map(|agent| async move {
+            self.obtain_agent_parameters(agent)
+                .await
+                .inspect_err(|e| {
+                    log::warn!("Failed to obtain parameters of agent {}: {}", agent, e);
+                })
+                .ok()
+                .map(|parameters| (agent, parameters))
+        });
+
+        futures::future::join_all(requests)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    async fn obtain_agent_parameters(&self, agent: usize) -> Result<AgentParameters, Error> {
+        let response = self
+            .client(agent)?
+            .get_parameters(ParametersRequest {
+                _unknown_fields: Default::default(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(AgentParameters {
+            dst: response.dst.to_string(),
+            curve: response.curve.to_string(),
+            tenant_key_id: response.tenant_key_id.to_string(),
+        })
+    }
+
+    /// Members of the topology without a public share of the `generation`
+    pub fn members_without_public_share(&self, generation: u64) -> Vec<usize> {
+        let mut members = self
+            .members
+            .keys()
+            .copied()
+            .filter(|agent| {
+                self.public_shares
+                    .get(&generation)
+                    .is_none_or(|shares| !shares.contains_key(agent))
+            })
+            .collect::<Vec<_>>();
+        members.sort_unstable();
+
+        members
+    }
+
+    /// Number of this agent and the authentication of the request with the `blinded_values` to the `agent`,
+    /// requests for the default tenant or without an identity of this agent are not authenticated
+    fn authentication<T: AsRef<[u8]>>(
+        &self,
+        agent: usize,
+        generation: u64,
+        scope: &TenantScope<G::ScalarExt>,
+        blinded_values: &[T],
+    ) -> Result<(u64, Bytes), Error> {
+        match &self.channels {
+            Some(channels) if !scope.tenant().is_default() => {
+                let requester = channels.agent() as u64;
+                let message = cooperation_message(
+                    generation,
+                    scope.tenant().as_str(),
+                    requester,
+                    blinded_values,
+                );
+
+                Ok((
+                    requester,
+                    Bytes::from(channels.channel(agent)?.authenticate(&message)),
+                ))
+            }
+            _ => Ok((0, Bytes::new())),
+        }
+    }
+
+    /// Public share `[s_i * t] G` of the `agent` for the tenant `scope`
+    fn public_share(
+        &self,
+        agent: usize,
+        generation: u64,
+        scope: &TenantScope<G::ScalarExt>,
+    ) -> Result<G, Error> {
+        self.public_shares
+            .get(&generation)
+            .and_then(|shares| shares.get(&agent))
+            .map(|public_share| *public_share * scope.scalar())
+            .ok_or(anyhow::anyhow!(
+                "No public share of agent {} (generation {}), shard can't be verified",
+                agent,
+                generation
+            ))
+    }
 }
 
-impl AgentsTopology<Fr, G1> for GrpcAgentsTopology {
+impl<G: FingerprintCurve> AgentsTopology<G::ScalarExt, G> for GrpcAgentsTopology<G> {
     fn count(&self) -> usize {
         self.count
     }
@@ -65,49 +337,353 @@ impl AgentsTopology<Fr, G1> for GrpcAgentsTopology {
         self.threshold
     }
 
+    fn select_agents(&self, agent: usize) -> Vec<usize> {
+        self.selection
+            .order((1..=self.count).filter(|i| *i != agent))
+    }
+
+    fn hedge_delay(&self) -> Option<Duration> {
+        self.hedge_delay
+    }
+
+    fn report(&self, agent: usize, latency: Duration, success: bool) {
+        self.selection.record(agent, latency, success);
+    }
+
+    fn redundancy(&self) -> usize {
+        self.redundancy
+    }
+
+    fn report_faulty(&self, agent: usize) {
+        self.selection.exclude(agent);
+    }
+
     async fn obtain_shard(
         &self,
         agent: usize,
         generation: u64,
-        blinded_value: G1,
-    ) -> Result<(usize, G1), Error> {
-        if agent == 0 || agent > self.count {
-            return Err(anyhow::anyhow!(
-                "Invalid agent number, should be in range 1 to {}",
-                self.count
-            ));
-        }
+        scope: &TenantScope<G::ScalarExt>,
+        blinded_value: G,
+    ) -> Result<(usize, G), Error> {
+        let client = self.client(agent)?;
+        let public_share = self.public_share(agent, generation, scope)?;
 
-        let clients = self
-            .members
-            .get(&agent)
-            .ok_or(anyhow::anyhow!("No clients for agent {}", agent))?;
-        let client = rand::thread_rng().gen_range(0..clients.len());
-        let client = &clients[client];
-
-        let bytes = blinded_value.to_bytes();
+        let bytes = Bytes::copy_from_slice(blinded_value.to_bytes().as_ref());
+        let (requester, authentication) =
+            self.authentication(agent, generation, scope, &[&bytes])?;
 
         let exponent = client
             .compute_exponent(CooperationRequest {
                 generation,
-                blinded_value: Bytes::copy_from_slice(bytes.as_ref()),
+                agent: requester,
+                tenant: scope.tenant().as_str().to_string().into(),
+                blinded_value: bytes,
+                authentication,
                 _unknown_fields: Default::default(),
             })
             .await?;
 
-        let exponent = exponent.into_inner().blinded_exponent;
-        let mut exponent_point = G1Compressed::default();
+        let response = exponent.into_inner();
+
+        check_generation(agent, generation, response.generation)?;
 
-        // todo verify that received bytes are exactly 32 bytes
-        exponent_point.as_mut().copy_from_slice(exponent.as_ref());
-        let exponent_point =
-            G1::from_bytes(&exponent_point)
-                .into_option()
-                .ok_or(anyhow::anyhow!(
-                    "Invalid exponent point, agent {} returned wrong value",
-                    agent
-                ))?;
+        let exponent_point = verify_exponent(
+            agent,
+            &public_share,
+            blinded_value,
+            &response.blinded_exponent,
+            &response.proof_of_computation,
+        )?;
 
         Ok((agent, exponent_point))
     }
+
+    async fn obtain_shards(
+        &self,
+        agent: usize,
+        generation: u64,
+        scope: &TenantScope<G::ScalarExt>,
+        blinded_values: &[G],
+    ) -> Result<(usize, Vec<G>), Error> {
+        let client = &self.client(agent)?;
+        let public_share = &self.public_share(agent, generation, scope)?;
+
+        let batches = blinded_values
+            .chunks(MAX_BATCH_SIZE)
+            .map(|batch| async move {
+                let blinded_bytes = batch
+                    .iter()
+                    .map(|value| Bytes::copy_from_slice(value.to_bytes().as_ref()))
+                    .collect::<Vec<_>>();
+                let (requester, authentication) =
+                    self.authentication(agent, generation, scope, &blinded_bytes)?;
+
+                let response = client
+                    .compute_exponent_batch(CooperationBatchRequest {
+                        generation,
+                        agent: requester,
+                        tenant: scope.tenant().as_str().to_string().into(),
+                        blinded_values: blinded_bytes,
+                        authentication,
+                        _unknown_fields: Default::default(),
+                    })
+                    .await?
+                    .into_inner();
+
+                check_generation(agent, generation, response.generation)?;
+
+                if response.blinded_exponents.len() != batch.len()
+                    || response.proofs_of_computation.len() != batch.len()
+                {
+                    return Err(anyhow::anyhow!(
+                        "Agent {} returned {} exponents and {} proofs for batch of {}",
+                        agent,
+                        response.blinded_exponents.len(),
+                        response.proofs_of_computation.len(),
+                        batch.len()
+                    ));
+                }
+
+                batch
+                    .iter()
+                    .zip(&response.blinded_exponents)
+                    .zip(&response.proofs_of_computation)
+                    .map(|((blinded_value, exponent), proof)| {
+                        verify_exponent(agent, public_share, *blinded_value, exponent, proof)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            });
+
+        let shards = try_join_all(batches).await?.into_iter().flatten().collect();
+
+        Ok((agent, shards))
+    }
+}
+
+fn check_generation(agent: usize, requested: u64, computed: u64) -> Result<(), Error> {
+    if computed != requested {
+        return Err(anyhow::anyhow!(
+            "Agent {} computed exponent for generation {}, requested {}",
+            agent,
+            computed,
+            requested
+        ));
+    }
+
+    Ok(())
+}
+
+/// Decode the exponent returned by the `agent` and check its proof of computation against
+/// the `public_share`
+fn verify_exponent<G: FingerprintCurve>(
+    agent: usize,
+    public_share: &G,
+    blinded_value: G,
+    exponent: &[u8],
+    proof_of_computation: &[u8],
+) -> Result<G, Error> {
+    let exponent_point = decode_point::<G>(exponent)
+        .map_err(|e| anyhow::anyhow!("Invalid exponent point from agent {}: {}", agent, e))?;
+
+    let proof = DleqProof::<G::ScalarExt>::from_bytes(proof_of_computation)
+        .map_err(|e| anyhow::anyhow!("Invalid proof of computation from agent {}: {}", agent, e))?;
+
+    if !proof.verify(*public_share, blinded_value, exponent_point) {
+        return Err(anyhow::anyhow!(
+            "Proof of computation from agent {} is not valid, shard rejected",
+            agent
+        ));
+    }
+
+    Ok(exponent_point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::pso::transaction_fingerprinting::fingerprint::agent::v1::CooperationServiceServer;
+    use crate::test_utils::local_listener;
+    use crate::CooperationAgentService;
+    use fingerprinting_core::tenant::{Tenant, TenantKey};
+    use halo2_axiom::halo2curves::bn256::Fr;
+    use halo2_axiom::halo2curves::ff::Field;
+    use halo2_axiom::halo2curves::group::Group;
+    use rand::rngs::OsRng;
+    use volo_grpc::server::{Server, ServiceBuilder};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unverifiable_shards_rejected() -> Result<(), Error> {
+        let secret_shard = Fr::random(OsRng);
+
+        // Cooperation service of agent 2 on a free local port
+        let (addr, listener) = local_listener()?;
+        tokio::spawn(
+            Server::new()
+                .add_service(
+                    ServiceBuilder::new(CooperationServiceServer::new(
+                        CooperationAgentService::<G1>::new(secret_shard),
+                    ))
+                    .build(),
+                )
+                .run(listener),
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let topology = || GrpcAgentsTopology::<G1>::new(2, 2, vec![(2, addr.to_string())]);
+        let scope = TenantScope::default();
+        let blinded_value = G1::random(OsRng);
+
+        // Shard verified against the public share of the member
+        let verified =
+            topology().with_public_shares(0, HashMap::from([(2, G1::generator() * secret_shard)]));
+        assert!(verified.members_without_public_share(0).is_empty());
+        assert_eq!(
+            verified.obtain_shard(2, 0, &scope, blinded_value).await?.1,
+            blinded_value * secret_shard
+        );
+
+        // Shard of a member with another public share is rejected
+        let mismatched = topology().with_public_shares(0, HashMap::from([(2, G1::generator())]));
+        assert!(mismatched
+            .obtain_shard(2, 0, &scope, blinded_value)
+            .await
+            .is_err());
+
+        // Shard of a member without public share can't be verified and is rejected
+        let unverifiable = topology();
+        assert_eq!(unverifiable.members_without_public_share(0), vec![2]);
+        assert!(unverifiable
+            .obtain_shard(2, 0, &scope, blinded_value)
+            .await
+            .is_err());
+        assert!(unverifiable
+            .obtain_shards(2, 0, &scope, &[blinded_value])
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unresolvable_member_not_fatal() -> Result<(), Error> {
+        let (addr, listener) = local_listener()?;
+        tokio::spawn(
+            Server::new()
+                .add_service(
+                    ServiceBuilder::new(CooperationServiceServer::new(
+                        CooperationAgentService::<G1>::new(Fr::random(OsRng)),
+                    ))
+                    .build(),
+                )
+                .run(listener),
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // Address of agent 2 doesn't resolve, requests to it fail instead of panicking
+        let topology = GrpcAgentsTopology::<G1>::new(
+            3,
+            2,
+            vec![(2, "agent-2".to_string()), (3, addr.to_string())],
+        );
+        let error = topology.client(2).err().map(|e| e.to_string());
+        assert!(error.is_some_and(|e| e.contains("No clients for agent 2")));
+        assert!(topology
+            .obtain_shard(2, 0, &TenantScope::default(), G1::random(OsRng))
+            .await
+            .is_err());
+
+        // Other members still respond
+        let parameters = topology.obtain_parameters().await;
+        assert_eq!(
+            parameters
+                .iter()
+                .map(|(agent, _)| *agent)
+                .collect::<Vec<_>>(),
+            vec![3]
+        );
+
+        // Every round of publishing the group key obtains the public shares in G2 of the reachable members
+        for _ in 0..2 {
+            let public_shares = topology.obtain_public_shares_g2(0).await;
+            assert_eq!(
+                public_shares
+                    .iter()
+                    .map(|(agent, _)| *agent)
+                    .collect::<Vec<_>>(),
+                vec![3]
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tenant_requests_authenticated() -> Result<(), Error> {
+        let secret_shard = Fr::random(OsRng);
+        let key = TenantKey::generate();
+        let agent_1 = AgentIdentity::<G1>::generate();
+        let agent_2 = AgentIdentity::<G1>::generate();
+
+        // Agent 2 serves tenants only to agent 1
+        let (addr, listener) = local_listener()?;
+        tokio::spawn(
+            Server::new()
+                .add_service(
+                    ServiceBuilder::new(CooperationServiceServer::new(
+                        CooperationAgentService::<G1>::new(secret_shard)
+                            .with_tenant_key(key.clone())
+                            .with_members(2, &agent_2, [(1, agent_1.public())])?,
+                    ))
+                    .build(),
+                )
+                .run(listener),
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let topology = || {
+            GrpcAgentsTopology::<G1>::new(2, 2, vec![(2, addr.to_string())])
+                .with_public_shares(0, HashMap::from([(2, G1::generator() * secret_shard)]))
+        };
+        let scope = key.scope::<Fr>(&Tenant::new("retail")?);
+        let blinded_values = [G1::random(OsRng), G1::random(OsRng)];
+
+        let member = topology().with_identity(1, &agent_1, [(2, agent_2.public())])?;
+        assert_eq!(
+            member
+                .obtain_shard(2, 0, &scope, blinded_values[0])
+                .await?
+                .1,
+            blinded_values[0] * (secret_shard * scope.scalar())
+        );
+        assert_eq!(
+            member.obtain_shards(2, 0, &scope, &blinded_values).await?.1,
+            blinded_values
+                .iter()
+                .map(|value| value * (secret_shard * scope.scalar()))
+                .collect::<Vec<_>>()
+        );
+
+        // Agents without an identity known to agent 2 get only the default tenant
+        let anonymous = topology();
+        assert!(anonymous
+            .obtain_shard(2, 0, &scope, blinded_values[0])
+            .await
+            .is_err());
+        assert!(anonymous
+            .obtain_shard(2, 0, &TenantScope::default(), blinded_values[0])
+            .await
+            .is_ok());
+
+        let impostor = topology().with_identity(
+            1,
+            &AgentIdentity::<G1>::generate(),
+            [(2, agent_2.public())],
+        )?;
+        assert!(impostor
+            .obtain_shards(2, 0, &scope, &blinded_values)
+            .await
+            .is_err());
+
+        Ok(())
+    }
 }
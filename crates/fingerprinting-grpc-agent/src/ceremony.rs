@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Error};
+use fingerprinting_core::curves::FingerprintCurve;
+use fingerprinting_core::identity::{AgentIdentity, PeerChannel};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::ToSocketAddrs;
+use std::time::Duration;
+use volo::net::Address;
+use volo_grpc::{Code, Response, Status};
+
+/// Agent taking part in a ceremony
+#[derive(Debug, Clone)]
+pub struct CeremonyMember<G: FingerprintCurve> {
+    pub agent_id: usize,
+    pub address: String,
+    /// Public identity `[x_i] G` authenticating messages of the agent
+    pub identity: G,
+}
+
+/// Channels of the agent with other agents of the ceremony (or the topology) by their agent number
+#[derive(Debug, Clone)]
+pub(crate) struct CeremonyChannels {
+    agent: usize,
+    channels: HashMap<usize, PeerChannel>,
+}
+
+impl CeremonyChannels {
+    pub(crate) fn new<G: FingerprintCurve>(
+        agent: usize,
+        identity: &AgentIdentity<G>,
+        peers: impl IntoIterator<Item = (usize, G)>,
+    ) -> Result<Self, Error> {
+        let channels = peers
+            .into_iter()
+            .map(|(peer, public_identity)| {
+                identity
+                    .channel(&public_identity)
+                    .map(|channel| (peer, channel))
+                    .map_err(|e| anyhow!("Identity of agent {} is not usable, {}", peer, e))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self { agent, channels })
+    }
+
+    /// Agent number of this agent
+    pub(crate) fn agent(&self) -> usize {
+        self.agent
+    }
+
+    pub(crate) fn channel(&self, peer: usize) -> Result<&PeerChannel, Error> {
+        self.channels
+            .get(&peer)
+            .ok_or(anyhow!("No identity configured for agent {}", peer))
+    }
+
+    /// Channel with the `peer` whose `message` carries the `authentication` tag
+    pub(crate) fn authenticated<T: AsRef<[u8]>>(
+        &self,
+        peer: usize,
+        message: &[T],
+        authentication: &[u8],
+    ) -> Result<&PeerChannel, Box<Status>> {
+        let channel = self
+            .channels
+            .get(&peer)
+            .ok_or_else(|| Status::new(Code::Unauthenticated, format!("Unknown agent {}", peer)))?;
+
+        if !channel.verify(message, authentication) {
+            return Err(Box::new(Status::new(
+                Code::Unauthenticated,
+                format!("Message of agent {} is not authentic", peer),
+            )));
+        }
+
+        Ok(channel)
+    }
+}
+
+/// Send a ceremony message (`what`) to the `member` until it answers. Unreachable members and members
+/// not ready yet are retried every `retry_interval`, rejections of the member fail immediately.
+pub(crate) async fn send_to_member<T, F, Fut>(
+    member: usize,
+    what: &str,
+    retry_interval: Duration,
+    send: F,
+) -> Result<T, Error>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Response<T>, Status>>,
+{
+    loop {
+        match send().await {
+            Ok(response) => return Ok(response.into_inner()),
+            Err(status) if is_rejection(&status) => {
+                return Err(anyhow!(
+                    "Agent {} rejected the {}: {}",
+                    member,
+                    what,
+                    status.message()
+                ));
+            }
+            Err(status) => {
+                log::warn!(
+                    "Agent {} is not available for the {} ({}), retrying in {:?}",
+                    member,
+                    what,
+                    status.message(),
+                    retry_interval
+                );
+                tokio::time::sleep(retry_interval).await;
+            }
+        }
+    }
+}
+
+/// Errors of the receiving agent which don't go away with retries
+fn is_rejection(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::InvalidArgument | Code::FailedPrecondition | Code::Unauthenticated
+    )
+}
+
+/// Resolved address of a ceremony member
+pub(crate) fn member_address(remote_address: &str) -> Result<Address, Error> {
+    remote_address
+        .to_socket_addrs()?
+        .next()
+        .map(Address::from)
+        .ok_or(anyhow!("Cannot resolve address {}", remote_address))
+}
@@ -0,0 +1,734 @@
+use crate::ceremony::{member_address, send_to_member, CeremonyChannels, CeremonyMember};
+use crate::net::pso::transaction_fingerprinting::fingerprint::agent::v1::{
+    KeyGenerationService, KeyGenerationServiceClient, KeyGenerationServiceClientBuilder,
+    SubmitDealingRequest, SubmitDealingResponse, TranscriptRequest, TranscriptResponse,
+};
+use anyhow::{anyhow, Error};
+use fingerprinting_core::curves::{decode_point, decode_scalar, FingerprintCurve};
+use fingerprinting_core::dkg::DkgParticipant;
+use fingerprinting_core::identity::AgentIdentity;
+use fingerprinting_core::secret_sharing::ShareCommitments;
+use futures::future::try_join_all;
+use halo2_axiom::halo2curves::bn256::G1;
+use halo2_axiom::halo2curves::ff::PrimeField;
+use halo2_axiom::halo2curves::CurveExt;
+use pilota::{Bytes, FastStr};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use volo_grpc::{Code, Request, Response, Status};
+
+type SharedParticipant<G> = Arc<Mutex<DkgParticipant<<G as CurveExt>::ScalarExt, G>>>;
+
+/// Server side of the key generation ceremony: receives and verifies dealings of other agents
+/// and reports the digest of received commitments to them
+pub struct KeyGenerationAgentService<G: FingerprintCurve = G1> {
+    ceremony_id: String,
+    generation: u64,
+    participant: SharedParticipant<G>,
+    channels: Arc<CeremonyChannels>,
+    // members which obtained our transcript digest
+    confirmed: Arc<Mutex<HashSet<usize>>>,
+    progress: Arc<Notify>,
+}
+
+impl<G: FingerprintCurve> KeyGenerationAgentService<G> {
+    fn check_ceremony(&self, ceremony_id: &str, generation: u64) -> Result<(), Box<Status>> {
+        if ceremony_id != self.ceremony_id {
+            return Err(Box::new(Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "Agent takes part in ceremony {}, request is for {}",
+                    self.ceremony_id, ceremony_id
+                ),
+            )));
+        }
+
+        if generation != self.generation {
+            return Err(Box::new(Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "Agent generates secret generation {}, request is for {}",
+                    self.generation, generation
+                ),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl<G: FingerprintCurve> KeyGenerationService for KeyGenerationAgentService<G> {
+    async fn submit_dealing(
+        &self,
+        req: Request<SubmitDealingRequest>,
+    ) -> Result<Response<SubmitDealingResponse>, Status> {
+        let request = req.into_inner();
+
+        self.check_ceremony(&request.ceremony_id, request.generation)
+            .map_err(|e| *e)?;
+
+        let dealer = usize::try_from(request.dealer)
+            .map_err(|_| Status::new(Code::InvalidArgument, "Invalid dealer number"))?;
+
+        let channel = self
+            .channels
+            .authenticated(dealer, &dealing_message(&request), &request.authentication)
+            .map_err(|e| *e)?;
+
+        if request.receiver != self.channels.agent() as u64 {
+            return Err(Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "Dealing is for agent {}, this is agent {}",
+                    request.receiver,
+                    self.channels.agent()
+                ),
+            ));
+        }
+
+        let commitments = request
+            .commitments
+            .iter()
+            .map(|commitment| decode_point::<G>(commitment))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                Status::new(
+                    Code::InvalidArgument,
+                    format!("Invalid commitments of {}, {}", G::CURVE.name(), e),
+                )
+            })?;
+
+        let share = channel
+            .decrypt(
+                &share_context(
+                    &request.ceremony_id,
+                    request.generation,
+                    request.dealer,
+                    request.receiver,
+                ),
+                &request.nonce,
+                &request.share,
+            )
+            .and_then(|share| decode_scalar(&share))
+            .map_err(|e| {
+                Status::new(
+                    Code::InvalidArgument,
+                    format!("Invalid share of {}, {}", G::CURVE.name(), e),
+                )
+            })?;
+
+        let mut participant = self
+            .participant
+            .lock()
+            .map_err(|_| Status::new(Code::Internal, "Key generation state is not available"))?;
+
+        participant
+            .receive(dealer, ShareCommitments::new(commitments), share)
+            .map_err(|e| {
+                log::error!("Complaint against dealer {}: {}", dealer, e);
+                Status::new(Code::InvalidArgument, e.to_string())
+            })?;
+
+        log::info!(
+            "Accepted dealing from agent {}, still waiting for {:?}",
+            dealer,
+            participant.missing()
+        );
+
+        let receiver = participant.index() as u64;
+        drop(participant);
+
+        self.progress.notify_waiters();
+
+        Ok(Response::new(SubmitDealingResponse {
+            receiver,
+            _unknown_fields: Default::default(),
+        }))
+    }
+
+    async fn get_transcript(
+        &self,
+        req: Request<TranscriptRequest>,
+    ) -> Result<Response<TranscriptResponse>, Status> {
+        let request = req.into_inner();
+
+        self.check_ceremony(&request.ceremony_id, request.generation)
+            .map_err(|e| *e)?;
+
+        let agent = usize::try_from(request.agent)
+            .map_err(|_| Status::new(Code::InvalidArgument, "Invalid agent number"))?;
+
+        let channel = self
+            .channels
+            .authenticated(
+                agent,
+                &transcript_request_message(&request.ceremony_id, request.generation, agent),
+                &request.authentication,
+            )
+            .map_err(|e| *e)?;
+
+        let digest = self
+            .participant
+            .lock()
+            .map_err(|_| Status::new(Code::Internal, "Key generation state is not available"))?
+            .transcript_digest()
+            .map_err(|e| Status::new(Code::Unavailable, e.to_string()))?;
+
+        let responder = self.channels.agent();
+        let authentication = channel.authenticate(&transcript_message(
+            &request.ceremony_id,
+            request.generation,
+            responder,
+            agent,
+            &digest,
+        ));
+
+        self.confirmed
+            .lock()
+            .map_err(|_| Status::new(Code::Internal, "Key generation state is not available"))?
+            .insert(agent);
+        self.progress.notify_waiters();
+
+        Ok(Response::new(TranscriptResponse {
+            agent: responder as u64,
+            digest: Bytes::copy_from_slice(&digest),
+            authentication: authentication.into(),
+            _unknown_fields: Default::default(),
+        }))
+    }
+}
+
+/// Dealerless key generation (or proactive refresh) between cooperating agents.
+///
+/// Every agent runs the ceremony with the same `ceremony_id`, serves [`KeyGenerationAgentService`]
+/// and delivers its own dealing to all other members. Dealings are authenticated and the shares encrypted
+/// with the channel keys of the agent identities. Once all dealings are received agents compare digests of
+/// the received commitments, so a dealer sending different commitments to different agents aborts the ceremony.
+/// As a result each agent gets its secret shard of the `generation` and the group commitments,
+/// nobody learns the master secret.
+pub struct KeyGenerationCeremony<G: FingerprintCurve = G1> {
+    ceremony_id: String,
+    generation: u64,
+    participant: SharedParticipant<G>,
+    channels: Arc<CeremonyChannels>,
+    confirmed: Arc<Mutex<HashSet<usize>>>,
+    progress: Arc<Notify>,
+    members: HashMap<usize, String>,
+}
+
+impl<G: FingerprintCurve> KeyGenerationCeremony<G> {
+    /// Ceremony producing secret shards of the `generation`: initial key generation when
+    /// `participant` is created with [`DkgParticipant::new`], proactive refresh with [`DkgParticipant::refresh`]
+    pub fn new(
+        ceremony_id: String,
+        generation: u64,
+        participant: DkgParticipant<G::ScalarExt, G>,
+        identity: &AgentIdentity<G>,
+        members: Vec<CeremonyMember<G>>,
+    ) -> Result<Self, Error> {
+        if participant.is_refresh() == (generation == 0) {
+            return Err(anyhow!(
+                "Generation 0 is produced by the key generation, further generations by the refresh"
+            ));
+        }
+
+        let agent = participant.index();
+        let agents = participant.participants();
+
+        let expected = (1..=agents).filter(|i| *i != agent);
+        if let Some(missing) = expected
+            .clone()
+            .find(|member| !members.iter().any(|m| m.agent_id == *member))
+        {
+            return Err(anyhow!("No address configured for agent {}", missing));
+        }
+        if members.len() != expected.count() {
+            return Err(anyhow!(
+                "Members should contain all agents from 1 to {} except {}",
+                agents,
+                agent
+            ));
+        }
+
+        let channels = CeremonyChannels::new(
+            agent,
+            identity,
+            members
+                .iter()
+                .map(|member| (member.agent_id, member.identity)),
+        )?;
+
+        Ok(Self {
+            ceremony_id,
+            generation,
+            participant: Arc::new(Mutex::new(participant)),
+            channels: Arc::new(channels),
+            confirmed: Arc::new(Mutex::new(HashSet::new())),
+            progress: Arc::new(Notify::new()),
+            members: members
+                .into_iter()
+                .map(|member| (member.agent_id, member.address))
+                .collect(),
+        })
+    }
+
+    /// gRPC service receiving dealings of other agents for this ceremony
+    pub fn service(&self) -> KeyGenerationAgentService<G> {
+        KeyGenerationAgentService {
+            ceremony_id: self.ceremony_id.clone(),
+            generation: self.generation,
+            participant: Arc::clone(&self.participant),
+            channels: Arc::clone(&self.channels),
+            confirmed: Arc::clone(&self.confirmed),
+            progress: Arc::clone(&self.progress),
+        }
+    }
+
+    /// Deliver own dealing to every member, wait for the dealings of all members and check that all members
+    /// received the same commitments. Unreachable members are retried every `retry_interval`.
+    pub async fn run(
+        &self,
+        retry_interval: Duration,
+    ) -> Result<(G::ScalarExt, ShareCommitments<G>), Error> {
+        let (dealer, commitments) = {
+            let participant = self.lock()?;
+            let commitments = participant
+                .commitments()
+                .commitments()
+                .iter()
+                .map(|c| Bytes::copy_from_slice(c.to_bytes().as_ref()))
+                .collect::<Vec<_>>();
+
+            (participant.index(), commitments)
+        };
+
+        try_join_all(self.members.iter().map(|(member, address)| {
+            self.deliver(
+                dealer,
+                *member,
+                address,
+                commitments.clone(),
+                retry_interval,
+            )
+        }))
+        .await?;
+
+        log::info!("Own dealing delivered to all members");
+
+        self.wait_for(|ceremony| Ok(ceremony.lock()?.is_complete()))
+            .await?;
+
+        let digest = self.lock()?.transcript_digest()?;
+        try_join_all(self.members.iter().map(|(member, address)| {
+            self.confirm_transcript(*member, address, &digest, retry_interval)
+        }))
+        .await?;
+
+        log::info!("All members received the same commitments");
+
+        // Members still comparing their transcripts with ours need this agent running
+        self.wait_for(|ceremony| {
+            let confirmed = ceremony
+                .confirmed
+                .lock()
+                .map_err(|_| anyhow!("Key generation state is not available"))?;
+
+            Ok(ceremony
+                .members
+                .keys()
+                .all(|member| confirmed.contains(member)))
+        })
+        .await?;
+
+        self.lock()?.finalize()
+    }
+
+    async fn wait_for(&self, done: impl Fn(&Self) -> Result<bool, Error>) -> Result<(), Error> {
+        loop {
+            let progress = self.progress.notified();
+
+            if done(self)? {
+                return Ok(());
+            }
+
+            progress.await;
+        }
+    }
+
+    async fn deliver(
+        &self,
+        dealer: usize,
+        member: usize,
+        address: &str,
+        commitments: Vec<Bytes>,
+        retry_interval: Duration,
+    ) -> Result<(), Error> {
+        let share = self.lock()?.share_for(member)?;
+        let channel = self.channels.channel(member)?;
+        let client = build_client(address)?;
+
+        let encrypted = channel.encrypt(
+            &share_context(
+                &self.ceremony_id,
+                self.generation,
+                dealer as u64,
+                member as u64,
+            ),
+            share.to_repr().as_ref(),
+        )?;
+        let mut request = SubmitDealingRequest {
+            ceremony_id: FastStr::new(&self.ceremony_id),
+            dealer: dealer as u64,
+            generation: self.generation,
+            receiver: member as u64,
+            commitments,
+            share: encrypted.ciphertext.into(),
+            nonce: Bytes::copy_from_slice(&encrypted.nonce),
+            authentication: Bytes::new(),
+            _unknown_fields: Default::default(),
+        };
+        request.authentication = channel.authenticate(&dealing_message(&request)).into();
+
+        send_to_member(member, "dealing", retry_interval, || {
+            client.submit_dealing(request.clone())
+        })
+        .await?;
+
+        log::info!("Dealing accepted by agent {}", member);
+        Ok(())
+    }
+
+    /// Compare our transcript `digest` with the one of the `member`, retrying until it received all dealings
+    async fn confirm_transcript(
+        &self,
+        member: usize,
+        address: &str,
+        digest: &[u8; 64],
+        retry_interval: Duration,
+    ) -> Result<(), Error> {
+        let channel = self.channels.channel(member)?;
+        let client = build_client(address)?;
+        let agent = self.channels.agent();
+
+        let request = TranscriptRequest {
+            ceremony_id: FastStr::new(&self.ceremony_id),
+            agent: agent as u64,
+            generation: self.generation,
+            authentication: channel
+                .authenticate(&transcript_request_message(
+                    &self.ceremony_id,
+                    self.generation,
+                    agent,
+                ))
+                .into(),
+            _unknown_fields: Default::default(),
+        };
+
+        let response = send_to_member(member, "transcript request", retry_interval, || {
+            client.get_transcript(request.clone())
+        })
+        .await?;
+
+        let message = transcript_message(
+            &self.ceremony_id,
+            self.generation,
+            member,
+            agent,
+            &response.digest,
+        );
+        if response.agent != member as u64 || !channel.verify(&message, &response.authentication) {
+            return Err(anyhow!("Transcript of agent {} is not authentic", member));
+        }
+
+        if response.digest.as_ref() != digest.as_slice() {
+            return Err(anyhow!(
+                "Agent {} received other commitments than this agent, some dealer equivocated, the ceremony is aborted",
+                member
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, DkgParticipant<G::ScalarExt, G>>, Error> {
+        self.participant
+            .lock()
+            .map_err(|_| anyhow!("Key generation state is not available"))
+    }
+}
+
+/// Authenticated fields of the dealing
+fn dealing_message(request: &SubmitDealingRequest) -> Vec<Vec<u8>> {
+    let mut message = vec![
+        b"dealing".to_vec(),
+        request.ceremony_id.as_bytes().to_vec(),
+        request.generation.to_le_bytes().to_vec(),
+        request.dealer.to_le_bytes().to_vec(),
+        request.receiver.to_le_bytes().to_vec(),
+        (request.commitments.len() as u64).to_le_bytes().to_vec(),
+    ];
+    message.extend(request.commitments.iter().map(|c| c.to_vec()));
+    message.push(request.nonce.to_vec());
+    message.push(request.share.to_vec());
+
+    message
+}
+
+/// Context the share of the `dealer` for the `receiver` is encrypted in, so it can't be replayed in another one
+fn share_context(ceremony_id: &str, generation: u64, dealer: u64, receiver: u64) -> Vec<Vec<u8>> {
+    vec![
+        b"share".to_vec(),
+        ceremony_id.as_bytes().to_vec(),
+        generation.to_le_bytes().to_vec(),
+        dealer.to_le_bytes().to_vec(),
+        receiver.to_le_bytes().to_vec(),
+    ]
+}
+
+/// Authenticated fields of the transcript request of the `agent`
+fn transcript_request_message(ceremony_id: &str, generation: u64, agent: usize) -> Vec<Vec<u8>> {
+    vec![
+        b"transcript-request".to_vec(),
+        ceremony_id.as_bytes().to_vec(),
+        generation.to_le_bytes().to_vec(),
+        (agent as u64).to_le_bytes().to_vec(),
+    ]
+}
+
+/// Authenticated fields of the transcript `digest` of the `responder` for the `requester`
+fn transcript_message(
+    ceremony_id: &str,
+    generation: u64,
+    responder: usize,
+    requester: usize,
+    digest: &[u8],
+) -> Vec<Vec<u8>> {
+    vec![
+        b"transcript".to_vec(),
+        ceremony_id.as_bytes().to_vec(),
+        generation.to_le_bytes().to_vec(),
+        (responder as u64).to_le_bytes().to_vec(),
+        (requester as u64).to_le_bytes().to_vec(),
+        digest.to_vec(),
+    ]
+}
+
+fn build_client(remote_address: &str) -> Result<KeyGenerationServiceClient, Error> {
+    let addr = member_address(remote_address)?;
+
+    Ok(
+        KeyGenerationServiceClientBuilder::new(format!("inter-agent-keygen-service-{}", addr))
+            .address(addr)
+            .build(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::pso::transaction_fingerprinting::fingerprint::agent::v1::KeyGenerationServiceServer;
+    use crate::test_utils::local_listener;
+    use fingerprinting_core::secret_sharing::SecretSharing;
+    use halo2_axiom::halo2curves::group::GroupEncoding;
+    use std::net::SocketAddr;
+    use volo_grpc::server::{Server, ServiceBuilder};
+
+    const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Ceremonies of `n` agents with threshold `t`, each serving its key generation service on a free local port
+    async fn ceremonies(
+        t: usize,
+        n: usize,
+    ) -> Result<
+        (
+            Vec<AgentIdentity<G1>>,
+            Vec<SocketAddr>,
+            Vec<KeyGenerationCeremony>,
+        ),
+        Error,
+    > {
+        let identities = (0..n)
+            .map(|_| AgentIdentity::generate())
+            .collect::<Vec<_>>();
+        let (addresses, listeners): (Vec<_>, Vec<_>) = (0..n)
+            .map(|_| local_listener())
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip();
+
+        let ceremonies = (1..=n)
+            .map(|agent| {
+                let members = (1..=n)
+                    .filter(|member| *member != agent)
+                    .map(|member| CeremonyMember {
+                        agent_id: member,
+                        address: addresses[member - 1].to_string(),
+                        identity: identities[member - 1].public(),
+                    })
+                    .collect();
+
+                KeyGenerationCeremony::new(
+                    "test".to_string(),
+                    0,
+                    DkgParticipant::new(agent, t, n)?,
+                    &identities[agent - 1],
+                    members,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        for (ceremony, listener) in ceremonies.iter().zip(listeners) {
+            tokio::spawn(
+                Server::new()
+                    .add_service(
+                        ServiceBuilder::new(KeyGenerationServiceServer::new(ceremony.service()))
+                            .build(),
+                    )
+                    .run(listener),
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        Ok((identities, addresses, ceremonies))
+    }
+
+    /// Dealing of the `dealer` for the `receiver` authenticated with the `identity` of the dealer
+    fn dealing(
+        dealer: &DkgParticipant<<G1 as CurveExt>::ScalarExt, G1>,
+        identity: &AgentIdentity<G1>,
+        receiver: (usize, G1),
+    ) -> Result<SubmitDealingRequest, Error> {
+        let channel = identity.channel(&receiver.1)?;
+        let encrypted = channel.encrypt(
+            &share_context("test", 0, dealer.index() as u64, receiver.0 as u64),
+            dealer.share_for(receiver.0)?.to_repr().as_ref(),
+        )?;
+
+        let mut request = SubmitDealingRequest {
+            ceremony_id: FastStr::new("test"),
+            dealer: dealer.index() as u64,
+            generation: 0,
+            receiver: receiver.0 as u64,
+            commitments: dealer
+                .commitments()
+                .commitments()
+                .iter()
+                .map(|c| Bytes::copy_from_slice(c.to_bytes().as_ref()))
+                .collect(),
+            share: encrypted.ciphertext.into(),
+            nonce: Bytes::copy_from_slice(&encrypted.nonce),
+            authentication: Bytes::new(),
+            _unknown_fields: Default::default(),
+        };
+        request.authentication = channel.authenticate(&dealing_message(&request)).into();
+
+        Ok(request)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_key_generation_ceremony() -> Result<(), Error> {
+        let (_, _, ceremonies) = ceremonies(2, 3).await?;
+
+        let results = futures::future::try_join_all(
+            ceremonies
+                .iter()
+                .map(|ceremony| ceremony.run(RETRY_INTERVAL)),
+        )
+        .await?;
+
+        let commitments = &results[0].1;
+        for (agent, (secret_shard, agent_commitments)) in results.iter().enumerate() {
+            assert_eq!(agent_commitments, commitments);
+            assert!(SecretSharing::verify_share(
+                agent + 1,
+                *secret_shard,
+                commitments
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_equivocating_dealer_aborts_ceremony() -> Result<(), Error> {
+        let (identities, addresses, ceremonies) = ceremonies(2, 3).await?;
+
+        // Dealer 1 sends dealings of two different polynomials, each valid on its own, to agents 2 and 3.
+        // Agent 1 doesn't run its ceremony, its service only receives dealings and reports its transcript.
+        for receiver in [2, 3] {
+            build_client(&addresses[receiver - 1].to_string())?
+                .submit_dealing(dealing(
+                    &DkgParticipant::new(1, 2, 3)?,
+                    &identities[0],
+                    (receiver, identities[receiver - 1].public()),
+                )?)
+                .await?;
+        }
+
+        let results = tokio::time::timeout(
+            Duration::from_secs(30),
+            futures::future::join_all(
+                ceremonies[1..]
+                    .iter()
+                    .map(|ceremony| ceremony.run(RETRY_INTERVAL)),
+            ),
+        )
+        .await?;
+
+        // Agents 2 and 3 find out comparing transcripts and don't finalize
+        for result in results {
+            let error = result.unwrap_err().to_string();
+            assert!(error.contains("equivocated"), "{}", error);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unauthenticated_dealing_rejected() -> Result<(), Error> {
+        let (identities, addresses, _ceremonies) = ceremonies(2, 3).await?;
+        let client = build_client(&addresses[1].to_string())?;
+        let dealer = DkgParticipant::new(1, 2, 3)?;
+
+        // Dealing of agent 1 authenticated with a foreign identity
+        let forged = dealing(
+            &dealer,
+            &AgentIdentity::generate(),
+            (2, identities[1].public()),
+        )?;
+        let status = client.submit_dealing(forged).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // Dealing altered on the way
+        let mut altered = dealing(&dealer, &identities[0], (2, identities[1].public()))?;
+        altered.commitments.reverse();
+        let status = client.submit_dealing(altered).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // Dealing for another receiver
+        let misdirected =
+            dealing(&dealer, &identities[0], (2, identities[1].public())).map(|mut request| {
+                request.receiver = 3;
+                request.authentication = identities[0]
+                    .channel(&identities[1].public())
+                    .map(|channel| channel.authenticate(&dealing_message(&request)).into())
+                    .unwrap_or_default();
+                request
+            })?;
+        let status = client.submit_dealing(misdirected).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        // Authentic dealing is accepted
+        client
+            .submit_dealing(dealing(
+                &dealer,
+                &identities[0],
+                (2, identities[1].public()),
+            )?)
+            .await?;
+
+        Ok(())
+    }
+}
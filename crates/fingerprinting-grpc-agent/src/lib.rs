@@ -1,76 +1,555 @@
 mod agents_topology;
+mod ceremony;
+mod key_generation;
+mod resharing;
 
 // hide generated values in private module (volo-generated code)
 #[allow(clippy::clone_on_ref_ptr, clippy::single_match_else)]
 mod generator {
     include!(concat!(env!("OUT_DIR"), "/proto_gen.rs"));
 }
-pub use agents_topology::GrpcAgentsTopology;
+pub use agents_topology::{AgentParameters, GrpcAgentsTopology};
+pub use ceremony::CeremonyMember;
 pub use generator::proto_gen::*;
+pub use key_generation::{KeyGenerationAgentService, KeyGenerationCeremony};
+pub use resharing::{ResharingAgentService, ResharingCeremony};
 
-use halo2_axiom::halo2curves::bn256::{Fr, G1Compressed, G1};
-use halo2_axiom::halo2curves::group::GroupEncoding;
+use fingerprinting_core::curves::{decode_point, FingerprintCurve};
+use fingerprinting_core::dleq::DleqProof;
+use fingerprinting_core::identity::AgentIdentity;
+use fingerprinting_core::tenant::{self, Tenant, TenantKey, TenantScope};
+use fingerprinting_core::DomainSeparationTag;
+use halo2_axiom::halo2curves::bn256::G1;
+use halo2_axiom::halo2curves::group::{Group, GroupEncoding};
 use pilota::Bytes;
+use std::collections::HashMap;
 use volo_grpc::{Code, Request, Response, Status};
 
+use ceremony::CeremonyChannels;
+
 use net::pso::transaction_fingerprinting::fingerprint::agent::v1::{
-    CooperationRequest, CooperationResponse,
+    CooperationBatchRequest, CooperationBatchResponse, CooperationRequest, CooperationResponse,
+    ParametersRequest, ParametersResponse, PublicShareRequest, PublicShareResponse,
 };
 
-pub struct CooperationAgentService {
-    agent_secret_shard: Fr,
+/// Maximum number of blinded values in one `ComputeExponentBatch` request
+pub const MAX_BATCH_SIZE: usize = 4096;
+
+pub struct CooperationAgentService<G: FingerprintCurve = G1> {
+    agent_secret_shards: HashMap<u64, G::ScalarExt>, // by secret generation
+    dst: DomainSeparationTag,
+    tenant_key: Option<TenantKey>,
+    members: Option<CeremonyChannels>,
 }
 
-impl CooperationAgentService {
-    pub fn new(secret_shard: Fr) -> CooperationAgentService {
+impl<G: FingerprintCurve> CooperationAgentService<G> {
+    pub fn new(secret_shard: G::ScalarExt) -> CooperationAgentService<G> {
         CooperationAgentService {
-            agent_secret_shard: secret_shard,
+            agent_secret_shards: HashMap::from([(0, secret_shard)]),
+            dst: DomainSeparationTag::default(),
+            tenant_key: None,
+            members: None,
         }
     }
+
+    /// Service holding secret shards of several generations, e.g. during the proactive refresh
+    pub fn with_generations(
+        secret_shards: HashMap<u64, G::ScalarExt>,
+    ) -> CooperationAgentService<G> {
+        CooperationAgentService {
+            agent_secret_shards: secret_shards,
+            dst: DomainSeparationTag::default(),
+            tenant_key: None,
+            members: None,
+        }
+    }
+
+    /// Report the `dst` of the network to other agents instead of the default one
+    pub fn with_dst(mut self, dst: DomainSeparationTag) -> CooperationAgentService<G> {
+        self.dst = dst;
+        self
+    }
+
+    /// Compute exponents of tenants with the shard `s_i * t` derived with the tenant `key` of the network,
+    /// only the default tenant is available without it
+    pub fn with_tenant_key(mut self, key: TenantKey) -> CooperationAgentService<G> {
+        self.tenant_key = Some(key);
+        self
+    }
+
+    /// Serve tenants other than the default one only to the `members` of the topology, which authenticate
+    /// their requests with the channel of their public identity and the `identity` of the agent `agent_id`.
+    /// Without members only the default tenant is served, anyone reaching the agent could evaluate tenants otherwise.
+    pub fn with_members(
+        mut self,
+        agent_id: usize,
+        identity: &AgentIdentity<G>,
+        members: impl IntoIterator<Item = (usize, G)>,
+    ) -> Result<CooperationAgentService<G>, anyhow::Error> {
+        self.members = Some(CeremonyChannels::new(agent_id, identity, members)?);
+        Ok(self)
+    }
+
+    /// Check that the request for the `tenant` comes from a member, requests of the default tenant are not authenticated
+    fn authenticate<T: AsRef<[u8]>>(
+        &self,
+        tenant: &str,
+        agent: u64,
+        message: &[T],
+        authentication: &[u8],
+    ) -> Result<(), Box<Status>> {
+        if tenant.is_empty() {
+            return Ok(());
+        }
+
+        let members = self.members.as_ref().ok_or_else(|| {
+            Status::new(
+                Code::Unauthenticated,
+                "Agent has no member identities, only the default tenant is served",
+            )
+        })?;
+        let agent = usize::try_from(agent)
+            .map_err(|_| Status::new(Code::Unauthenticated, format!("Unknown agent {}", agent)))?;
+
+        members.authenticated(agent, message, authentication)?;
+
+        Ok(())
+    }
+
+    /// Secret shard `s_i * t` of the `generation` for the `tenant` requested by another agent
+    fn secret_shard(&self, generation: u64, tenant: &str) -> Result<G::ScalarExt, Box<Status>> {
+        let secret_shard = self.agent_secret_shards.get(&generation).ok_or_else(|| {
+            Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "Agent doesn't hold secret shard of generation {}",
+                    generation
+                ),
+            )
+        })?;
+
+        let tenant =
+            Tenant::new(tenant).map_err(|e| Status::new(Code::InvalidArgument, e.to_string()))?;
+        let scope: TenantScope<G::ScalarExt> = tenant::scope(self.tenant_key.as_ref(), &tenant)
+            .map_err(|e| Status::new(Code::FailedPrecondition, e.to_string()))?;
+
+        Ok(*secret_shard * scope.scalar())
+    }
+
+    /// Public share commitment `[s_i] G` of the `generation` used by other agents to verify our responses
+    pub fn public_share(&self, generation: u64) -> Option<G> {
+        self.agent_secret_shards
+            .get(&generation)
+            .map(|secret_shard| G::generator() * secret_shard)
+    }
+
+    /// Public share `[s_i] G2` of the `generation`, interpolated by other agents into the group key in G2
+    pub fn public_share_g2(&self, generation: u64) -> Option<G::G2> {
+        self.agent_secret_shards
+            .get(&generation)
+            .map(|secret_shard| G::G2::generator() * secret_shard)
+    }
 }
 
-impl net::pso::transaction_fingerprinting::fingerprint::agent::v1::CooperationService
-    for CooperationAgentService
+impl<G: FingerprintCurve>
+    net::pso::transaction_fingerprinting::fingerprint::agent::v1::CooperationService
+    for CooperationAgentService<G>
 {
     async fn compute_exponent(
         &self,
         req: Request<CooperationRequest>,
     ) -> Result<Response<CooperationResponse>, Status> {
         let request = req.into_inner();
-        let blinded_value = request.blinded_value;
         let generation = request.generation;
+        self.authenticate(
+            &request.tenant,
+            request.agent,
+            &cooperation_message(
+                generation,
+                &request.tenant,
+                request.agent,
+                &[&request.blinded_value],
+            ),
+            &request.authentication,
+        )
+        .map_err(|e| *e)?;
+        let secret_shard = self
+            .secret_shard(generation, &request.tenant)
+            .map_err(|e| *e)?;
 
-        if generation != 0 {
-            return Err(Status::new(
-                Code::InvalidArgument,
-                "Current implementation doesn't support secret generations",
-            ));
-        }
+        let (blinded_exponent, proof_of_computation) =
+            compute_exponent_with_proof::<G>(&secret_shard, &request.blinded_value)
+                .map_err(|e| Status::new(Code::InvalidArgument, e))?;
+
+        let response = CooperationResponse {
+            generation,
+            blinded_exponent,
+            proof_of_computation,
+            _unknown_fields: Default::default(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn compute_exponent_batch(
+        &self,
+        req: Request<CooperationBatchRequest>,
+    ) -> Result<Response<CooperationBatchResponse>, Status> {
+        let request = req.into_inner();
+        let generation = request.generation;
+        self.authenticate(
+            &request.tenant,
+            request.agent,
+            &cooperation_message(
+                generation,
+                &request.tenant,
+                request.agent,
+                &request.blinded_values,
+            ),
+            &request.authentication,
+        )
+        .map_err(|e| *e)?;
+        let secret_shard = self
+            .secret_shard(generation, &request.tenant)
+            .map_err(|e| *e)?;
 
-        if blinded_value.len() != 32 {
+        if request.blinded_values.len() > MAX_BATCH_SIZE {
             return Err(Status::new(
                 Code::InvalidArgument,
-                "Invalid blinded value, it should be exactly 32 bytes long",
+                format!(
+                    "Batch of {} blinded values exceeds the limit of {}",
+                    request.blinded_values.len(),
+                    MAX_BATCH_SIZE
+                ),
             ));
         }
-        let mut point = G1Compressed::default();
-        point.as_mut().copy_from_slice(blinded_value.as_ref());
 
-        let b_point = G1::from_bytes(&point).into_option().ok_or(Status::new(
-            Code::InvalidArgument,
-            "Invalid blinded value, it should be a valid G1 point",
-        ))?;
+        let (blinded_exponents, proofs_of_computation) = request
+            .blinded_values
+            .iter()
+            .map(|blinded_value| compute_exponent_with_proof::<G>(&secret_shard, blinded_value))
+            .collect::<Result<(Vec<_>, Vec<_>), _>>()
+            .map_err(|e| Status::new(Code::InvalidArgument, e))?;
 
-        let exponent = b_point * self.agent_secret_shard;
-        let exponent_bytes = exponent.to_bytes();
+        let response = CooperationBatchResponse {
+            generation,
+            blinded_exponents,
+            proofs_of_computation,
+            _unknown_fields: Default::default(),
+        };
 
-        let response = CooperationResponse {
+        Ok(Response::new(response))
+    }
+
+    async fn get_public_share(
+        &self,
+        req: Request<PublicShareRequest>,
+    ) -> Result<Response<PublicShareResponse>, Status> {
+        let generation = req.into_inner().generation;
+
+        let (public_share, public_share_g2) = self
+            .public_share(generation)
+            .zip(self.public_share_g2(generation))
+            .ok_or(Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "Agent doesn't hold secret shard of generation {}",
+                    generation
+                ),
+            ))?;
+
+        let response = PublicShareResponse {
             generation,
-            blinded_exponent: Bytes::copy_from_slice(exponent_bytes.as_ref()),
-            proof_of_computation: Default::default(),
+            public_share: Bytes::copy_from_slice(public_share.to_bytes().as_ref()),
+            public_share_g2: Bytes::copy_from_slice(public_share_g2.to_bytes().as_ref()),
             _unknown_fields: Default::default(),
         };
 
         Ok(Response::new(response))
     }
+
+    async fn get_parameters(
+        &self,
+        _req: Request<ParametersRequest>,
+    ) -> Result<Response<ParametersResponse>, Status> {
+        Ok(Response::new(ParametersResponse {
+            dst: self.dst.to_string().into(),
+            curve: G::CURVE.name().into(),
+            tenant_key_id: self
+                .tenant_key
+                .as_ref()
+                .map(TenantKey::id)
+                .unwrap_or_default()
+                .into(),
+            _unknown_fields: Default::default(),
+        }))
+    }
+}
+
+/// Fields of the cooperation request of the `agent` authenticated with its channel to the receiving agent
+pub(crate) fn cooperation_message<T: AsRef<[u8]>>(
+    generation: u64,
+    tenant: &str,
+    agent: u64,
+    blinded_values: &[T],
+) -> Vec<Vec<u8>> {
+    let mut message = vec![
+        b"cooperation".to_vec(),
+        generation.to_le_bytes().to_vec(),
+        tenant.as_bytes().to_vec(),
+        agent.to_le_bytes().to_vec(),
+        (blinded_values.len() as u64).to_le_bytes().to_vec(),
+    ];
+    message.extend(blinded_values.iter().map(|value| value.as_ref().to_vec()));
+
+    message
+}
+
+/// Compute `[s_i] B` for the compressed blinded value `B` with the DLEQ proof of computation
+fn compute_exponent_with_proof<G: FingerprintCurve>(
+    secret_shard: &G::ScalarExt,
+    blinded_value: &[u8],
+) -> Result<(Bytes, Bytes), String> {
+    let b_point = decode_point::<G>(blinded_value)
+        .map_err(|e| format!("Invalid blinded value of {}, {}", G::CURVE.name(), e))?;
+
+    let exponent = b_point * secret_shard;
+    let exponent_bytes = exponent.to_bytes();
+
+    let proof = DleqProof::prove(*secret_shard, b_point, exponent);
+
+    Ok((
+        Bytes::copy_from_slice(exponent_bytes.as_ref()),
+        Bytes::from(proof.to_bytes()),
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::net::SocketAddr;
+    use volo::net::DefaultIncoming;
+
+    /// Listener on a free local port for an in-process server, bound until the server takes it over
+    /// so that no other test (or outgoing connection) grabs the port in between
+    pub(crate) fn local_listener() -> Result<(SocketAddr, DefaultIncoming), anyhow::Error> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        Ok((
+            addr,
+            DefaultIncoming::from(tokio::net::TcpListener::from_std(listener)?),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::pso::transaction_fingerprinting::fingerprint::agent::v1::CooperationService;
+    use halo2_axiom::halo2curves::bn256::Fr;
+    use halo2_axiom::halo2curves::ff::Field;
+    use rand::rngs::OsRng;
+
+    fn request(generation: u64, blinded_value: &G1) -> Request<CooperationRequest> {
+        Request::new(CooperationRequest {
+            generation,
+            agent: 0,
+            tenant: Default::default(),
+            blinded_value: Bytes::copy_from_slice(blinded_value.to_bytes().as_ref()),
+            authentication: Bytes::new(),
+            _unknown_fields: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_compute_exponent() -> Result<(), anyhow::Error> {
+        let secret_shard = Fr::random(OsRng);
+        let public_share = G1::generator() * secret_shard;
+        let service = CooperationAgentService::<G1>::new(secret_shard);
+        let blinded_value = G1::random(OsRng);
+
+        let response = service
+            .compute_exponent(request(0, &blinded_value))
+            .await?
+            .into_inner();
+        let exponent: G1 = decode_point(&response.blinded_exponent)?;
+        assert_eq!(exponent, blinded_value * secret_shard);
+        assert!(
+            DleqProof::from_bytes(&response.proof_of_computation)?.verify(
+                public_share,
+                blinded_value,
+                exponent
+            )
+        );
+
+        // Unknown generation and invalid points are rejected
+        let status = service
+            .compute_exponent(request(1, &blinded_value))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        let mut invalid = request(0, &blinded_value);
+        invalid.get_mut().blinded_value = Bytes::from_static(&[0xff; 32]);
+        let status = service.compute_exponent(invalid).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compute_exponent_batch() -> Result<(), anyhow::Error> {
+        let secret_shard = Fr::random(OsRng);
+        let public_share = G1::generator() * secret_shard;
+        let service = CooperationAgentService::<G1>::new(secret_shard);
+        let blinded_values = (0..5).map(|_| G1::random(OsRng)).collect::<Vec<_>>();
+
+        let batch = |count: usize| {
+            Request::new(CooperationBatchRequest {
+                generation: 0,
+                agent: 0,
+                tenant: Default::default(),
+                blinded_values: blinded_values
+                    .iter()
+                    .cycle()
+                    .take(count)
+                    .map(|value| Bytes::copy_from_slice(value.to_bytes().as_ref()))
+                    .collect(),
+                authentication: Bytes::new(),
+                _unknown_fields: Default::default(),
+            })
+        };
+
+        let response = service
+            .compute_exponent_batch(batch(blinded_values.len()))
+            .await?
+            .into_inner();
+        assert_eq!(response.blinded_exponents.len(), blinded_values.len());
+        for ((blinded_value, exponent), proof) in blinded_values
+            .iter()
+            .zip(&response.blinded_exponents)
+            .zip(&response.proofs_of_computation)
+        {
+            let exponent: G1 = decode_point(exponent)?;
+            assert_eq!(exponent, blinded_value * secret_shard);
+            assert!(DleqProof::from_bytes(proof)?.verify(public_share, *blinded_value, exponent));
+        }
+
+        let status = service
+            .compute_exponent_batch(batch(MAX_BATCH_SIZE + 1))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tenant_requests_authenticated() -> Result<(), anyhow::Error> {
+        let secret_shard = Fr::random(OsRng);
+        let key = TenantKey::generate();
+        let agent = AgentIdentity::<G1>::generate();
+        let member = AgentIdentity::<G1>::generate();
+        let outsider = AgentIdentity::<G1>::generate();
+        let service = CooperationAgentService::<G1>::new(secret_shard)
+            .with_tenant_key(key.clone())
+            .with_members(1, &agent, [(2, member.public())])?;
+        let blinded_value = G1::random(OsRng);
+
+        let tenant_request = |requester: u64,
+                              identity: &AgentIdentity<G1>|
+         -> Result<Request<CooperationRequest>, anyhow::Error> {
+            let mut request = request(0, &blinded_value);
+            let message =
+                cooperation_message(0, "retail", requester, &[&request.get_ref().blinded_value]);
+            let request_mut = request.get_mut();
+            request_mut.tenant = "retail".into();
+            request_mut.agent = requester;
+            request_mut.authentication = identity
+                .channel(&agent.public())?
+                .authenticate(&message)
+                .into();
+
+            Ok(request)
+        };
+
+        // The member gets the exponent of the tenant shard `s_i * t`
+        let response = service
+            .compute_exponent(tenant_request(2, &member)?)
+            .await?
+            .into_inner();
+        let exponent: G1 = decode_point(&response.blinded_exponent)?;
+        let scalar = key.scope::<Fr>(&Tenant::new("retail")?).scalar();
+        assert_eq!(exponent, blinded_value * (secret_shard * scalar));
+
+        // Requests without authentication, forged ones and ones of unknown agents are rejected
+        let mut unauthenticated = tenant_request(2, &member)?;
+        unauthenticated.get_mut().authentication = Bytes::new();
+        let mut other_tenant = tenant_request(2, &member)?;
+        other_tenant.get_mut().tenant = "corporate".into();
+        for rejected in [
+            unauthenticated,
+            other_tenant,
+            tenant_request(2, &outsider)?,
+            tenant_request(3, &outsider)?,
+        ] {
+            let status = service.compute_exponent(rejected).await.unwrap_err();
+            assert_eq!(status.code(), Code::Unauthenticated);
+        }
+
+        // The default tenant is served to anyone, agents without members serve no other tenant
+        service.compute_exponent(request(0, &blinded_value)).await?;
+        let without_members = CooperationAgentService::<G1>::new(secret_shard).with_tenant_key(key);
+        let status = without_members
+            .compute_exponent(tenant_request(2, &member)?)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_public_share_and_parameters() -> Result<(), anyhow::Error> {
+        let secret_shard = Fr::random(OsRng);
+        let service = CooperationAgentService::<G1>::with_generations(HashMap::from([
+            (0, secret_shard),
+            (1, secret_shard.double()),
+        ]));
+
+        let response = service
+            .get_public_share(Request::new(PublicShareRequest {
+                generation: 1,
+                _unknown_fields: Default::default(),
+            }))
+            .await?
+            .into_inner();
+        let public_share: G1 = decode_point(&response.public_share)?;
+        assert_eq!(public_share, G1::generator() * secret_shard.double());
+
+        let status = service
+            .get_public_share(Request::new(PublicShareRequest {
+                generation: 2,
+                _unknown_fields: Default::default(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        let parameters = service
+            .get_parameters(Request::new(ParametersRequest {
+                _unknown_fields: Default::default(),
+            }))
+            .await?
+            .into_inner();
+        assert_eq!(parameters.curve.as_str(), "bn256");
+        assert_eq!(
+            parameters.dst.as_str(),
+            DomainSeparationTag::default().to_string()
+        );
+        assert!(parameters.tenant_key_id.is_empty());
+
+        Ok(())
+    }
 }
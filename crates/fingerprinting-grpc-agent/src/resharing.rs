@@ -0,0 +1,1007 @@
+use crate::ceremony::{member_address, send_to_member, CeremonyChannels, CeremonyMember};
+use crate::net::pso::transaction_fingerprinting::fingerprint::agent::v1::{
+    ResharingService, ResharingServiceClient, ResharingServiceClientBuilder,
+    ResharingTranscriptRequest, ResharingTranscriptResponse, SubmitResharingDealingRequest,
+    SubmitResharingDealingResponse,
+};
+use anyhow::{anyhow, Error};
+use fingerprinting_core::curves::{decode_point, decode_scalar, FingerprintCurve};
+use fingerprinting_core::identity::AgentIdentity;
+use fingerprinting_core::resharing::{ResharingDealer, ResharingReceiver};
+use fingerprinting_core::secret_sharing::ShareCommitments;
+use futures::future::try_join_all;
+use halo2_axiom::halo2curves::bn256::G1;
+use halo2_axiom::halo2curves::ff::PrimeField;
+use halo2_axiom::halo2curves::CurveExt;
+use pilota::{Bytes, FastStr};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use volo_grpc::{Code, Request, Response, Status};
+
+type SharedReceiver<G> = Arc<Mutex<ResharingReceiver<<G as CurveExt>::ScalarExt, G>>>;
+
+/// Server side of the resharing ceremony: receives and verifies dealings of the old committee
+/// and reports the digest of received commitments to other new agents
+pub struct ResharingAgentService<G: FingerprintCurve = G1> {
+    ceremony_id: String,
+    generation: u64,
+    receiver: Option<SharedReceiver<G>>,
+    // channels with the dealers of the old committee by their old agent number
+    dealers: Arc<CeremonyChannels>,
+    // channels with the agents of the new committee by their new agent number
+    members: Arc<CeremonyChannels>,
+    // new agents which obtained our transcript digest
+    confirmed: Arc<Mutex<HashSet<usize>>>,
+    progress: Arc<Notify>,
+}
+
+impl<G: FingerprintCurve> ResharingAgentService<G> {
+    fn check_ceremony(
+        &self,
+        ceremony_id: &str,
+        generation: u64,
+    ) -> Result<&SharedReceiver<G>, Box<Status>> {
+        if ceremony_id != self.ceremony_id {
+            return Err(Box::new(Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "Agent takes part in ceremony {}, request is for {}",
+                    self.ceremony_id, ceremony_id
+                ),
+            )));
+        }
+
+        if generation != self.generation {
+            return Err(Box::new(Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "Agent reshares into secret generation {}, request is for {}",
+                    self.generation, generation
+                ),
+            )));
+        }
+
+        self.receiver.as_ref().ok_or_else(|| {
+            Box::new(Status::new(
+                Code::FailedPrecondition,
+                "Agent is not a member of the new committee",
+            ))
+        })
+    }
+}
+
+impl<G: FingerprintCurve> ResharingService for ResharingAgentService<G> {
+    async fn submit_resharing_dealing(
+        &self,
+        req: Request<SubmitResharingDealingRequest>,
+    ) -> Result<Response<SubmitResharingDealingResponse>, Status> {
+        let request = req.into_inner();
+
+        let receiver = self
+            .check_ceremony(&request.ceremony_id, request.generation)
+            .map_err(|e| *e)?;
+
+        let dealer = usize::try_from(request.dealer)
+            .map_err(|_| Status::new(Code::InvalidArgument, "Invalid dealer number"))?;
+
+        let channel = self
+            .dealers
+            .authenticated(dealer, &dealing_message(&request), &request.authentication)
+            .map_err(|e| *e)?;
+
+        let index = lock(receiver)
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?
+            .index();
+        if request.receiver != index as u64 {
+            return Err(Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "Dealing is for new agent {}, this is new agent {}",
+                    request.receiver, index
+                ),
+            ));
+        }
+
+        let commitments = request
+            .commitments
+            .iter()
+            .map(|commitment| decode_point::<G>(commitment))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                Status::new(
+                    Code::InvalidArgument,
+                    format!("Invalid commitments of {}, {}", G::CURVE.name(), e),
+                )
+            })?;
+
+        let share = channel
+            .decrypt(
+                &share_context(
+                    &request.ceremony_id,
+                    request.generation,
+                    request.dealer,
+                    request.receiver,
+                ),
+                &request.nonce,
+                &request.share,
+            )
+            .and_then(|share| decode_scalar(&share))
+            .map_err(|e| {
+                Status::new(
+                    Code::InvalidArgument,
+                    format!("Invalid share of {}, {}", G::CURVE.name(), e),
+                )
+            })?;
+
+        let mut receiver = receiver
+            .lock()
+            .map_err(|_| Status::new(Code::Internal, "Resharing state is not available"))?;
+
+        receiver
+            .receive(dealer, ShareCommitments::new(commitments), share)
+            .map_err(|e| {
+                log::error!("Complaint against dealer {}: {}", dealer, e);
+                Status::new(Code::InvalidArgument, e.to_string())
+            })?;
+
+        log::info!(
+            "Accepted resharing dealing from agent {}, still waiting for {:?}",
+            dealer,
+            receiver.missing()
+        );
+        drop(receiver);
+
+        self.progress.notify_waiters();
+
+        Ok(Response::new(SubmitResharingDealingResponse {
+            receiver: index as u64,
+            _unknown_fields: Default::default(),
+        }))
+    }
+
+    async fn get_resharing_transcript(
+        &self,
+        req: Request<ResharingTranscriptRequest>,
+    ) -> Result<Response<ResharingTranscriptResponse>, Status> {
+        let request = req.into_inner();
+
+        let receiver = self
+            .check_ceremony(&request.ceremony_id, request.generation)
+            .map_err(|e| *e)?;
+
+        let agent = usize::try_from(request.agent)
+            .map_err(|_| Status::new(Code::InvalidArgument, "Invalid agent number"))?;
+
+        let channel = self
+            .members
+            .authenticated(
+                agent,
+                &transcript_request_message(&request.ceremony_id, request.generation, agent),
+                &request.authentication,
+            )
+            .map_err(|e| *e)?;
+
+        let (responder, digest) = {
+            let receiver =
+                lock(receiver).map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+            let digest = receiver
+                .transcript_digest()
+                .map_err(|e| Status::new(Code::Unavailable, e.to_string()))?;
+
+            (receiver.index(), digest)
+        };
+
+        let authentication = channel.authenticate(&transcript_message(
+            &request.ceremony_id,
+            request.generation,
+            responder,
+            agent,
+            &digest,
+        ));
+
+        self.confirmed
+            .lock()
+            .map_err(|_| Status::new(Code::Internal, "Resharing state is not available"))?
+            .insert(agent);
+        self.progress.notify_waiters();
+
+        Ok(Response::new(ResharingTranscriptResponse {
+            agent: responder as u64,
+            digest: Bytes::copy_from_slice(&digest),
+            authentication: authentication.into(),
+            _unknown_fields: Default::default(),
+        }))
+    }
+}
+
+/// Redistribution of the existing secret from the old committee to a new committee.
+///
+/// An agent takes part as a `dealer` (agent of the old committee redistributing its shard),
+/// as a `receiver` (agent of the new committee) or both. Dealers deliver their dealings to every
+/// member of the new committee, receivers serve [`ResharingAgentService`] and wait for all dealings.
+/// Dealings are authenticated and the shares encrypted with the channel keys of the agent identities.
+/// Once all dealings are received new agents compare digests of the received commitments, so a dealer
+/// sending different commitments to different agents aborts the ceremony.
+/// As a result each new agent gets its secret shard of the `generation` for the same group key.
+pub struct ResharingCeremony<G: FingerprintCurve = G1> {
+    ceremony_id: String,
+    generation: u64,
+    dealer: Option<ResharingDealer<G::ScalarExt, G>>,
+    receiver: Option<SharedReceiver<G>>,
+    dealers: Arc<CeremonyChannels>,
+    members: Arc<CeremonyChannels>,
+    confirmed: Arc<Mutex<HashSet<usize>>>,
+    progress: Arc<Notify>,
+    addresses: HashMap<usize, String>,
+}
+
+impl<G: FingerprintCurve> ResharingCeremony<G> {
+    /// Ceremony producing secret shards of the `generation` for the new committee. `dealers` are public
+    /// identities of the old agents by their old agent number, `members` the other agents of the new committee.
+    pub fn new(
+        ceremony_id: String,
+        generation: u64,
+        dealer: Option<ResharingDealer<G::ScalarExt, G>>,
+        receiver: Option<ResharingReceiver<G::ScalarExt, G>>,
+        identity: &AgentIdentity<G>,
+        dealers: Vec<(usize, G)>,
+        members: Vec<CeremonyMember<G>>,
+    ) -> Result<Self, Error> {
+        if generation == 0 {
+            return Err(anyhow!(
+                "Generation 0 is produced by the key generation, resharing produces further generations"
+            ));
+        }
+
+        let own_dealer = dealer.as_ref().map(ResharingDealer::index);
+        let own_index = receiver.as_ref().map(ResharingReceiver::index);
+        let participants = match (&dealer, &receiver) {
+            (None, None) => {
+                return Err(anyhow!(
+                    "Agent should be a dealer of the old committee or a member of the new committee"
+                ));
+            }
+            (Some(dealer), Some(receiver)) => {
+                if receiver.participants() != dealer.participants()
+                    || receiver.threshold() != dealer.commitments().threshold()
+                {
+                    return Err(anyhow!(
+                        "Dealer and receiver are configured for different committees"
+                    ));
+                }
+
+                dealer.participants()
+            }
+            (Some(dealer), None) => dealer.participants(),
+            (None, Some(receiver)) => receiver.participants(),
+        };
+
+        // Dealers deliver to all new agents, new agents compare transcripts with all others
+        if let Some(missing) = (1..=participants)
+            .filter(|member| Some(*member) != own_index)
+            .find(|member| !members.iter().any(|m| m.agent_id == *member))
+        {
+            return Err(anyhow!("No address configured for new agent {}", missing));
+        }
+
+        // Own dealing is accepted locally, all other dealings are authenticated
+        let dealers = dealers
+            .into_iter()
+            .filter(|(dealer, _)| Some(*dealer) != own_dealer)
+            .collect::<Vec<_>>();
+        if let Some(receiver) = &receiver {
+            if let Some(missing) = receiver
+                .dealers()
+                .iter()
+                .filter(|dealer| Some(**dealer) != own_dealer)
+                .find(|dealer| !dealers.iter().any(|(d, _)| d == *dealer))
+            {
+                return Err(anyhow!("No identity configured for dealer {}", missing));
+            }
+        }
+
+        // Agents of the new committee are numbered from 1, dealers leaving the committee have no number
+        let own_number = own_index.unwrap_or_default();
+        let dealers = CeremonyChannels::new(own_number, identity, dealers)?;
+        let channels = CeremonyChannels::new(
+            own_number,
+            identity,
+            members
+                .iter()
+                .map(|member| (member.agent_id, member.identity)),
+        )?;
+
+        Ok(Self {
+            ceremony_id,
+            generation,
+            dealer,
+            receiver: receiver.map(|receiver| Arc::new(Mutex::new(receiver))),
+            dealers: Arc::new(dealers),
+            members: Arc::new(channels),
+            confirmed: Arc::new(Mutex::new(HashSet::new())),
+            progress: Arc::new(Notify::new()),
+            addresses: members
+                .into_iter()
+                .map(|member| (member.agent_id, member.address))
+                .collect(),
+        })
+    }
+
+    /// gRPC service receiving dealings of the old committee for this ceremony
+    pub fn service(&self) -> ResharingAgentService<G> {
+        ResharingAgentService {
+            ceremony_id: self.ceremony_id.clone(),
+            generation: self.generation,
+            receiver: self.receiver.as_ref().map(Arc::clone),
+            dealers: Arc::clone(&self.dealers),
+            members: Arc::clone(&self.members),
+            confirmed: Arc::clone(&self.confirmed),
+            progress: Arc::clone(&self.progress),
+        }
+    }
+
+    /// Deliver own dealing (if dealer) to every new agent, wait for all dealings and check that all new
+    /// agents received the same commitments (if receiver). Unreachable members are retried every `retry_interval`.
+    ///
+    /// Returns the new secret shard and group commitments for members of the new committee.
+    pub async fn run(
+        &self,
+        retry_interval: Duration,
+    ) -> Result<Option<(G::ScalarExt, ShareCommitments<G>)>, Error> {
+        if let Some(dealer) = &self.dealer {
+            let commitments = dealer
+                .commitments()
+                .commitments()
+                .iter()
+                .map(|c| Bytes::copy_from_slice(c.to_bytes().as_ref()))
+                .collect::<Vec<_>>();
+
+            // Own dealing for ourselves as a new agent is accepted locally
+            let own_index = self
+                .receiver
+                .as_ref()
+                .map(|receiver| {
+                    let mut receiver = lock(receiver)?;
+                    let share = dealer.share_for(receiver.index())?;
+                    receiver.receive(dealer.index(), dealer.commitments().clone(), share)?;
+
+                    Ok::<_, Error>(receiver.index())
+                })
+                .transpose()?;
+
+            try_join_all(
+                self.addresses
+                    .iter()
+                    .filter(|(member, _)| Some(**member) != own_index)
+                    .map(|(member, address)| {
+                        self.deliver(
+                            dealer,
+                            *member,
+                            address,
+                            commitments.clone(),
+                            retry_interval,
+                        )
+                    }),
+            )
+            .await?;
+
+            log::info!("Own dealing delivered to all members of the new committee");
+        }
+
+        let Some(receiver) = &self.receiver else {
+            return Ok(None);
+        };
+
+        self.wait_for(|_| Ok(lock(receiver)?.is_complete())).await?;
+
+        let digest = lock(receiver)?.transcript_digest()?;
+        try_join_all(self.addresses.iter().map(|(member, address)| {
+            self.confirm_transcript(*member, address, &digest, retry_interval)
+        }))
+        .await?;
+
+        log::info!("All members of the new committee received the same commitments");
+
+        // New agents still comparing their transcripts with ours need this agent running
+        self.wait_for(|ceremony| {
+            let confirmed = ceremony
+                .confirmed
+                .lock()
+                .map_err(|_| anyhow!("Resharing state is not available"))?;
+
+            Ok(ceremony
+                .addresses
+                .keys()
+                .all(|member| confirmed.contains(member)))
+        })
+        .await?;
+
+        lock(receiver)?.finalize().map(Some)
+    }
+
+    async fn wait_for(&self, done: impl Fn(&Self) -> Result<bool, Error>) -> Result<(), Error> {
+        loop {
+            let progress = self.progress.notified();
+
+            if done(self)? {
+                return Ok(());
+            }
+
+            progress.await;
+        }
+    }
+
+    async fn deliver(
+        &self,
+        dealer: &ResharingDealer<G::ScalarExt, G>,
+        member: usize,
+        address: &str,
+        commitments: Vec<Bytes>,
+        retry_interval: Duration,
+    ) -> Result<(), Error> {
+        let share = dealer.share_for(member)?;
+        let channel = self.members.channel(member)?;
+        let client = build_client(address)?;
+
+        let encrypted = channel.encrypt(
+            &share_context(
+                &self.ceremony_id,
+                self.generation,
+                dealer.index() as u64,
+                member as u64,
+            ),
+            share.to_repr().as_ref(),
+        )?;
+        let mut request = SubmitResharingDealingRequest {
+            ceremony_id: FastStr::new(&self.ceremony_id),
+            dealer: dealer.index() as u64,
+            generation: self.generation,
+            receiver: member as u64,
+            commitments,
+            share: encrypted.ciphertext.into(),
+            nonce: Bytes::copy_from_slice(&encrypted.nonce),
+            authentication: Bytes::new(),
+            _unknown_fields: Default::default(),
+        };
+        request.authentication = channel.authenticate(&dealing_message(&request)).into();
+
+        send_to_member(member, "resharing dealing", retry_interval, || {
+            client.submit_resharing_dealing(request.clone())
+        })
+        .await?;
+
+        log::info!("Resharing dealing accepted by new agent {}", member);
+        Ok(())
+    }
+
+    /// Compare our transcript `digest` with the one of the new agent `member`, retrying until it received all dealings
+    async fn confirm_transcript(
+        &self,
+        member: usize,
+        address: &str,
+        digest: &[u8; 64],
+        retry_interval: Duration,
+    ) -> Result<(), Error> {
+        let channel = self.members.channel(member)?;
+        let client = build_client(address)?;
+        let agent = self.members.agent();
+
+        let request = ResharingTranscriptRequest {
+            ceremony_id: FastStr::new(&self.ceremony_id),
+            agent: agent as u64,
+            generation: self.generation,
+            authentication: channel
+                .authenticate(&transcript_request_message(
+                    &self.ceremony_id,
+                    self.generation,
+                    agent,
+                ))
+                .into(),
+            _unknown_fields: Default::default(),
+        };
+
+        let response = send_to_member(member, "transcript request", retry_interval, || {
+            client.get_resharing_transcript(request.clone())
+        })
+        .await?;
+
+        let message = transcript_message(
+            &self.ceremony_id,
+            self.generation,
+            member,
+            agent,
+            &response.digest,
+        );
+        if response.agent != member as u64 || !channel.verify(&message, &response.authentication) {
+            return Err(anyhow!(
+                "Transcript of new agent {} is not authentic",
+                member
+            ));
+        }
+
+        if response.digest.as_ref() != digest.as_slice() {
+            return Err(anyhow!(
+                "New agent {} received other commitments than this agent, some dealer equivocated, the ceremony is aborted",
+                member
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn lock<G: FingerprintCurve>(
+    receiver: &SharedReceiver<G>,
+) -> Result<std::sync::MutexGuard<'_, ResharingReceiver<G::ScalarExt, G>>, Error> {
+    receiver
+        .lock()
+        .map_err(|_| anyhow!("Resharing state is not available"))
+}
+
+/// Authenticated fields of the resharing dealing
+fn dealing_message(request: &SubmitResharingDealingRequest) -> Vec<Vec<u8>> {
+    let mut message = vec![
+        b"resharing-dealing".to_vec(),
+        request.ceremony_id.as_bytes().to_vec(),
+        request.generation.to_le_bytes().to_vec(),
+        request.dealer.to_le_bytes().to_vec(),
+        request.receiver.to_le_bytes().to_vec(),
+        (request.commitments.len() as u64).to_le_bytes().to_vec(),
+    ];
+    message.extend(request.commitments.iter().map(|c| c.to_vec()));
+    message.push(request.nonce.to_vec());
+    message.push(request.share.to_vec());
+
+    message
+}
+
+/// Context the share of the old `dealer` for the new agent `receiver` is encrypted in
+fn share_context(ceremony_id: &str, generation: u64, dealer: u64, receiver: u64) -> Vec<Vec<u8>> {
+    vec![
+        b"resharing-share".to_vec(),
+        ceremony_id.as_bytes().to_vec(),
+        generation.to_le_bytes().to_vec(),
+        dealer.to_le_bytes().to_vec(),
+        receiver.to_le_bytes().to_vec(),
+    ]
+}
+
+/// Authenticated fields of the transcript request of the new `agent`
+fn transcript_request_message(ceremony_id: &str, generation: u64, agent: usize) -> Vec<Vec<u8>> {
+    vec![
+        b"resharing-transcript-request".to_vec(),
+        ceremony_id.as_bytes().to_vec(),
+        generation.to_le_bytes().to_vec(),
+        (agent as u64).to_le_bytes().to_vec(),
+    ]
+}
+
+/// Authenticated fields of the transcript `digest` of the `responder` for the `requester`
+fn transcript_message(
+    ceremony_id: &str,
+    generation: u64,
+    responder: usize,
+    requester: usize,
+    digest: &[u8],
+) -> Vec<Vec<u8>> {
+    vec![
+        b"resharing-transcript".to_vec(),
+        ceremony_id.as_bytes().to_vec(),
+        generation.to_le_bytes().to_vec(),
+        (responder as u64).to_le_bytes().to_vec(),
+        (requester as u64).to_le_bytes().to_vec(),
+        digest.to_vec(),
+    ]
+}
+
+fn build_client(remote_address: &str) -> Result<ResharingServiceClient, Error> {
+    let addr = member_address(remote_address)?;
+
+    Ok(
+        ResharingServiceClientBuilder::new(format!("inter-agent-resharing-service-{}", addr))
+            .address(addr)
+            .build(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::pso::transaction_fingerprinting::fingerprint::agent::v1::ResharingServiceServer;
+    use crate::test_utils::local_listener;
+    use fingerprinting_core::secret_sharing::SecretSharing;
+    use halo2_axiom::halo2curves::bn256::Fr;
+    use halo2_axiom::halo2curves::group::GroupEncoding;
+    use std::net::SocketAddr;
+    use volo_grpc::server::{Server, ServiceBuilder};
+
+    const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Members of the new committee with the `identities` of new agents, except the agent `except`
+    fn members(
+        addresses: &[SocketAddr],
+        identities: &[AgentIdentity<G1>],
+        except: Option<usize>,
+    ) -> Vec<CeremonyMember<G1>> {
+        (1..=identities.len())
+            .filter(|member| Some(*member) != except)
+            .map(|member| CeremonyMember {
+                agent_id: member,
+                address: addresses
+                    .get(member - 1)
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+                identity: identities[member - 1].public(),
+            })
+            .collect()
+    }
+
+    /// Serve the resharing services of the `ceremonies` on the `listeners`
+    async fn serve(
+        ceremonies: &[&ResharingCeremony<G1>],
+        listeners: Vec<volo::net::DefaultIncoming>,
+    ) {
+        for (ceremony, listener) in ceremonies.iter().zip(listeners) {
+            tokio::spawn(
+                Server::new()
+                    .add_service(
+                        ServiceBuilder::new(ResharingServiceServer::new(ceremony.service()))
+                            .build(),
+                    )
+                    .run(listener),
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    /// Dealing of the `dealer` with the `share` for the new agent `receiver` authenticated with the `identity`
+    fn dealing(
+        dealer: &ResharingDealer<Fr, G1>,
+        identity: &AgentIdentity<G1>,
+        receiver: (usize, G1),
+        generation: u64,
+        share: Fr,
+    ) -> Result<SubmitResharingDealingRequest, Error> {
+        let channel = identity.channel(&receiver.1)?;
+        let encrypted = channel.encrypt(
+            &share_context("test", generation, dealer.index() as u64, receiver.0 as u64),
+            share.to_repr().as_ref(),
+        )?;
+
+        let mut request = SubmitResharingDealingRequest {
+            ceremony_id: FastStr::new("test"),
+            dealer: dealer.index() as u64,
+            generation,
+            receiver: receiver.0 as u64,
+            commitments: dealer
+                .commitments()
+                .commitments()
+                .iter()
+                .map(|c| Bytes::copy_from_slice(c.to_bytes().as_ref()))
+                .collect(),
+            share: encrypted.ciphertext.into(),
+            nonce: Bytes::copy_from_slice(&encrypted.nonce),
+            authentication: Bytes::new(),
+            _unknown_fields: Default::default(),
+        };
+        request.authentication = channel.authenticate(&dealing_message(&request)).into();
+
+        Ok(request)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resharing_ceremony() -> Result<(), Error> {
+        // 2-of-3 committee reshares into a 3-of-4 committee, old agents 1 and 2 deal,
+        // old agent 1 is also the new agent 1
+        let secret = Fr::from(1234567u64);
+        let old = SecretSharing::generate(secret, 2, 3);
+        let old_commitments = old.commitments::<G1>();
+        let dealers = [1, 2];
+
+        let new_identities = (0..4)
+            .map(|_| AgentIdentity::<G1>::generate())
+            .collect::<Vec<_>>();
+        let old_agent_2_identity = AgentIdentity::<G1>::generate();
+        let dealer_identities = vec![
+            (1, new_identities[0].public()),
+            (2, old_agent_2_identity.public()),
+        ];
+
+        let (addresses, listeners): (Vec<_>, Vec<_>) = (0..4)
+            .map(|_| local_listener())
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip();
+        let dealer = |index: usize| {
+            ResharingDealer::new(index, old.get_shares()[&index], &old_commitments, 3, 4)
+        };
+        let receiver =
+            |index: usize| ResharingReceiver::new(index, 3, 4, old_commitments.clone(), &dealers);
+
+        let receivers = (1..=4)
+            .map(|index| {
+                ResharingCeremony::<G1>::new(
+                    "test".to_string(),
+                    1,
+                    if index == 1 { Some(dealer(1)?) } else { None },
+                    Some(receiver(index)?),
+                    &new_identities[index - 1],
+                    dealer_identities.clone(),
+                    members(&addresses, &new_identities, Some(index)),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let old_agent_2 = ResharingCeremony::<G1>::new(
+            "test".to_string(),
+            1,
+            Some(dealer(2)?),
+            None,
+            &old_agent_2_identity,
+            dealer_identities.clone(),
+            members(&addresses, &new_identities, None),
+        )?;
+
+        serve(&receivers.iter().collect::<Vec<_>>(), listeners).await;
+
+        let (results, dealt) = tokio::join!(
+            futures::future::try_join_all(
+                receivers
+                    .iter()
+                    .map(|ceremony| ceremony.run(RETRY_INTERVAL))
+            ),
+            old_agent_2.run(RETRY_INTERVAL)
+        );
+        assert!(dealt?.is_none());
+
+        let results = results?.into_iter().flatten().collect::<Vec<_>>();
+        assert_eq!(results.len(), 4);
+
+        let commitments = &results[0].1;
+        assert_eq!(commitments.threshold(), 3);
+        assert_eq!(commitments.group_key(), old_commitments.group_key());
+        for (agent, (secret_shard, agent_commitments)) in results.iter().enumerate() {
+            assert_eq!(agent_commitments, commitments);
+            assert!(SecretSharing::verify_share(
+                agent + 1,
+                *secret_shard,
+                commitments
+            ));
+        }
+
+        // Receivers need the identities of all other dealers
+        assert!(ResharingCeremony::<G1>::new(
+            "test".to_string(),
+            1,
+            None,
+            Some(receiver(2)?),
+            &new_identities[1],
+            vec![(1, new_identities[0].public())],
+            members(&addresses, &new_identities, Some(2)),
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_invalid_resharing_dealings_rejected() -> Result<(), Error> {
+        let old = SecretSharing::generate(Fr::from(7u64), 2, 3);
+        let old_commitments = old.commitments::<G1>();
+        let dealer =
+            ResharingDealer::<Fr, G1>::new(1, old.get_shares()[&1], &old_commitments, 2, 2)?;
+
+        let dealer_identities = (0..3)
+            .map(|_| AgentIdentity::<G1>::generate())
+            .collect::<Vec<_>>();
+        let new_identities = (0..2)
+            .map(|_| AgentIdentity::<G1>::generate())
+            .collect::<Vec<_>>();
+
+        let receiver = ResharingCeremony::<G1>::new(
+            "test".to_string(),
+            1,
+            None,
+            Some(ResharingReceiver::new(
+                1,
+                2,
+                2,
+                old_commitments.clone(),
+                &[1, 2],
+            )?),
+            &new_identities[0],
+            vec![
+                (1, dealer_identities[0].public()),
+                (2, dealer_identities[1].public()),
+            ],
+            members(&[], &new_identities, Some(1)),
+        )?
+        .service();
+        let old_agent_3 = ResharingCeremony::<G1>::new(
+            "test".to_string(),
+            1,
+            Some(ResharingDealer::new(
+                3,
+                old.get_shares()[&3],
+                &old_commitments,
+                2,
+                2,
+            )?),
+            None,
+            &dealer_identities[2],
+            vec![],
+            members(&[], &new_identities, None),
+        )?
+        .service();
+
+        let new_agent_1 = (1, new_identities[0].public());
+        let share = dealer.share_for(1)?;
+        let authentic = || dealing(&dealer, &dealer_identities[0], new_agent_1, 1, share);
+
+        let status = receiver
+            .submit_resharing_dealing(Request::new(dealing(
+                &dealer,
+                &dealer_identities[0],
+                new_agent_1,
+                2,
+                share,
+            )?))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        let status = receiver
+            .submit_resharing_dealing(Request::new(dealing(
+                &dealer,
+                &dealer_identities[0],
+                new_agent_1,
+                1,
+                share + Fr::one(),
+            )?))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        // Dealing of dealer 1 authenticated with a foreign identity
+        let status = receiver
+            .submit_resharing_dealing(Request::new(dealing(
+                &dealer,
+                &AgentIdentity::generate(),
+                new_agent_1,
+                1,
+                share,
+            )?))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // Dealing altered on the way
+        let mut altered = authentic()?;
+        altered.commitments.reverse();
+        let status = receiver
+            .submit_resharing_dealing(Request::new(altered))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // Dealing for another new agent
+        let mut misdirected = authentic()?;
+        misdirected.receiver = 2;
+        misdirected.authentication = dealer_identities[0]
+            .channel(&new_identities[0].public())?
+            .authenticate(&dealing_message(&misdirected))
+            .into();
+        let status = receiver
+            .submit_resharing_dealing(Request::new(misdirected))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        let status = old_agent_3
+            .submit_resharing_dealing(Request::new(authentic()?))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        receiver
+            .submit_resharing_dealing(Request::new(authentic()?))
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_equivocating_resharing_dealer_aborts_ceremony() -> Result<(), Error> {
+        // 2-of-3 committee reshares into a 2-of-2 committee, old agents 1 and 2 deal
+        let old = SecretSharing::generate(Fr::from(7u64), 2, 3);
+        let old_commitments = old.commitments::<G1>();
+
+        let dealer_identities = (0..2)
+            .map(|_| AgentIdentity::<G1>::generate())
+            .collect::<Vec<_>>();
+        let new_identities = (0..2)
+            .map(|_| AgentIdentity::<G1>::generate())
+            .collect::<Vec<_>>();
+        let identities_of_dealers = vec![
+            (1, dealer_identities[0].public()),
+            (2, dealer_identities[1].public()),
+        ];
+
+        let (addresses, listeners): (Vec<_>, Vec<_>) = (0..2)
+            .map(|_| local_listener())
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip();
+        let dealer = |index: usize| {
+            ResharingDealer::<Fr, G1>::new(index, old.get_shares()[&index], &old_commitments, 2, 2)
+        };
+
+        let receivers = (1..=2)
+            .map(|index| {
+                ResharingCeremony::<G1>::new(
+                    "test".to_string(),
+                    1,
+                    None,
+                    Some(ResharingReceiver::new(
+                        index,
+                        2,
+                        2,
+                        old_commitments.clone(),
+                        &[1, 2],
+                    )?),
+                    &new_identities[index - 1],
+                    identities_of_dealers.clone(),
+                    members(&addresses, &new_identities, Some(index)),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let old_agent_2 = ResharingCeremony::<G1>::new(
+            "test".to_string(),
+            1,
+            Some(dealer(2)?),
+            None,
+            &dealer_identities[1],
+            identities_of_dealers.clone(),
+            members(&addresses, &new_identities, None),
+        )?;
+
+        serve(&receivers.iter().collect::<Vec<_>>(), listeners).await;
+
+        // Old agent 1 sends dealings of two different polynomials, each valid on its own, to the new agents
+        for receiver in [1, 2] {
+            let equivocating = dealer(1)?;
+            build_client(&addresses[receiver - 1].to_string())?
+                .submit_resharing_dealing(dealing(
+                    &equivocating,
+                    &dealer_identities[0],
+                    (receiver, new_identities[receiver - 1].public()),
+                    1,
+                    equivocating.share_for(receiver)?,
+                )?)
+                .await?;
+        }
+
+        let (results, dealt) = tokio::time::timeout(
+            Duration::from_secs(30),
+            futures::future::join(
+                futures::future::join_all(
+                    receivers
+                        .iter()
+                        .map(|ceremony| ceremony.run(RETRY_INTERVAL)),
+                ),
+                old_agent_2.run(RETRY_INTERVAL),
+            ),
+        )
+        .await?;
+        assert!(dealt?.is_none());
+
+        // New agents find out comparing transcripts and don't finalize
+        for result in results {
+            let error = result.unwrap_err().to_string();
+            assert!(error.contains("equivocated"), "{}", error);
+        }
+
+        Ok(())
+    }
+}
@@ -5,42 +5,259 @@ mod generator {
 }
 
 use crate::net::pso::transaction_fingerprinting::fingerprint::v1::{
-    compute_batch_fingerprint_request::Item, ComputeBatchFingerprintRequest,
-    ComputeBatchFingerprintResponse, ComputeSingleFingerprintRequest,
-    ComputeSingleFingerprintResponse,
+    compute_batch_fingerprint_request::Item, compute_single_fingerprint_response::Neighbour,
+    CheckAndRecordFingerprintRequest, CheckAndRecordFingerprintResponse,
+    ComputeBatchFingerprintRequest, ComputeBatchFingerprintResponse,
+    ComputeSingleFingerprintRequest, ComputeSingleFingerprintResponse, EvaluateBlindedBatchRequest,
+    EvaluateBlindedBatchResponse, EvaluateBlindedRequest, EvaluateBlindedResponse,
+    Fingerprint as FingerprintMessage, GetGroupKeyRequest, GetGroupKeyResponse,
+    VerifyFingerprintRequest, VerifyFingerprintResponse,
 };
-use fingerprinting_core::{Fingerprint, FingerprintProtocol, TransactionFingerprintData};
+use anyhow::anyhow;
+use chrono::Utc;
+use fingerprinting_core::curves::{decode_point, FingerprintCurve};
+use fingerprinting_core::schema::{FingerprintSchema, SchemaRegistry};
+use fingerprinting_core::tenant::{Tenant, TenantScope};
+use fingerprinting_core::{
+    BlindedEvaluation, BlindedProtocol, Fingerprint, FingerprintProtocol, TenantEvaluation,
+    TenantEvaluator, TransactionFingerprintData,
+};
+use fingerprinting_store::{FingerprintStore, StoredFingerprint, MAX_ITEM_ID_LENGTH};
 use fingerprinting_types::RawTransaction;
 use futures::stream::StreamExt;
-use halo2_axiom::halo2curves::bn256::Fr;
-use std::sync::Arc;
+use halo2_axiom::halo2curves::bn256::{Fr, G1};
+use halo2_axiom::halo2curves::group::GroupEncoding;
+use pilota::Bytes;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+use subtle::ConstantTimeEq;
 use tokio::sync::mpsc;
 use volo_grpc::codegen::ReceiverStream;
 use volo_grpc::{BoxStream, Code, Request, Response, Status};
 
 pub use generator::proto_gen::*; // Reexport only subpackage from `proto_gen`
 
-pub struct FingerprintService<P: FingerprintProtocol<Fr>> {
+/// Number of batch items computed together in one protocol round
+const BATCH_CHUNK_SIZE: usize = 1024;
+
+/// Maximum number of neighbour buckets on each side in one `ComputeSingleFingerprint` request
+pub const MAX_NEIGHBOUR_BUCKETS: u32 = 16;
+
+/// Maximum number of blinded elements in one `EvaluateBlindedBatch` request
+pub const MAX_BLINDED_BATCH_SIZE: usize = 4096;
+
+/// Group public key `([k] G1, [k] G2)`, set once it is derived from the public shares of agents
+pub type GroupKey<G = G1> = Arc<OnceLock<(G, <G as FingerprintCurve>::G2)>>;
+
+/// Scalar of the network curve the protocol `P` computes on
+type Scalar<P> =
+    <<P as FingerprintProtocol<Fr>>::Point as halo2_axiom::halo2curves::group::Group>::Scalar;
+
+///
+/// Tenants each caller of the service may use. Callers authenticate with the `authorization: Bearer <token>` metadata,
+/// only SHA-256 digests of the tokens are kept. The service itself doesn't terminate TLS, the tokens are protected
+/// only when callers reach it through a TLS-terminating proxy.
+#[derive(Debug, Default)]
+pub struct TenantAllowlist {
+    callers: HashMap<[u8; 32], (String, HashSet<Tenant>)>, // by digest of the token
+}
+
+impl TenantAllowlist {
+    /// Allow the caller `name` authenticating with the `token` to use the `tenants`, including the default one if listed
+    pub fn with_caller(
+        mut self,
+        name: &str,
+        token: &str,
+        tenants: impl IntoIterator<Item = Tenant>,
+    ) -> Result<Self, anyhow::Error> {
+        if token.is_empty() {
+            return Err(anyhow!("Token of caller {} is empty", name));
+        }
+
+        let tenants = tenants.into_iter().collect();
+        if let Some((other, _)) = self
+            .callers
+            .insert(token_digest(token), (name.to_string(), tenants))
+        {
+            return Err(anyhow!(
+                "Callers {} and {} authenticate with the same token",
+                other,
+                name
+            ));
+        }
+
+        Ok(self)
+    }
+
+    /// Check that the caller of the `metadata` may use the `tenant`
+    fn check(
+        &self,
+        metadata: &volo_grpc::metadata::MetadataMap,
+        tenant: &Tenant,
+    ) -> Result<(), Box<Status>> {
+        let token = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| {
+                Status::new(
+                    Code::Unauthenticated,
+                    "Missing bearer token in the authorization metadata",
+                )
+            })?;
+
+        let (caller, tenants) = self
+            .callers
+            .get(&token_digest(token))
+            .ok_or_else(|| Status::new(Code::Unauthenticated, "Unknown bearer token"))?;
+
+        if !tenants.contains(tenant) {
+            return Err(Box::new(Status::new(
+                Code::PermissionDenied,
+                format!("Caller {} is not allowed to use tenant {}", caller, tenant),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn token_digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+pub struct FingerprintService<P: FingerprintProtocol<Fr, Point: FingerprintCurve>> {
     protocol: Arc<P>,
+    group_key: GroupKey<P::Point>,
+    schemas: SchemaRegistry,
+    store: Option<Arc<dyn FingerprintStore>>,
+    allowlist: Option<TenantAllowlist>,
 }
 
-impl<P: FingerprintProtocol<Fr> + Sync> FingerprintService<P> {
+impl<P: FingerprintProtocol<Fr, Point: FingerprintCurve> + Sync> FingerprintService<P> {
     pub fn new(protocol: P) -> FingerprintService<P> {
         FingerprintService {
             protocol: Arc::new(protocol),
+            group_key: GroupKey::default(),
+            schemas: SchemaRegistry::default(),
+            store: None,
+            allowlist: None,
+        }
+    }
+
+    /// Compute fingerprints with the schemas of the `schemas` registry, by default only the first schema is supported
+    pub fn with_schemas(mut self, schemas: SchemaRegistry) -> Self {
+        self.schemas = schemas;
+        self
+    }
+
+    /// Schema of the requested `version`, `0` stands for the current schema
+    fn schema(&self, version: u32) -> Result<FingerprintSchema, String> {
+        if version == 0 {
+            return Ok(self.schemas.current().clone());
         }
+
+        self.schemas.get(version).cloned().ok_or(format!(
+            "Schema version {} is not supported, supported versions are {:?}",
+            version,
+            self.schemas.versions().collect::<Vec<_>>()
+        ))
+    }
+
+    /// Record fingerprints via `CheckAndRecordFingerprint` in the `store`, without a store the RPC is not available
+    pub fn with_store(mut self, store: Arc<dyn FingerprintStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Publish the `group_key` via `GetGroupKey` as soon as it is set
+    pub fn with_group_key(mut self, group_key: GroupKey<P::Point>) -> Self {
+        self.group_key = group_key;
+        self
+    }
+
+    /// Authenticate callers and let them use only the tenants of the `allowlist`,
+    /// without an allowlist anyone may use the default tenant and nobody the other ones
+    pub fn with_tenant_allowlist(mut self, allowlist: TenantAllowlist) -> Self {
+        self.allowlist = Some(allowlist);
+        self
     }
 }
 
-impl<P: FingerprintProtocol<Fr> + Send + Sync + 'static>
-    net::pso::transaction_fingerprinting::fingerprint::v1::FingerprintService
+impl<P: FingerprintProtocol<Fr, Point: FingerprintCurve> + TenantEvaluation<P::Point> + Sync>
+    FingerprintService<P>
+{
+    /// Scope of the requested `tenant`, the caller of the `request` must be allowed to use it
+    fn tenant_scope<T>(
+        &self,
+        request: &Request<T>,
+        tenant: &str,
+    ) -> Result<TenantScope<Scalar<P>>, Box<Status>> {
+        let tenant =
+            Tenant::new(tenant).map_err(|e| Status::new(Code::InvalidArgument, e.to_string()))?;
+
+        match &self.allowlist {
+            Some(allowlist) => allowlist.check(request.metadata(), &tenant)?,
+            None if tenant.is_default() => {}
+            None => {
+                return Err(Box::new(Status::new(
+                    Code::PermissionDenied,
+                    format!(
+                        "Tenant {} is not allowed, the service has no tenant allowlist",
+                        tenant
+                    ),
+                )))
+            }
+        }
+
+        self.protocol
+            .scope(&tenant)
+            .map_err(|e| Box::new(Status::new(Code::FailedPrecondition, e.to_string())))
+    }
+}
+
+/// Protocol computing fingerprints of the tenant `scope`, the points are blinded before they are evaluated
+fn tenant_protocol<
+    P: FingerprintProtocol<Fr, Point: FingerprintCurve> + TenantEvaluation<P::Point> + Sync,
+>(
+    protocol: &P,
+    scope: TenantScope<Scalar<P>>,
+) -> BlindedProtocol<TenantEvaluator<'_, P, P::Point>, P::Point> {
+    BlindedProtocol::new(TenantEvaluator::new(protocol, scope)).with_dst(protocol.dst().clone())
+}
+
+impl<
+        P: FingerprintProtocol<Fr, Point: FingerprintCurve>
+            + BlindedEvaluation<P::Point>
+            + TenantEvaluation<P::Point>
+            + Send
+            + Sync
+            + 'static,
+    > net::pso::transaction_fingerprinting::fingerprint::v1::FingerprintService
     for FingerprintService<P>
 {
     async fn compute_single_fingerprint(
         &self,
         req: Request<ComputeSingleFingerprintRequest>,
     ) -> Result<Response<ComputeSingleFingerprintResponse>, Status> {
+        let scope = self
+            .tenant_scope(&req, &req.get_ref().tenant)
+            .map_err(|e| *e)?;
+        let protocol = tenant_protocol(self.protocol.as_ref(), scope);
         let request = req.into_inner();
+        let schema = self
+            .schema(request.schema_version)
+            .map_err(|e| Status::new(Code::InvalidArgument, e))?;
+        if request.neighbour_buckets > MAX_NEIGHBOUR_BUCKETS {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!(
+                    "Too many neighbour buckets requested: {}, maximum is {}",
+                    request.neighbour_buckets, MAX_NEIGHBOUR_BUCKETS
+                ),
+            ));
+        }
         let tx_data = request.transaction_data.ok_or(Status::new(
             Code::InvalidArgument,
             "Transaction data missing",
@@ -49,21 +266,57 @@ impl<P: FingerprintProtocol<Fr> + Send + Sync + 'static>
 
         // preparing TransactionFingerprintData
         let raw_tx: TransactionFingerprintData<Fr> = raw_tx.try_into()?;
+        let raw_tx = raw_tx.with_schema(schema);
 
-        // using the provided protocol built the fingerprint
-        let fingerprint = raw_tx
-            .complete_fingerprint(self.protocol.as_ref())
-            .await
-            .map_err(|e| {
-                Status::new(
-                    Code::Aborted,
-                    format!("Failed to complete fingerprint computation: {}", e),
-                )
-            })?
-            .into();
+        // using the provided protocol built the fingerprint, the neighbours in the same protocol round
+        // (the bucket of the transaction included)
+        let computed = if request.neighbour_buckets > 0 {
+            raw_tx
+                .complete_verifiable_neighbour_fingerprints(&protocol, request.neighbour_buckets)
+                .await
+        } else {
+            raw_tx
+                .complete_verifiable_fingerprint(&protocol)
+                .await
+                .map(|fingerprint| vec![(0, fingerprint)])
+        }
+        .map_err(|e| {
+            Status::new(
+                Code::Aborted,
+                format!("Failed to complete fingerprint computation: {}", e),
+            )
+        })?;
+
+        let fingerprint = computed
+            .iter()
+            .find(|(offset, _)| *offset == 0)
+            .map(|(_, fingerprint)| FingerprintMessage {
+                schema_version: raw_tx.schema().version(),
+                ..(*fingerprint).into()
+            })
+            .ok_or(Status::new(
+                Code::Internal,
+                "Fingerprint of the transaction bucket missing",
+            ))?;
+
+        let neighbours = if request.neighbour_buckets > 0 {
+            computed
+                .into_iter()
+                .map(|(offset, (fingerprint, _))| Neighbour {
+                    offset,
+                    fingerprint: Some(FingerprintMessage {
+                        schema_version: raw_tx.schema().version(),
+                        ..fingerprint.into()
+                    }),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
 
         let response = ComputeSingleFingerprintResponse {
             fingerprint: Some(fingerprint),
+            neighbours,
             _unknown_fields: Default::default(),
         };
 
@@ -75,45 +328,88 @@ impl<P: FingerprintProtocol<Fr> + Send + Sync + 'static>
         req: Request<ComputeBatchFingerprintRequest>,
     ) -> Result<Response<BoxStream<'static, Result<ComputeBatchFingerprintResponse, Status>>>, Status>
     {
+        let scope = self
+            .tenant_scope(&req, &req.get_ref().tenant)
+            .map_err(|e| *e)?;
         let request = req.into_inner();
+        let schema = self
+            .schema(request.schema_version)
+            .map_err(|e| Status::new(Code::InvalidArgument, e))?;
         let tx_data = request.transaction_batch;
         let protocol = Arc::clone(&self.protocol);
 
+        // Group items so that every chunk takes one protocol round (one request per cooperating agent)
         let mut stream = futures::stream::iter(tx_data)
-            .map(move |item: Item| {
+            .chunks(BATCH_CHUNK_SIZE)
+            .map(move |chunk: Vec<Item>| {
                 let protocol = Arc::clone(&protocol);
+                let schema = schema.clone();
+                let scope = scope.clone();
                 async move {
-                    let item_id = item.item_id;
-                    let raw_tx = item.transaction_data.ok_or(Status::new(
-                        Code::InvalidArgument,
-                        "Transaction data missing",
-                    ))?;
+                    let protocol = tenant_protocol(protocol.as_ref(), scope);
+                    let mut transactions = Vec::with_capacity(chunk.len());
+                    let mut prepared = Vec::with_capacity(chunk.len());
 
-                    let raw_tx: RawTransaction = raw_tx.try_into()?;
+                    for item in chunk {
+                        let tx_data: Result<TransactionFingerprintData<Fr>, Status> = item
+                            .transaction_data
+                            .ok_or(Status::new(
+                                Code::InvalidArgument,
+                                "Transaction data missing",
+                            ))
+                            .and_then(TryInto::try_into)
+                            .map(|tx_data: TransactionFingerprintData<Fr>| {
+                                tx_data.with_schema(schema.clone())
+                            });
 
-                    // preparing TransactionFingerprintData
-                    let raw_tx: TransactionFingerprintData<Fr> = raw_tx.try_into()?;
+                        match tx_data {
+                            Ok(tx_data) => {
+                                transactions.push(tx_data);
+                                prepared.push(Ok(item.item_id));
+                            }
+                            Err(status) => prepared.push(Err(status)),
+                        }
+                    }
 
-                    // using the provided protocol built the fingerprint
-                    let fingerprint = raw_tx
-                        .complete_fingerprint(protocol.as_ref())
+                    // using the provided protocol built the fingerprints of the whole chunk
+                    let mut fingerprints =
+                        TransactionFingerprintData::complete_verifiable_fingerprints(
+                            &transactions,
+                            &protocol,
+                        )
                         .await
-                        .map_err(|e| {
-                            Status::new(
-                                Code::Aborted,
-                                format!("Failed to complete fingerprint computation: {}", e),
-                            )
-                        })?
-                        .into();
-
-                    Ok(ComputeBatchFingerprintResponse {
-                        item_id,
-                        fingerprint: Some(fingerprint),
-                        _unknown_fields: Default::default(),
-                    })
+                        .into_iter();
+
+                    let mut responses = Vec::with_capacity(prepared.len());
+
+                    for item in prepared {
+                        let response = match item {
+                            Ok(item_id) => match fingerprints.next() {
+                                Some(Ok(fingerprint)) => Ok(ComputeBatchFingerprintResponse {
+                                    item_id,
+                                    fingerprint: Some(FingerprintMessage {
+                                        schema_version: schema.version(),
+                                        ..fingerprint.into()
+                                    }),
+                                    _unknown_fields: Default::default(),
+                                }),
+                                Some(Err(e)) => Err(Status::new(
+                                    Code::Aborted,
+                                    format!("Failed to complete fingerprint computation: {}", e),
+                                )),
+                                None => Err(Status::new(Code::Internal, "Missing fingerprint")),
+                            },
+                            Err(status) => Err(status),
+                        };
+
+                        responses.push(response);
+                    }
+
+                    responses
                 }
             })
-            .buffer_unordered(16);
+            .buffer_unordered(4)
+            .flat_map(futures::stream::iter);
 
         let (tx, rx) = mpsc::channel(16);
 
@@ -130,15 +426,276 @@ impl<P: FingerprintProtocol<Fr> + Send + Sync + 'static>
 
         Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
     }
+
+    async fn evaluate_blinded(
+        &self,
+        req: Request<EvaluateBlindedRequest>,
+    ) -> Result<Response<EvaluateBlindedResponse>, Status> {
+        let scope = self
+            .tenant_scope(&req, &req.get_ref().tenant)
+            .map_err(|e| *e)?;
+        let request = req.into_inner();
+        let blinded = decode_blinded_element::<P::Point>(&request.blinded_element)
+            .map_err(|e| Status::new(Code::InvalidArgument, e))?;
+
+        let evaluated = self
+            .protocol
+            .evaluate_for(&scope, blinded)
+            .await
+            .map_err(|e| {
+                Status::new(
+                    Code::Aborted,
+                    format!("Failed to evaluate blinded element: {}", e),
+                )
+            })?;
+
+        let response = EvaluateBlindedResponse {
+            evaluated_element: Bytes::copy_from_slice(evaluated.to_bytes().as_ref()),
+            _unknown_fields: Default::default(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn evaluate_blinded_batch(
+        &self,
+        req: Request<EvaluateBlindedBatchRequest>,
+    ) -> Result<Response<EvaluateBlindedBatchResponse>, Status> {
+        let scope = self
+            .tenant_scope(&req, &req.get_ref().tenant)
+            .map_err(|e| *e)?;
+        let request = req.into_inner();
+
+        if request.blinded_elements.len() > MAX_BLINDED_BATCH_SIZE {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!(
+                    "Batch of {} blinded elements exceeds the limit of {}",
+                    request.blinded_elements.len(),
+                    MAX_BLINDED_BATCH_SIZE
+                ),
+            ));
+        }
+
+        let mut blinded = Vec::with_capacity(request.blinded_elements.len());
+        for element in &request.blinded_elements {
+            blinded.push(
+                decode_blinded_element::<P::Point>(element)
+                    .map_err(|e| Status::new(Code::InvalidArgument, e))?,
+            );
+        }
+
+        let mut evaluated_elements = Vec::with_capacity(blinded.len());
+        for evaluated in self.protocol.evaluate_batch_for(&scope, blinded).await {
+            let evaluated = evaluated.map_err(|e| {
+                Status::new(
+                    Code::Aborted,
+                    format!("Failed to evaluate blinded elements: {}", e),
+                )
+            })?;
+
+            evaluated_elements.push(Bytes::copy_from_slice(evaluated.to_bytes().as_ref()));
+        }
+
+        let response = EvaluateBlindedBatchResponse {
+            evaluated_elements,
+            _unknown_fields: Default::default(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn check_and_record_fingerprint(
+        &self,
+        req: Request<CheckAndRecordFingerprintRequest>,
+    ) -> Result<Response<CheckAndRecordFingerprintResponse>, Status> {
+        let store = self.store.as_ref().map(Arc::clone).ok_or(Status::new(
+            Code::FailedPrecondition,
+            "Fingerprint store is not configured",
+        ))?;
+        let scope = self
+            .tenant_scope(&req, &req.get_ref().tenant)
+            .map_err(|e| *e)?;
+        let request = req.into_inner();
+
+        if request.item_id.is_empty() || request.item_id.len() > MAX_ITEM_ID_LENGTH {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!(
+                    "Item id must be 1 to {} bytes long, got {}",
+                    MAX_ITEM_ID_LENGTH,
+                    request.item_id.len()
+                ),
+            ));
+        }
+        let schema = self
+            .schema(request.schema_version)
+            .map_err(|e| Status::new(Code::InvalidArgument, e))?;
+
+        // Only fingerprints computed by the service are recorded, so that callers can't plant arbitrary values
+        // colliding with the fingerprints of other items
+        let tx_data = request.transaction_data.ok_or(Status::new(
+            Code::InvalidArgument,
+            "Transaction data missing",
+        ))?;
+        let raw_tx: TransactionFingerprintData<Fr> = tx_data.try_into()?;
+        let raw_tx = raw_tx.with_schema(schema.clone());
+
+        // Occurrences are recorded per tenant, so that callers of one tenant never learn the items of another one
+        let tenant = scope.tenant().as_str().to_string();
+        let fingerprint: FingerprintMessage = raw_tx
+            .complete_verifiable_fingerprint(&tenant_protocol(self.protocol.as_ref(), scope))
+            .await
+            .map_err(|e| {
+                Status::new(
+                    Code::Aborted,
+                    format!("Failed to complete fingerprint computation: {}", e),
+                )
+            })?
+            .into();
+        let fingerprint = FingerprintMessage {
+            schema_version: schema.version(),
+            ..fingerprint
+        };
+
+        let stored = StoredFingerprint::new(
+            schema.version(),
+            fingerprint
+                .fingerprint
+                .as_ref()
+                .try_into()
+                .map_err(|_| Status::new(Code::Internal, "Fingerprint is not 32 bytes long"))?,
+        )
+        .with_tenant(&tenant);
+        let item_id = request.item_id.to_string();
+        let earlier = tokio::task::spawn_blocking(move || {
+            store.check_and_record(&stored, &item_id, Utc::now())
+        })
+        .await
+        .map_err(|e| {
+            Status::new(
+                Code::Internal,
+                format!("Failed to record fingerprint: {}", e),
+            )
+        })?
+        .map_err(|e| {
+            Status::new(
+                Code::Internal,
+                format!("Failed to record fingerprint: {}", e),
+            )
+        })?;
+
+        let response = CheckAndRecordFingerprintResponse {
+            fingerprint: Some(fingerprint),
+            duplicate: !earlier.is_empty(),
+            earlier_occurrences: earlier.into_iter().map(Into::into).collect(),
+            _unknown_fields: Default::default(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn verify_fingerprint(
+        &self,
+        req: Request<VerifyFingerprintRequest>,
+    ) -> Result<Response<VerifyFingerprintResponse>, Status> {
+        let scope = self
+            .tenant_scope(&req, &req.get_ref().tenant)
+            .map_err(|e| *e)?;
+        let request = req.into_inner();
+        let schema = self
+            .schema(request.schema_version)
+            .map_err(|e| Status::new(Code::InvalidArgument, e))?;
+        let claimed: &[u8; 32] = request.fingerprint.as_ref().try_into().map_err(|_| {
+            Status::new(
+                Code::InvalidArgument,
+                "Invalid fingerprint, it should be exactly 32 bytes long",
+            )
+        })?;
+        let tx_data = request.transaction_data.ok_or(Status::new(
+            Code::InvalidArgument,
+            "Transaction data missing",
+        ))?;
+
+        let raw_tx: TransactionFingerprintData<Fr> = tx_data.try_into()?;
+        let raw_tx = raw_tx.with_schema(schema);
+
+        let fingerprint = raw_tx
+            .complete_fingerprint(&tenant_protocol(self.protocol.as_ref(), scope))
+            .await
+            .map_err(|e| {
+                Status::new(
+                    Code::Aborted,
+                    format!("Failed to complete fingerprint computation: {}", e),
+                )
+            })?;
+
+        let response = VerifyFingerprintResponse {
+            matches: fingerprint_matches(claimed, &fingerprint),
+            _unknown_fields: Default::default(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn get_group_key(
+        &self,
+        req: Request<GetGroupKeyRequest>,
+    ) -> Result<Response<GetGroupKeyResponse>, Status> {
+        let scope = self
+            .tenant_scope(&req, &req.get_ref().tenant)
+            .map_err(|e| *e)?;
+        let (group_key, group_key_g2) = self.group_key.get().ok_or(Status::new(
+            Code::Unavailable,
+            "Group key is not derived yet",
+        ))?;
+
+        // Group key of the tenant `[k * t] G`
+        let group_key = *group_key * scope.scalar();
+        let group_key_g2 = *group_key_g2 * scope.scalar();
+
+        let response = GetGroupKeyResponse {
+            group_key: Bytes::copy_from_slice(group_key.to_bytes().as_ref()),
+            group_key_g2: Bytes::copy_from_slice(group_key_g2.to_bytes().as_ref()),
+            curve: P::Point::CURVE.name().into(),
+            _unknown_fields: Default::default(),
+        };
+
+        Ok(Response::new(response))
+    }
+}
+
+/// Compare the claimed fingerprint with the computed one in constant time, so that the timing of the response
+/// doesn't reveal how many leading bytes of the claimed fingerprint are right
+fn fingerprint_matches(claimed: &[u8; 32], fingerprint: &Fr) -> bool {
+    claimed.ct_eq(&fingerprint.to_bytes()).into()
+}
+
+/// Decode the compressed point blinded by the client, identity is not accepted
+fn decode_blinded_element<G: FingerprintCurve>(bytes: &[u8]) -> Result<G, String> {
+    let point = decode_point::<G>(bytes)
+        .map_err(|e| format!("Invalid blinded element of {}, {}", G::CURVE.name(), e))?;
+
+    if bool::from(point.is_identity()) {
+        return Err("Invalid blinded element, identity point is not accepted".to_string());
+    }
+
+    Ok(point)
 }
 
 mod dto_convert {
     use crate::net;
+    use crate::net::pso::transaction_fingerprinting::fingerprint::v1::Direction;
     use anyhow::anyhow;
-    use chrono::{DateTime, Utc};
-    use fingerprinting_core::Compact;
+    use chrono::{DateTime, Datelike, NaiveDate, Utc};
+    use crate::net::pso::transaction_fingerprinting::fingerprint::v1::check_and_record_fingerprint_response::Occurrence as OccurrenceMessage;
+    use fingerprinting_core::{Compact, TransactionFingerprintData};
+    use fingerprinting_store::Occurrence;
     use fingerprinting_types::currencies::Currency;
-    use fingerprinting_types::{Money, RawTransaction, RawTransactionBuilder};
+    use fingerprinting_types::{
+        Direction as TransactionDirection, Money, RawTransaction, RawTransactionBuilder,
+    };
+    use fingerprinting_core::curves::FingerprintCurve;
     use halo2_axiom::halo2curves::bn256::Fr;
     use pilota::FastStr;
     use volo_grpc::{Code, Status};
@@ -153,6 +710,21 @@ mod dto_convert {
         }
     }
 
+    impl TryInto<NaiveDate> for net::pso::transaction_fingerprinting::common::v1::Date {
+        type Error = anyhow::Error;
+
+        fn try_into(self) -> Result<NaiveDate, Self::Error> {
+            let year = i32::try_from(self.year).map_err(|_| anyhow!("Date year out of range"))?;
+
+            NaiveDate::from_ymd_opt(year, self.month, self.day).ok_or(anyhow!(
+                "Date {}-{}-{} is not valid",
+                self.year,
+                self.month,
+                self.day
+            ))
+        }
+    }
+
     impl TryInto<Money> for net::pso::transaction_fingerprinting::common::v1::Money {
         type Error = anyhow::Error;
 
@@ -164,11 +736,14 @@ mod dto_convert {
             let currency = Currency::try_from(currency_numeric_code)
                 .map_err(|_| anyhow!("Invalid of unknown currency"))?;
 
-            Ok(Money {
+            let money = Money {
                 amount_base: self.units,
                 amount_atto: self.atto,
                 currency,
-            })
+            };
+            money.validate()?;
+
+            Ok(money)
         }
     }
 
@@ -188,19 +763,117 @@ mod dto_convert {
             ))?;
 
             let date_time: DateTime<Utc> = tx_date_time.try_into()?;
-            let amount: Money = tx_amount.try_into()?;
-
-            let raw_tx = RawTransactionBuilder::default()
-                .bic(self.bic)
-                .date_time(date_time)
-                .amount(amount)
-                .build()
-                .map_err(|e| {
-                    Status::new(
-                        Code::InvalidArgument,
-                        format!("Failed to build transaction: {}", e),
-                    )
+            let amount: Money = tx_amount.try_into().map_err(|e: anyhow::Error| {
+                Status::new(Code::InvalidArgument, format!("Invalid amount: {}", e))
+            })?;
+
+            let mut raw_tx = RawTransactionBuilder::default();
+            raw_tx.bic(self.bic).date_time(date_time).amount(amount);
+
+            if let Some(iban) = self.iban {
+                raw_tx.iban(iban.to_string());
+            }
+
+            if let Some(reference) = self.reference {
+                raw_tx.reference(reference.to_string());
+            }
+
+            if let Some(value_date) = self.value_date {
+                let value_date: NaiveDate = value_date.try_into().map_err(|e| {
+                    Status::new(Code::InvalidArgument, format!("Invalid value date: {}", e))
                 })?;
+                raw_tx.value_date(value_date);
+            }
+
+            match self.direction {
+                Direction::DIRECTION_UNSPECIFIED => {}
+                Direction::DIRECTION_DEBIT => {
+                    raw_tx.direction(TransactionDirection::Debit);
+                }
+                Direction::DIRECTION_CREDIT => {
+                    raw_tx.direction(TransactionDirection::Credit);
+                }
+                direction => {
+                    return Err(Status::new(
+                        Code::InvalidArgument,
+                        format!("Unknown transaction direction {}", direction.inner()),
+                    ))
+                }
+            }
+
+            let raw_tx = raw_tx.build().map_err(|e| {
+                Status::new(
+                    Code::InvalidArgument,
+                    format!("Failed to build transaction: {}", e),
+                )
+            })?;
+
+            Ok(raw_tx)
+        }
+    }
+
+    impl TryFrom<&RawTransaction>
+        for net::pso::transaction_fingerprinting::fingerprint::v1::TransactionFingerprintData
+    {
+        type Error = anyhow::Error;
+
+        fn try_from(tx: &RawTransaction) -> Result<Self, Self::Error> {
+            let seconds = u64::try_from(tx.date_time.timestamp())
+                .map_err(|_| anyhow!("Transaction date time {} is out of range", tx.date_time))?;
+            let direction = match tx.direction {
+                None => Direction::DIRECTION_UNSPECIFIED,
+                Some(TransactionDirection::Debit) => Direction::DIRECTION_DEBIT,
+                Some(TransactionDirection::Credit) => Direction::DIRECTION_CREDIT,
+            };
+
+            Ok(Self {
+                bic: FastStr::new(&tx.bic),
+                amount: Some(net::pso::transaction_fingerprinting::common::v1::Money {
+                    currency: i32::from(tx.amount.currency as u16).into(),
+                    units: tx.amount.amount_base,
+                    atto: tx.amount.amount_atto,
+                    _unknown_fields: Default::default(),
+                }),
+                date_time: Some(
+                    net::pso::transaction_fingerprinting::common::v1::Timestamp {
+                        seconds,
+                        nanos: tx.date_time.timestamp_subsec_nanos(),
+                        _unknown_fields: Default::default(),
+                    },
+                ),
+                iban: tx.iban.as_deref().map(FastStr::new),
+                reference: tx.reference.as_deref().map(FastStr::new),
+                value_date: tx
+                    .value_date
+                    .map(|value_date| {
+                        Ok::<_, anyhow::Error>(
+                            net::pso::transaction_fingerprinting::common::v1::Date {
+                                year: u32::try_from(value_date.year()).map_err(|_| {
+                                    anyhow!("Value date {} is out of range", value_date)
+                                })?,
+                                month: value_date.month(),
+                                day: value_date.day(),
+                                _unknown_fields: Default::default(),
+                            },
+                        )
+                    })
+                    .transpose()?,
+                direction,
+                _unknown_fields: Default::default(),
+            })
+        }
+    }
+
+    impl TryInto<TransactionFingerprintData<Fr>>
+        for net::pso::transaction_fingerprinting::fingerprint::v1::TransactionFingerprintData
+    {
+        type Error = Status;
+
+        fn try_into(self) -> Result<TransactionFingerprintData<Fr>, Self::Error> {
+            let raw_tx: RawTransaction = self.try_into()?;
+
+            // preparing TransactionFingerprintData
+            let raw_tx: TransactionFingerprintData<Fr> = raw_tx.try_into()?;
 
             Ok(raw_tx)
         }
@@ -211,17 +884,50 @@ mod dto_convert {
             net::pso::transaction_fingerprinting::fingerprint::v1::Fingerprint {
                 fingerprint: pilota::Bytes::copy_from_slice(value.to_bytes().as_slice()),
                 compact_fingerprint: FastStr::new(value.compact()),
+                point: pilota::Bytes::new(),
+                schema_version: 0,
                 _unknown_fields: Default::default(),
             }
         }
     }
+
+    impl From<Occurrence> for OccurrenceMessage {
+        fn from(occurrence: Occurrence) -> Self {
+            OccurrenceMessage {
+                item_id: FastStr::new(occurrence.item_id),
+                recorded_at: Some(
+                    net::pso::transaction_fingerprinting::common::v1::Timestamp {
+                        seconds: u64::try_from(occurrence.recorded_at.timestamp())
+                            .unwrap_or_default(),
+                        nanos: occurrence.recorded_at.timestamp_subsec_nanos(),
+                        _unknown_fields: Default::default(),
+                    },
+                ),
+            }
+        }
+    }
+
+    impl<G: FingerprintCurve> From<(Fr, G)>
+        for net::pso::transaction_fingerprinting::fingerprint::v1::Fingerprint
+    {
+        fn from((value, point): (Fr, G)) -> Self {
+            net::pso::transaction_fingerprinting::fingerprint::v1::Fingerprint {
+                point: pilota::Bytes::copy_from_slice(point.to_bytes().as_ref()),
+                ..value.into()
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::net::pso::transaction_fingerprinting::fingerprint::v1::FingerprintService as _;
     use chrono::Utc;
-    use fingerprinting_core::Compact;
+    use fingerprinting_core::tenant::TenantKey;
+    use fingerprinting_core::{Compact, NaiveProtocol};
+    use fingerprinting_store::MemoryStore;
+    use fingerprinting_types::test_utils::sample_transaction;
     use lazy_static::lazy_static;
     use std::net::SocketAddr;
     use volo::FastStr;
@@ -237,6 +943,245 @@ mod tests {
             .build()
         };
     }
+
+    #[test]
+    fn test_fingerprint_matches() {
+        let fingerprint = Fr::from(42);
+        let mut claimed = fingerprint.to_bytes();
+        assert!(fingerprint_matches(&claimed, &fingerprint));
+
+        claimed[31] ^= 1;
+        assert!(!fingerprint_matches(&claimed, &fingerprint));
+    }
+
+    #[test]
+    fn test_tenant_allowlist() -> Result<(), anyhow::Error> {
+        let retail = Tenant::new("retail")?;
+        let corporate = Tenant::new("corporate")?;
+        let allowlist = TenantAllowlist::default()
+            .with_caller(
+                "retail-app",
+                "s3cr3t-r",
+                [retail.clone(), Tenant::default()],
+            )?
+            .with_caller("corporate-app", "s3cr3t-c", [corporate.clone()])?;
+
+        let metadata = |token: &str| {
+            let mut metadata = volo_grpc::metadata::MetadataMap::new();
+            metadata.insert("authorization", token.parse().unwrap());
+            metadata
+        };
+
+        assert!(allowlist
+            .check(&metadata("Bearer s3cr3t-r"), &retail)
+            .is_ok());
+        assert!(allowlist
+            .check(&metadata("Bearer s3cr3t-r"), &Tenant::default())
+            .is_ok());
+        assert!(allowlist
+            .check(&metadata("Bearer s3cr3t-c"), &corporate)
+            .is_ok());
+
+        // Tenants of other callers are denied
+        let denied = allowlist
+            .check(&metadata("Bearer s3cr3t-c"), &retail)
+            .unwrap_err();
+        assert_eq!(denied.code(), Code::PermissionDenied);
+        assert!(allowlist
+            .check(&metadata("Bearer s3cr3t-c"), &Tenant::default())
+            .is_err());
+
+        // Unknown or missing tokens are not authenticated
+        for metadata in [
+            metadata("Bearer s3cr3t"),
+            metadata("s3cr3t-r"),
+            volo_grpc::metadata::MetadataMap::new(),
+        ] {
+            let unauthenticated = allowlist.check(&metadata, &retail).unwrap_err();
+            assert_eq!(unauthenticated.code(), Code::Unauthenticated);
+        }
+
+        // Tokens identify callers, they can't be shared
+        assert!(TenantAllowlist::default()
+            .with_caller("a", "token", [retail.clone()])?
+            .with_caller("b", "token", [corporate])
+            .is_err());
+        assert!(TenantAllowlist::default()
+            .with_caller("a", "", [retail])
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_check_and_record_fingerprint() -> Result<(), anyhow::Error> {
+        let protocol = NaiveProtocol::<G1>::new(Fr::from(42u64));
+        let service = FingerprintService::new(NaiveProtocol::<G1>::new(Fr::from(42u64)))
+            .with_store(Arc::new(MemoryStore::new(chrono::Duration::days(30))));
+        let request = |item_id: &str,
+                       tx: &RawTransaction|
+         -> Result<Request<CheckAndRecordFingerprintRequest>, anyhow::Error> {
+            Ok(Request::new(CheckAndRecordFingerprintRequest {
+                item_id: FastStr::new(item_id),
+                tenant: Default::default(),
+                transaction_data: Some(tx.try_into()?),
+                schema_version: 0,
+                _unknown_fields: Default::default(),
+            }))
+        };
+
+        let tx = sample_transaction().build()?;
+        let expected = TransactionFingerprintData::<Fr>::try_from(&tx)?
+            .complete_fingerprint(&protocol)
+            .await?;
+
+        // The service records the fingerprint it computes from the transaction data
+        let first = service
+            .check_and_record_fingerprint(request("item-1", &tx)?)
+            .await?
+            .into_inner();
+        assert!(!first.duplicate);
+        assert_eq!(
+            first.fingerprint.map(|fingerprint| fingerprint.fingerprint),
+            Some(Bytes::copy_from_slice(expected.to_bytes().as_slice()))
+        );
+
+        let second = service
+            .check_and_record_fingerprint(request("item-2", &tx)?)
+            .await?
+            .into_inner();
+        assert!(second.duplicate);
+        assert_eq!(
+            second
+                .earlier_occurrences
+                .iter()
+                .map(|occurrence| occurrence.item_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["item-1"]
+        );
+
+        // Requests without transaction data are rejected
+        let status = service
+            .check_and_record_fingerprint(Request::new(CheckAndRecordFingerprintRequest {
+                item_id: FastStr::new("item-3"),
+                tenant: Default::default(),
+                transaction_data: None,
+                schema_version: 0,
+                _unknown_fields: Default::default(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_check_and_record_fingerprint_per_tenant() -> Result<(), anyhow::Error> {
+        let service = FingerprintService::new(
+            NaiveProtocol::<G1>::new(Fr::from(42u64)).with_tenant_key(TenantKey::generate()),
+        )
+        .with_store(Arc::new(MemoryStore::new(chrono::Duration::days(30))))
+        .with_tenant_allowlist(TenantAllowlist::default().with_caller(
+            "bank-app",
+            "s3cr3t",
+            [
+                Tenant::default(),
+                Tenant::new("retail")?,
+                Tenant::new("corporate")?,
+            ],
+        )?);
+        let tx = sample_transaction().build()?;
+        let record = |item_id: &'static str, tenant: &'static str| {
+            let mut request = Request::new(CheckAndRecordFingerprintRequest {
+                item_id: FastStr::new(item_id),
+                tenant: FastStr::new(tenant),
+                transaction_data: Some((&tx).try_into().unwrap()),
+                schema_version: 0,
+                _unknown_fields: Default::default(),
+            });
+            request
+                .metadata_mut()
+                .insert("authorization", "Bearer s3cr3t".parse().unwrap());
+
+            service.check_and_record_fingerprint(request)
+        };
+        let earlier = |response: CheckAndRecordFingerprintResponse| {
+            response
+                .earlier_occurrences
+                .iter()
+                .map(|occurrence| occurrence.item_id.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(!record("item-1", "retail").await?.into_inner().duplicate);
+
+        // The same transaction of other tenants doesn't reveal the occurrence in the retail one
+        assert!(!record("item-2", "corporate").await?.into_inner().duplicate);
+        assert!(!record("item-3", "").await?.into_inner().duplicate);
+
+        assert_eq!(
+            earlier(record("item-4", "retail").await?.into_inner()),
+            vec!["item-1"]
+        );
+        assert_eq!(
+            earlier(record("item-5", "corporate").await?.into_inner()),
+            vec!["item-2"]
+        );
+        assert_eq!(
+            earlier(record("item-6", "").await?.into_inner()),
+            vec!["item-3"]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_fingerprint() -> Result<(), anyhow::Error> {
+        let service = FingerprintService::new(NaiveProtocol::<G1>::new(Fr::from(42u64)));
+        let request = |tx: &RawTransaction,
+                       fingerprint: &[u8]|
+         -> Result<Request<VerifyFingerprintRequest>, anyhow::Error> {
+            Ok(Request::new(VerifyFingerprintRequest {
+                tenant: Default::default(),
+                transaction_data: Some(tx.try_into()?),
+                fingerprint: Bytes::copy_from_slice(fingerprint),
+                schema_version: 0,
+                _unknown_fields: Default::default(),
+            }))
+        };
+
+        let tx = sample_transaction().build()?;
+        let fingerprint = TransactionFingerprintData::<Fr>::try_from(&tx)?
+            .complete_fingerprint(&NaiveProtocol::<G1>::new(Fr::from(42u64)))
+            .await?
+            .to_bytes();
+
+        // The fingerprint of the transaction matches
+        let response = service
+            .verify_fingerprint(request(&tx, &fingerprint)?)
+            .await?
+            .into_inner();
+        assert!(response.matches);
+
+        // The fingerprint of another transaction doesn't
+        let other = sample_transaction().bic("DEUTDEFF").build()?;
+        let response = service
+            .verify_fingerprint(request(&other, &fingerprint)?)
+            .await?
+            .into_inner();
+        assert!(!response.matches);
+
+        // Claimed fingerprint of a wrong length is rejected
+        let status = service
+            .verify_fingerprint(request(&tx, &fingerprint[..31])?)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        Ok(())
+    }
+
     #[tokio::test]
     pub async fn test_fingerprint_computation() -> Result<(), anyhow::Error> {
         let tx_date = Utc::now();
@@ -258,6 +1203,10 @@ mod tests {
                         _unknown_fields: Default::default(),
                     },
                 ),
+                iban: None,
+                reference: None,
+                value_date: None,
+                direction: Default::default(),
                 _unknown_fields: Default::default(),
             };
 
@@ -266,7 +1215,10 @@ mod tests {
 
         let response = CLIENT
             .compute_single_fingerprint(ComputeSingleFingerprintRequest {
+                tenant: Default::default(),
                 transaction_data: Some(transaction_data),
+                schema_version: 0,
+                neighbour_buckets: 0,
                 _unknown_fields: Default::default(),
             })
             .await?;
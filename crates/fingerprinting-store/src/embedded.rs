@@ -0,0 +1,174 @@
+use anyhow::{anyhow, Error};
+use chrono::{DateTime, Duration, Utc};
+use redb::{Database, MultimapTableDefinition, ReadableMultimapTable};
+use std::path::Path;
+
+use crate::{retained_since, FingerprintStore, Occurrence, StoredFingerprint};
+
+/// Occurrences by the stored fingerprint, every value is the recording time followed by the item id
+const OCCURRENCES: MultimapTableDefinition<&[u8], &[u8]> =
+    MultimapTableDefinition::new("occurrences");
+
+///
+/// Store persisting occurrences in the embedded database file, every recording is a durable transaction
+pub struct EmbeddedStore {
+    database: Database,
+    retention: Duration,
+}
+
+impl EmbeddedStore {
+    /// Open the database file at `path`, it is created when it doesn't exist
+    pub fn open<P: AsRef<Path>>(path: P, retention: Duration) -> Result<Self, Error> {
+        let database = Database::create(path)?;
+
+        // Create the table, so that reads never fail on a new database
+        let write = database.begin_write()?;
+        write.open_multimap_table(OCCURRENCES)?;
+        write.commit()?;
+
+        Ok(Self {
+            database,
+            retention,
+        })
+    }
+}
+
+/// Recording time (milliseconds since Unix epoch, 8 bytes big-endian, so values are ordered by time)
+/// followed by the item id
+fn encode_occurrence(occurrence: &Occurrence) -> Vec<u8> {
+    let mut value = Vec::with_capacity(8 + occurrence.item_id.len());
+    value.extend_from_slice(&occurrence.recorded_at.timestamp_millis().to_be_bytes());
+    value.extend_from_slice(occurrence.item_id.as_bytes());
+    value
+}
+
+fn decode_occurrence(value: &[u8]) -> Result<Occurrence, Error> {
+    let (millis, item_id) = value
+        .split_first_chunk::<8>()
+        .ok_or(anyhow!("Stored occurrence is too short"))?;
+
+    Ok(Occurrence {
+        item_id: String::from_utf8(item_id.to_vec())?,
+        recorded_at: DateTime::from_timestamp_millis(i64::from_be_bytes(*millis))
+            .ok_or(anyhow!("Stored occurrence time is out of range"))?,
+    })
+}
+
+impl FingerprintStore for EmbeddedStore {
+    fn check_and_record(
+        &self,
+        fingerprint: &StoredFingerprint,
+        item_id: &str,
+        recorded_at: DateTime<Utc>,
+    ) -> Result<Vec<Occurrence>, Error> {
+        let since = retained_since(recorded_at, self.retention);
+        let key = fingerprint.to_bytes();
+
+        let write = self.database.begin_write()?;
+        let earlier = {
+            let mut table = write.open_multimap_table(OCCURRENCES)?;
+
+            let mut recorded = Vec::new();
+            for value in table.get(key.as_slice())? {
+                let value = value?.value().to_vec();
+                recorded.push((decode_occurrence(&value)?, value));
+            }
+
+            let mut earlier = Vec::new();
+            let mut already_recorded = false;
+            for (occurrence, value) in recorded {
+                if occurrence.recorded_at < since {
+                    table.remove(key.as_slice(), value.as_slice())?;
+                } else if occurrence.item_id == item_id {
+                    already_recorded = true;
+                } else {
+                    earlier.push(occurrence);
+                }
+            }
+
+            if !already_recorded {
+                let occurrence = Occurrence {
+                    item_id: item_id.to_string(),
+                    recorded_at,
+                };
+                table.insert(key.as_slice(), encode_occurrence(&occurrence).as_slice())?;
+            }
+
+            earlier
+        };
+        write.commit()?;
+
+        Ok(earlier)
+    }
+
+    fn purge(&self, now: DateTime<Utc>) -> Result<usize, Error> {
+        let since = retained_since(now, self.retention);
+
+        let write = self.database.begin_write()?;
+        let purged = {
+            let mut table = write.open_multimap_table(OCCURRENCES)?;
+
+            let mut expired = Vec::new();
+            for entry in table.iter()? {
+                let (key, values) = entry?;
+                for value in values {
+                    let value = value?.value().to_vec();
+                    if decode_occurrence(&value)?.recorded_at < since {
+                        expired.push((key.value().to_vec(), value));
+                    }
+                }
+            }
+
+            for (key, value) in &expired {
+                table.remove(key.as_slice(), value.as_slice())?;
+            }
+
+            expired.len()
+        };
+        write.commit()?;
+
+        if purged > 0 {
+            log::debug!("Purged {} fingerprint occurrences", purged);
+        }
+
+        Ok(purged)
+    }
+
+    fn retention(&self) -> Duration {
+        self.retention
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_embedded_store() -> Result<(), Error> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("fingerprints.redb");
+
+        crate::tests::check_store(&EmbeddedStore::open(&path, Duration::days(7))?)?;
+
+        // Occurrences survive reopening of the database
+        let recorded_at = Utc.with_ymd_and_hms(2025, 10, 1, 10, 0, 0).unwrap();
+        let fingerprint = StoredFingerprint::new(1, [9u8; 32]);
+        {
+            let store = EmbeddedStore::open(&path, Duration::days(7))?;
+            store.check_and_record(&fingerprint, "item-1", recorded_at)?;
+        }
+
+        let store = EmbeddedStore::open(&path, Duration::days(7))?;
+        let earlier = store.check_and_record(&fingerprint, "item-2", recorded_at)?;
+        assert_eq!(
+            earlier,
+            vec![Occurrence {
+                item_id: "item-1".to_string(),
+                recorded_at,
+            }]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+//!
+//! Storage of recorded fingerprints for duplicate detection.
+//!
+//! Every occurrence of a fingerprint is recorded with the id of the item it was computed for,
+//! occurrences older than the retention window are forgotten. Fingerprints are comparable only within
+//! the same schema version and tenant, so both are a part of the stored key.
+use anyhow::Error;
+use chrono::{DateTime, Duration, Utc};
+
+mod embedded;
+mod memory;
+
+pub use embedded::EmbeddedStore;
+pub use memory::MemoryStore;
+
+/// Maximum length of the item id in bytes
+pub const MAX_ITEM_ID_LENGTH: usize = 256;
+
+/// Fingerprint of the schema version and tenant, the key of the recorded occurrences
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoredFingerprint {
+    pub schema_version: u32,
+    pub fingerprint: [u8; 32],
+    /// Tenant the fingerprint was computed for, empty for the default tenant
+    pub tenant: String,
+}
+
+impl StoredFingerprint {
+    pub fn new(schema_version: u32, fingerprint: [u8; 32]) -> Self {
+        Self {
+            schema_version,
+            fingerprint,
+            tenant: String::new(),
+        }
+    }
+
+    pub fn with_tenant(self, tenant: &str) -> Self {
+        Self {
+            tenant: tenant.to_string(),
+            ..self
+        }
+    }
+
+    /// Schema version (4 bytes, big-endian) followed by the fingerprint and the tenant name,
+    /// keys of the default tenant are the same as before tenants were introduced
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(36 + self.tenant.len());
+        bytes.extend_from_slice(&self.schema_version.to_be_bytes());
+        bytes.extend_from_slice(&self.fingerprint);
+        bytes.extend_from_slice(self.tenant.as_bytes());
+        bytes
+    }
+}
+
+/// Earlier occurrence of the fingerprint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub item_id: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+pub trait FingerprintStore: Send + Sync {
+    ///
+    /// Record the occurrence of the `fingerprint` for the `item_id` and return its earlier occurrences
+    /// within the retention window (oldest first). Recording the same item again is idempotent,
+    /// the item is not reported as a duplicate of itself.
+    fn check_and_record(
+        &self,
+        fingerprint: &StoredFingerprint,
+        item_id: &str,
+        recorded_at: DateTime<Utc>,
+    ) -> Result<Vec<Occurrence>, Error>;
+
+    /// Forget occurrences outside of the retention window at `now`, returns the number of forgotten occurrences
+    fn purge(&self, now: DateTime<Utc>) -> Result<usize, Error>;
+
+    /// How long occurrences are remembered
+    fn retention(&self) -> Duration;
+}
+
+/// Occurrences recorded before the returned time are outside of the retention window at `now`
+pub(crate) fn retained_since(now: DateTime<Utc>, retention: Duration) -> DateTime<Utc> {
+    now.checked_sub_signed(retention)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Behaviour every store must have
+    pub(crate) fn check_store(store: &impl FingerprintStore) -> Result<(), Error> {
+        let day = |day| Utc.with_ymd_and_hms(2025, 9, day, 10, 0, 0).unwrap();
+        let fingerprint = StoredFingerprint::new(1, [7u8; 32]);
+
+        assert!(store
+            .check_and_record(&fingerprint, "item-1", day(1))?
+            .is_empty());
+
+        // Recording the same item again is not a duplicate
+        assert!(store
+            .check_and_record(&fingerprint, "item-1", day(2))?
+            .is_empty());
+
+        let earlier = store.check_and_record(&fingerprint, "item-2", day(3))?;
+        assert_eq!(
+            earlier,
+            vec![Occurrence {
+                item_id: "item-1".to_string(),
+                recorded_at: day(1),
+            }]
+        );
+
+        // Same fingerprint of another schema version is another fingerprint
+        assert!(store
+            .check_and_record(&StoredFingerprint::new(2, [7u8; 32]), "item-3", day(3))?
+            .is_empty());
+
+        // Same fingerprint of another tenant is another fingerprint
+        assert!(store
+            .check_and_record(&fingerprint.clone().with_tenant("acme"), "item-3", day(3))?
+            .is_empty());
+
+        // Retention of the stores under the test is 7 days
+        let earlier = store.check_and_record(&fingerprint, "item-4", day(9))?;
+        assert_eq!(
+            earlier
+                .iter()
+                .map(|o| o.item_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["item-2"]
+        );
+
+        assert_eq!(store.purge(day(30))?, 4);
+        assert!(store
+            .check_and_record(&fingerprint, "item-5", day(30))?
+            .is_empty());
+
+        Ok(())
+    }
+}
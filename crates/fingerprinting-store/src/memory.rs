@@ -0,0 +1,92 @@
+use anyhow::{anyhow, Error};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::{retained_since, FingerprintStore, Occurrence, StoredFingerprint};
+
+///
+/// Store keeping occurrences in memory only, they are lost with the restart of the agent
+pub struct MemoryStore {
+    occurrences: Mutex<HashMap<StoredFingerprint, Vec<Occurrence>>>,
+    retention: Duration,
+}
+
+impl MemoryStore {
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            occurrences: Mutex::default(),
+            retention,
+        }
+    }
+}
+
+impl FingerprintStore for MemoryStore {
+    fn check_and_record(
+        &self,
+        fingerprint: &StoredFingerprint,
+        item_id: &str,
+        recorded_at: DateTime<Utc>,
+    ) -> Result<Vec<Occurrence>, Error> {
+        let since = retained_since(recorded_at, self.retention);
+        let mut occurrences = self
+            .occurrences
+            .lock()
+            .map_err(|_| anyhow!("Fingerprint store lock is poisoned"))?;
+
+        let recorded = occurrences.entry(fingerprint.clone()).or_default();
+        recorded.retain(|occurrence| occurrence.recorded_at >= since);
+
+        let earlier = recorded
+            .iter()
+            .filter(|occurrence| occurrence.item_id != item_id)
+            .cloned()
+            .collect();
+
+        if !recorded
+            .iter()
+            .any(|occurrence| occurrence.item_id == item_id)
+        {
+            recorded.push(Occurrence {
+                item_id: item_id.to_string(),
+                recorded_at,
+            });
+            recorded.sort_by_key(|occurrence| occurrence.recorded_at);
+        }
+
+        Ok(earlier)
+    }
+
+    fn purge(&self, now: DateTime<Utc>) -> Result<usize, Error> {
+        let since = retained_since(now, self.retention);
+        let mut occurrences = self
+            .occurrences
+            .lock()
+            .map_err(|_| anyhow!("Fingerprint store lock is poisoned"))?;
+
+        let mut purged = 0;
+        occurrences.retain(|_, recorded| {
+            let before = recorded.len();
+            recorded.retain(|occurrence| occurrence.recorded_at >= since);
+            purged += before - recorded.len();
+
+            !recorded.is_empty()
+        });
+
+        Ok(purged)
+    }
+
+    fn retention(&self) -> Duration {
+        self.retention
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store() -> Result<(), Error> {
+        crate::tests::check_store(&MemoryStore::new(Duration::days(7)))
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use derive_builder::Builder;
 use fixed_num::Dec19x19;
 use fixed_num_helper::FRAC_SCALE_I128;
@@ -7,7 +7,22 @@ pub mod currencies {
     pub use iso4217_static::*;
 }
 
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+
+/// Number of atto units in one whole unit of the currency
+pub const ATTO_PER_UNIT: u64 = 1_000_000_000_000_000_000;
+
+/// Number of decimal places of the atto unit
+const ATTO_DECIMALS: u32 = 18;
+
 // Amount with currency representation
+//
+// Canonical representation of the non-negative amount `units.fraction` is the pair
+// `(amount_base, amount_atto)` of the whole `units` and the `fraction` in atto (10^-18) units,
+// so `amount_atto < 10^18`. The fraction has at most as many decimal places as the minor unit
+// of the currency (ISO 4217 exponent: 2 for EUR, 0 for JPY, 3 for BHD), e.g. 1000.55 USD is
+// `(1000, 550_000_000_000_000_000)`. Currencies without a minor unit (e.g. XAU) allow all 18 places.
 #[derive(Builder, Debug, Clone, PartialEq)]
 #[builder(setter(into))]
 pub struct Money {
@@ -16,6 +31,96 @@ pub struct Money {
     pub currency: currencies::Currency,
 }
 
+impl Money {
+    ///
+    /// Parse the exact decimal `amount` (e.g. `1000.55`, no sign, exponent or separators) of the `currency`,
+    /// amounts with more decimal places than the minor unit of the currency allows are rejected
+    pub fn parse(amount: &str, currency: currencies::Currency) -> Result<Self, anyhow::Error> {
+        let amount = amount.trim();
+
+        if amount.starts_with('-') {
+            return Err(anyhow::anyhow!(
+                "Negative amount {} is not supported, amounts are absolute",
+                amount
+            ));
+        }
+
+        let (units, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+
+        if units.is_empty()
+            || !units.bytes().all(|b| b.is_ascii_digit())
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+            || (amount.contains('.') && fraction.is_empty())
+        {
+            return Err(anyhow::anyhow!(
+                "Amount {} is not a decimal number, expected digits with optional decimal point",
+                amount
+            ));
+        }
+
+        let amount_base = units
+            .parse::<u64>()
+            .map_err(|_| anyhow::anyhow!("Amount {} is too large", amount))?;
+
+        // Trailing zeros don't change the value, only significant decimal places count
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > ATTO_DECIMALS as usize {
+            return Err(anyhow::anyhow!(
+                "Amount {} has more than {} decimal places",
+                amount,
+                ATTO_DECIMALS
+            ));
+        }
+
+        let amount_atto = if fraction.is_empty() {
+            0
+        } else {
+            let padded = format!("{:0<width$}", fraction, width = ATTO_DECIMALS as usize);
+            padded.parse::<u64>()?
+        };
+
+        let money = Money {
+            amount_base,
+            amount_atto,
+            currency,
+        };
+        money.validate()?;
+
+        Ok(money)
+    }
+
+    /// Decimal places of the minor unit of the currency, `None` for currencies without minor unit
+    pub fn minor_unit(&self) -> Option<u8> {
+        self.currency.minor_unit()
+    }
+
+    /// Check the amount is in the canonical representation and fits the minor unit of the currency
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.amount_atto >= ATTO_PER_UNIT {
+            return Err(anyhow::anyhow!(
+                "Atto part {} of the amount must be less than {}",
+                self.amount_atto,
+                ATTO_PER_UNIT
+            ));
+        }
+
+        let decimals = self.minor_unit().map_or(ATTO_DECIMALS, u32::from);
+        let minor_unit_atto = 10u64.pow(ATTO_DECIMALS - decimals.min(ATTO_DECIMALS));
+
+        if !self.amount_atto.is_multiple_of(minor_unit_atto) {
+            return Err(anyhow::anyhow!(
+                "Amount {}.{:018} has more decimal places than {} allows ({})",
+                self.amount_base,
+                self.amount_atto,
+                self.currency.as_str(),
+                decimals
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 impl Default for Money {
     fn default() -> Self {
         Money {
@@ -26,6 +131,30 @@ impl Default for Money {
     }
 }
 
+/// Credit-debit indicator of the transaction from the point of view of the reporting account,
+/// amounts are absolute and the direction tells a debit from its reversal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Debit,
+    Credit,
+}
+
+impl std::str::FromStr for Direction {
+    type Err = anyhow::Error;
+
+    /// Parse `debit` / `credit` or the ISO 20022 codes `DBIT` / `CRDT`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "debit" | "DBIT" => Ok(Direction::Debit),
+            "credit" | "CRDT" => Ok(Direction::Credit),
+            _ => Err(anyhow::anyhow!(
+                "Unknown direction {}, expected debit (DBIT) or credit (CRDT)",
+                value
+            )),
+        }
+    }
+}
+
 // Raw Transaction representation
 #[derive(Default, Builder, Debug, Clone, PartialEq)]
 #[builder(setter(into))]
@@ -33,12 +162,23 @@ pub struct RawTransaction {
     pub bic: String,
     pub amount: Money,
     pub date_time: DateTime<Utc>,
+    /// Account of the transaction (ISO 13616), validated when fingerprinted
+    #[builder(setter(into, strip_option), default)]
+    pub iban: Option<String>,
+    /// End-to-end reference of the payment, UETR (UUIDv4) or EndToEndId, validated when fingerprinted
+    #[builder(setter(into, strip_option), default)]
+    pub reference: Option<String>,
+    /// Settlement / value date (World Wide Date), the date of `date_time` when not given
+    #[builder(setter(into, strip_option), default)]
+    pub value_date: Option<NaiveDate>,
+    /// Credit-debit indicator, unknown when not given
+    #[builder(setter(into, strip_option), default)]
+    pub direction: Option<Direction>,
 }
 
 impl TryFrom<(Dec19x19, &str)> for Money {
     type Error = anyhow::Error;
 
-    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
     fn try_from(value: (Dec19x19, &str)) -> Result<Self, Self::Error> {
         let amount = value.0;
         let currency = currencies::Currency::try_from(value.1).map_err(|_| {
@@ -48,11 +188,46 @@ impl TryFrom<(Dec19x19, &str)> for Money {
             )
         })?;
 
-        Ok(Money {
-            amount_base: (amount.repr / FRAC_SCALE_I128) as u64,
-            amount_atto: (amount.repr % FRAC_SCALE_I128) as u64 / 10,
+        if amount.repr < 0 {
+            return Err(anyhow::anyhow!(
+                "Negative amount {} is not supported, amounts are absolute",
+                amount
+            ));
+        }
+
+        // Dec19x19 has 19 decimal places, the last one is below the atto precision
+        let fraction = amount.repr % FRAC_SCALE_I128;
+        if fraction % 10 != 0 {
+            return Err(anyhow::anyhow!(
+                "Amount {} has more than {} decimal places",
+                amount,
+                ATTO_DECIMALS
+            ));
+        }
+
+        let money = Money {
+            amount_base: u64::try_from(amount.repr / FRAC_SCALE_I128)?,
+            amount_atto: u64::try_from(fraction / 10)?,
             currency,
-        })
+        };
+        money.validate()?;
+
+        Ok(money)
+    }
+}
+
+impl TryFrom<(&str, &str)> for Money {
+    type Error = anyhow::Error;
+
+    fn try_from(value: (&str, &str)) -> Result<Self, Self::Error> {
+        let currency = currencies::Currency::try_from(value.1).map_err(|_| {
+            anyhow::anyhow!(
+                "Provided invalid currency code: {}, expected ISO 4217 code",
+                value.1
+            )
+        })?;
+
+        Money::parse(value.0, currency)
     }
 }
 
@@ -67,8 +242,15 @@ impl TryFrom<(i32, &str)> for Money {
             )
         })?;
 
+        let amount_base = u64::try_from(value.0).map_err(|_| {
+            anyhow::anyhow!(
+                "Negative amount {} is not supported, amounts are absolute",
+                value.0
+            )
+        })?;
+
         Ok(Money {
-            amount_base: u64::from(value.0.unsigned_abs()),
+            amount_base,
             amount_atto: 0,
             currency,
         })
@@ -103,8 +285,15 @@ impl TryFrom<(i64, &str)> for Money {
             )
         })?;
 
+        let amount_base = u64::try_from(value.0).map_err(|_| {
+            anyhow::anyhow!(
+                "Negative amount {} is not supported, amounts are absolute",
+                value.0
+            )
+        })?;
+
         Ok(Money {
-            amount_base: value.0.unsigned_abs(),
+            amount_base,
             amount_atto: 0,
             currency,
         })
@@ -139,16 +328,82 @@ mod tests {
 
         let money_1 = MoneyBuilder::default()
             .amount_base(1000u32)
-            .amount_atto(554325 * 10u64.pow(12)) // .5
+            .amount_atto(55 * 10u64.pow(16)) // .55
             .currency(currency)
             .build()
             .unwrap();
 
-        let money_2: Money = (Dec19x19!(1000.554325), "132").try_into().unwrap();
+        let money_2: Money = (Dec19x19!(1000.55), "USD").try_into().unwrap();
+        let money_3: Money = ("1000.550", "USD").try_into().unwrap();
 
         println!("Builder money:{:?}", money_1);
         println!("Converted money:{:?}", money_2);
 
         assert_eq!(money_1, money_2);
+        assert_eq!(money_1, money_3);
+
+        // Negative amounts are rejected instead of dropping the sign
+        assert!(Money::try_from((Dec19x19!(-1000.55), "USD")).is_err());
+        assert!(Money::try_from((-1000i64, "USD")).is_err());
+        assert!(Money::try_from((-1000i32, "USD")).is_err());
+
+        // Precision beyond the minor unit of the currency
+        assert!(Money::try_from((Dec19x19!(1000.554325), "USD")).is_err());
+        assert!(Money::try_from(("1000.5", "JPY")).is_err());
+    }
+
+    #[test]
+    pub fn test_direction_parse() {
+        assert_eq!("DBIT".parse::<Direction>().unwrap(), Direction::Debit);
+        assert_eq!("credit".parse::<Direction>().unwrap(), Direction::Credit);
+        assert!("reversal".parse::<Direction>().is_err());
+    }
+
+    #[test]
+    pub fn test_money_parse() -> Result<(), anyhow::Error> {
+        let currency = |code: &str| currencies::Currency::try_from(code).unwrap();
+
+        let yen = Money::parse("1000", currency("JPY"))?;
+        assert_eq!((yen.amount_base, yen.amount_atto), (1000, 0));
+
+        let dinar = Money::parse(" 12.345 ", currency("BHD"))?;
+        assert_eq!(
+            (dinar.amount_base, dinar.amount_atto),
+            (12, 345 * 10u64.pow(15))
+        );
+        assert!(Money::parse("12.3456", currency("BHD")).is_err());
+
+        // Currency without minor unit allows atto precision
+        let gold = Money::parse("0.000000000000000001", currency("XAU"))?;
+        assert_eq!((gold.amount_base, gold.amount_atto), (0, 1));
+        assert!(Money::parse("0.0000000000000000001", currency("XAU")).is_err());
+
+        let euro = currency("EUR");
+        for invalid in [
+            "",
+            ".5",
+            "5.",
+            "-5",
+            "+5",
+            "1,000.00",
+            "1e3",
+            "18446744073709551616",
+        ] {
+            assert!(
+                Money::parse(invalid, euro).is_err(),
+                "{} is accepted",
+                invalid
+            );
+        }
+
+        // Non canonical atto part
+        let money = Money {
+            amount_base: 1,
+            amount_atto: ATTO_PER_UNIT,
+            currency: euro,
+        };
+        assert!(money.validate().is_err());
+
+        Ok(())
     }
 }
@@ -0,0 +1,33 @@
+//!
+//! Sample transactions shared by the tests of the workspace crates, enabled with the `test-utils` feature.
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::currencies::Currency;
+use crate::{Money, MoneyBuilder, RawTransactionBuilder};
+
+/// Booking date time of the sample transaction, 2025-09-16 10:00:00 UTC
+pub fn sample_date_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 9, 16, 10, 0, 0).unwrap()
+}
+
+/// Amount of `amount_base` whole euros
+pub fn euros(amount_base: u64) -> Money {
+    MoneyBuilder::default()
+        .currency(Currency::Euro)
+        .amount_base(amount_base)
+        .amount_atto(0u64)
+        .build()
+        .unwrap()
+}
+
+///
+/// Builder of the sample transaction of 100 EUR at `BCEELU21` booked at [`sample_date_time`],
+/// tests override the fields they are about on the returned builder
+pub fn sample_transaction() -> RawTransactionBuilder {
+    let mut tx = RawTransactionBuilder::default();
+    tx.bic("BCEELU21")
+        .amount(euros(100))
+        .date_time(sample_date_time());
+
+    tx
+}
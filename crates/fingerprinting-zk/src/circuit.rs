@@ -0,0 +1,378 @@
+//!
+//! Circuit proving the fingerprint and the commitment are squeezed from the same canonical serialization
+//! of the transaction, and the amount of the transaction in the public currency is at least the public floor.
+//!
+//! The serialized bytes are range checked by the lookup into the byte table and packed into limbs the same way
+//! `HashSqueeze for Bytes` does, the fingerprint chains the limbs from zero, the commitment from the blinding.
+use anyhow::{anyhow, Error};
+use fingerprinting_core::schema::{FingerprintSchema, SchemaComponent};
+use fingerprinting_core::squeeze_limb_size;
+use halo2_axiom::circuit::{Cell, Layouter, Region, SimpleFloorPlanner, Value};
+use halo2_axiom::halo2curves::bn256::Fr;
+use halo2_axiom::plonk::{
+    self, Advice, Circuit, Column, ConstraintSystem, Instance, Selector, TableColumn,
+};
+use halo2_axiom::poly::Rotation;
+
+use crate::poseidon::{PoseidonChip, PoseidonConfig, WIDTH};
+
+/// Rows of the public inputs in the instance column
+pub(crate) const FINGERPRINT_ROW: usize = 0;
+pub(crate) const COMMITMENT_ROW: usize = 1;
+pub(crate) const CURRENCY_ROW: usize = 2;
+pub(crate) const AMOUNT_FLOOR_ROW: usize = 3;
+
+/// The amount is a 256-bit big-endian integer in atto units, valid amounts fit into its lower 128 bits
+const AMOUNT_HIGH_BYTES: usize = 16;
+const AMOUNT_LOW_BYTES: usize = 16;
+
+/// Assigned cell together with its value
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Variable {
+    pub(crate) cell: Cell,
+    pub(crate) value: Value<Fr>,
+}
+
+/// Positions of the proven parts in the serialized data of the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Layout {
+    prefix: [u8; 8],
+    size: usize,
+    amount: usize,
+    currency: usize,
+}
+
+impl Layout {
+    pub(crate) fn new(schema: &FingerprintSchema) -> Result<Self, Error> {
+        let offset = |component| {
+            schema.offset(component).ok_or(anyhow!(
+                "Schema {} doesn't include the {:?} component, fingerprints of it can't be proven",
+                schema.version(),
+                component
+            ))
+        };
+
+        Ok(Self {
+            prefix: *schema.prefix(),
+            size: schema.size(),
+            amount: offset(SchemaComponent::Amount)?,
+            currency: offset(SchemaComponent::Currency)?,
+        })
+    }
+
+    /// Amount of the transaction in atto units, `None` when it doesn't fit into 128 bits
+    pub(crate) fn amount(&self, serialized: &[u8]) -> Option<u128> {
+        let (high, low) = serialized
+            [self.amount..self.amount + AMOUNT_HIGH_BYTES + AMOUNT_LOW_BYTES]
+            .split_at(AMOUNT_HIGH_BYTES);
+
+        if high.iter().any(|byte| *byte != 0) {
+            return None;
+        }
+
+        Some(u128::from_be_bytes(low.try_into().ok()?))
+    }
+
+    /// ISO 4217 numeric code of the currency of the transaction
+    pub(crate) fn currency(&self, serialized: &[u8]) -> u16 {
+        u16::from_be_bytes([serialized[self.currency], serialized[self.currency + 1]])
+    }
+}
+
+/// Limbs of the serialized data, each limb is the little-endian number of its bytes as in `HashSqueeze for Bytes`
+pub(crate) fn limbs(serialized: &[u8]) -> Vec<Fr> {
+    serialized
+        .chunks(squeeze_limb_size(serialized.len()))
+        .map(|chunk| {
+            chunk.iter().rev().fold(Fr::zero(), |acc, byte| {
+                acc * Fr::from(256) + Fr::from(*byte as u64)
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FingerprintConfig {
+    advice: [Column<Advice>; WIDTH],
+    instance: Column<Instance>,
+    bytes: TableColumn,
+    pack_first: Selector,
+    pack_next: Selector,
+    add: Selector,
+    poseidon: PoseidonConfig,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FingerprintCircuit {
+    pub(crate) layout: Layout,
+    pub(crate) serialized: Value<Vec<u8>>,
+    pub(crate) blinding: Value<Fr>,
+    pub(crate) amount_floor: Value<u128>,
+}
+
+impl FingerprintCircuit {
+    /// Circuit of the `layout` without witnesses, for generating keys
+    pub(crate) fn empty(layout: Layout) -> Self {
+        Self {
+            layout,
+            serialized: Value::unknown(),
+            blinding: Value::unknown(),
+            amount_floor: Value::unknown(),
+        }
+    }
+
+    ///
+    /// Pack the big-endian `bytes` into a number, each byte is range checked by the lookup.
+    /// Packed bytes are constrained equal to the `copy_of` cells, when they are given.
+    fn pack(
+        config: &FingerprintConfig,
+        region: &mut Region<'_, Fr>,
+        offset: &mut usize,
+        bytes: &[Value<Fr>],
+        copy_of: &[Cell],
+    ) -> Result<(Vec<Variable>, Variable), plonk::Error> {
+        let mut packed = Vec::with_capacity(bytes.len());
+        let mut acc = Value::known(Fr::zero());
+        let mut acc_cell = None;
+
+        for (i, byte) in bytes.iter().enumerate() {
+            let row = *offset + i;
+            if i == 0 {
+                config.pack_first.enable(region, row)?;
+            } else {
+                config.pack_next.enable(region, row)?;
+            }
+
+            let cell = region.assign_advice(config.advice[0], row, *byte).cell();
+            if let Some(source) = copy_of.get(i) {
+                region.constrain_equal(cell, *source);
+            }
+            packed.push(Variable { cell, value: *byte });
+
+            acc = acc.zip(*byte).map(|(acc, byte)| acc * Fr::from(256) + byte);
+            acc_cell = Some(region.assign_advice(config.advice[1], row, acc).cell());
+        }
+        *offset += bytes.len();
+
+        let cell = acc_cell.ok_or(plonk::Error::Synthesis)?;
+        Ok((packed, Variable { cell, value: acc }))
+    }
+
+    /// Pack the `bytes` assigned before, e.g. to read a component out of the serialized data
+    fn repack(
+        config: &FingerprintConfig,
+        region: &mut Region<'_, Fr>,
+        offset: &mut usize,
+        bytes: &[Variable],
+    ) -> Result<Variable, plonk::Error> {
+        let values = bytes.iter().map(|byte| byte.value).collect::<Vec<_>>();
+        let cells = bytes.iter().map(|byte| byte.cell).collect::<Vec<_>>();
+
+        Ok(Self::pack(config, region, offset, &values, &cells)?.1)
+    }
+}
+
+impl Circuit<Fr> for FingerprintCircuit {
+    type Config = FingerprintConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::empty(self.layout.clone())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let advice = [(); WIDTH].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        let bytes = meta.lookup_table_column();
+        let pack_first = meta.complex_selector();
+        let pack_next = meta.complex_selector();
+        let add = meta.selector();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        let poseidon = PoseidonChip::configure(meta, advice);
+
+        // acc = byte for the first byte, acc = 256 * previous acc + byte for the others
+        meta.create_gate("pack", |meta| {
+            let first = meta.query_selector(pack_first);
+            let next = meta.query_selector(pack_next);
+            let byte = meta.query_advice(advice[0], Rotation::cur());
+            let acc = meta.query_advice(advice[1], Rotation::cur());
+            let previous = meta.query_advice(advice[1], Rotation::prev());
+
+            vec![
+                first * (acc.clone() - byte.clone()),
+                next * (acc - previous * Fr::from(256) - byte),
+            ]
+        });
+
+        meta.lookup("byte", |meta| {
+            let selected = meta.query_selector(pack_first) + meta.query_selector(pack_next);
+            let byte = meta.query_advice(advice[0], Rotation::cur());
+
+            vec![(selected * byte, bytes)]
+        });
+
+        meta.create_gate("add", |meta| {
+            let q = meta.query_selector(add);
+            let [a, b, c] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            vec![q * (a + b - c)]
+        });
+
+        FingerprintConfig {
+            advice,
+            instance,
+            bytes,
+            pack_first,
+            pack_next,
+            add,
+            poseidon,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), plonk::Error> {
+        let layout = &self.layout;
+        let byte_values = (0..layout.size)
+            .map(|i| {
+                self.serialized
+                    .as_ref()
+                    .map(|serialized| Fr::from(serialized[i] as u64))
+            })
+            .collect::<Vec<_>>();
+
+        layouter.assign_table(
+            || "bytes",
+            |mut table| {
+                for byte in 0..256 {
+                    table.assign_cell(
+                        || "byte",
+                        config.bytes,
+                        byte,
+                        || Value::known(Fr::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        // The whole circuit is one region, gadgets take its rows one after another
+        let [fingerprint, commitment, currency] = layouter.assign_region(
+            || "fingerprint",
+            |mut region| {
+                let region = &mut region;
+                let mut offset = 0;
+
+                // Limbs are little-endian, so their bytes are packed from the last one
+                let limb_size = squeeze_limb_size(layout.size);
+                let mut bytes = vec![None; layout.size];
+                let mut limbs = Vec::new();
+                for start in (0..layout.size).step_by(limb_size) {
+                    let end = (start + limb_size).min(layout.size);
+                    let reversed = byte_values[start..end]
+                        .iter()
+                        .rev()
+                        .copied()
+                        .collect::<Vec<_>>();
+                    let (packed, limb) = Self::pack(&config, region, &mut offset, &reversed, &[])?;
+
+                    for (position, byte) in (start..end).rev().zip(packed) {
+                        bytes[position] = Some(byte);
+                    }
+                    limbs.push(limb);
+                }
+                let bytes = bytes
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(plonk::Error::Synthesis)?;
+
+                for (byte, expected) in bytes.iter().zip(layout.prefix) {
+                    region.constrain_constant(byte.cell, Fr::from(expected as u64))?;
+                }
+                for byte in &bytes[layout.amount..layout.amount + AMOUNT_HIGH_BYTES] {
+                    region.constrain_constant(byte.cell, Fr::zero())?;
+                }
+
+                let zero = Value::known(Fr::zero());
+                let zero_cell = region.assign_advice(config.advice[0], offset, zero).cell();
+                region.constrain_constant(zero_cell, Fr::zero())?;
+                let blinding_cell = region
+                    .assign_advice(config.advice[1], offset, self.blinding)
+                    .cell();
+                offset += 1;
+
+                let poseidon = PoseidonChip::construct(config.poseidon.clone());
+                let mut fingerprint = Variable {
+                    cell: zero_cell,
+                    value: zero,
+                };
+                let mut commitment = Variable {
+                    cell: blinding_cell,
+                    value: self.blinding,
+                };
+                for limb in &limbs {
+                    fingerprint = poseidon.hash(region, &mut offset, limb, &fingerprint)?;
+                    commitment = poseidon.hash(region, &mut offset, limb, &commitment)?;
+                }
+
+                let currency = Self::repack(
+                    &config,
+                    region,
+                    &mut offset,
+                    &bytes[layout.currency..layout.currency + 2],
+                )?;
+
+                // floor + difference = amount, where all of them are below 2^128, so amount >= floor
+                let amount_start = layout.amount + AMOUNT_HIGH_BYTES;
+                let amount = Self::repack(
+                    &config,
+                    region,
+                    &mut offset,
+                    &bytes[amount_start..amount_start + AMOUNT_LOW_BYTES],
+                )?;
+
+                let difference = self
+                    .serialized
+                    .as_ref()
+                    .zip(self.amount_floor)
+                    .map(|(serialized, floor)| {
+                        layout
+                            .amount(serialized)
+                            .unwrap_or_default()
+                            .wrapping_sub(floor)
+                            .to_be_bytes()
+                    })
+                    .transpose_array()
+                    .map(|byte| byte.map(|byte| Fr::from(byte as u64)));
+                let (_, difference) = Self::pack(&config, region, &mut offset, &difference, &[])?;
+
+                config.add.enable(region, offset)?;
+                region.assign_advice_from_instance(
+                    || "amount floor",
+                    config.instance,
+                    AMOUNT_FLOOR_ROW,
+                    config.advice[0],
+                    offset,
+                )?;
+                for (column, variable) in config.advice[1..].iter().zip([&difference, &amount]) {
+                    let cell = region.assign_advice(*column, offset, variable.value).cell();
+                    region.constrain_equal(cell, variable.cell);
+                }
+
+                Ok([fingerprint.cell, commitment.cell, currency.cell])
+            },
+        )?;
+
+        layouter.constrain_instance(fingerprint, config.instance, FINGERPRINT_ROW);
+        layouter.constrain_instance(commitment, config.instance, COMMITMENT_ROW);
+        layouter.constrain_instance(currency, config.instance, CURRENCY_ROW);
+
+        Ok(())
+    }
+}
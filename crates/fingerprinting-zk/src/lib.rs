@@ -0,0 +1,410 @@
+//!
+//! Zero-knowledge proofs that a fingerprint belongs to a committed transaction with a public property.
+//!
+//! The bank commits to the canonical serialization of the transaction (including the date time component
+//! processed by the protocol) with a random blinding when it records the transaction. Later it proves, e.g. to
+//! a regulator, that the fingerprint is squeezed from the same serialization as the commitment and the amount
+//! of the transaction in the given currency is at least the given floor, without revealing the transaction.
+//!
+//! Proofs are PLONK proofs with KZG commitments over BN254. Keys are derived from the setup parameters and the
+//! fingerprint schema, so the prover and the verifier only have to share the parameters.
+use anyhow::{anyhow, Error};
+use fingerprinting_core::schema::FingerprintSchema;
+use fingerprinting_core::TransactionFingerprintData;
+use fingerprinting_types::{Money, ATTO_PER_UNIT};
+use halo2_axiom::circuit::Value;
+use halo2_axiom::halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2_axiom::halo2curves::ff::PrimeField;
+use halo2_axiom::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey, VerifyingKey,
+};
+use halo2_axiom::poly::commitment::Params;
+use halo2_axiom::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_axiom::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_axiom::poly::kzg::strategy::SingleStrategy;
+use halo2_axiom::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2_axiom::SerdeFormat;
+use rand_core::RngCore;
+use std::io::{Read, Write};
+
+mod circuit;
+mod poseidon;
+
+use crate::circuit::{
+    limbs, FingerprintCircuit, Layout, AMOUNT_FLOOR_ROW, COMMITMENT_ROW, CURRENCY_ROW,
+    FINGERPRINT_ROW,
+};
+
+/// The circuit has `2^K` rows, enough for the schema with all components
+pub const K: u32 = 10;
+
+///
+/// Setup parameters shared by provers and verifiers.
+///
+/// Whoever knows the secret of the setup can forge proofs, so production parameters come from
+/// a setup ceremony (e.g. a powers of tau transcript), `SetupParams::generate` is for tests and demos only.
+#[derive(Debug, Clone)]
+pub struct SetupParams(ParamsKZG<Bn256>);
+
+impl SetupParams {
+    /// Fresh parameters, the caller learns the secret of the setup
+    pub fn generate<R: RngCore>(rng: R) -> Self {
+        Self(ParamsKZG::setup(K, rng))
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let params = ParamsKZG::read_custom(reader, SerdeFormat::RawBytes)?;
+
+        if params.k() != K {
+            return Err(anyhow!(
+                "Setup parameters are for 2^{} rows, expected 2^{}",
+                params.k(),
+                K
+            ));
+        }
+
+        Ok(Self(params))
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        Ok(self.0.write_custom(writer, SerdeFormat::RawBytes)?)
+    }
+}
+
+/// Public inputs of the proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FingerprintStatement {
+    pub fingerprint: Fr,
+    /// Commitment to the serialized transaction, see `commit`
+    pub commitment: Fr,
+    /// ISO 4217 numeric code of the currency of the transaction
+    pub currency: u16,
+    /// Minimal amount of the transaction in atto units
+    pub amount_floor: u128,
+}
+
+impl FingerprintStatement {
+    ///
+    /// Statement that the `fingerprint` and the `commitment` belong to a transaction of at least the `amount_floor`
+    pub fn new(fingerprint: Fr, commitment: Fr, amount_floor: &Money) -> Self {
+        Self {
+            fingerprint,
+            commitment,
+            currency: amount_floor.currency as u16,
+            amount_floor: amount_floor.amount_base as u128 * ATTO_PER_UNIT as u128
+                + amount_floor.amount_atto as u128,
+        }
+    }
+
+    fn instances(&self) -> Vec<Fr> {
+        let mut instances = vec![Fr::zero(); 4];
+        instances[FINGERPRINT_ROW] = self.fingerprint;
+        instances[COMMITMENT_ROW] = self.commitment;
+        instances[CURRENCY_ROW] = Fr::from(self.currency as u64);
+        instances[AMOUNT_FLOOR_ROW] = Fr::from_u128(self.amount_floor);
+        instances
+    }
+}
+
+///
+/// Commitment to the transaction `tx` with the date time component `date_time` processed by the protocol,
+/// the `blinding` must be random and kept secret together with the transaction
+pub fn commit(
+    tx: &TransactionFingerprintData<Fr>,
+    date_time: Fr,
+    blinding: Fr,
+) -> Result<Fr, Error> {
+    let serialized = tx.serialize_with_date_time(date_time)?;
+
+    poseidon::chain(&limbs(&serialized), blinding)
+}
+
+/// Prover of fingerprints of the schema
+pub struct FingerprintProver {
+    params: ParamsKZG<Bn256>,
+    pk: ProvingKey<G1Affine>,
+    schema: FingerprintSchema,
+    layout: Layout,
+}
+
+impl FingerprintProver {
+    /// Derive the proving key, the schema must include the amount and the currency components
+    pub fn new(params: &SetupParams, schema: &FingerprintSchema) -> Result<Self, Error> {
+        let layout = Layout::new(schema)?;
+        let circuit = FingerprintCircuit::empty(layout.clone());
+
+        let vk = keygen_vk(&params.0, &circuit)?;
+        let pk = keygen_pk(&params.0, vk, &circuit)?;
+
+        Ok(Self {
+            params: params.0.clone(),
+            pk,
+            schema: schema.clone(),
+            layout,
+        })
+    }
+
+    ///
+    /// Prove the fingerprint of the transaction `tx` with the date time component `date_time` processed by
+    /// the protocol belongs to the transaction committed with the `blinding`, and the amount of the transaction
+    /// is at least the `amount_floor`. Returns the statement and the proof of it.
+    pub fn prove<R: RngCore>(
+        &self,
+        tx: &TransactionFingerprintData<Fr>,
+        date_time: Fr,
+        blinding: Fr,
+        amount_floor: &Money,
+        rng: R,
+    ) -> Result<(FingerprintStatement, Vec<u8>), Error> {
+        if tx.schema() != &self.schema {
+            return Err(anyhow!(
+                "Transaction is fingerprinted with schema {}, the prover is for schema {}",
+                tx.schema().version(),
+                self.schema.version()
+            ));
+        }
+
+        let serialized = tx.serialize_with_date_time(date_time)?;
+        let limbs = limbs(&serialized);
+        let statement = FingerprintStatement::new(
+            poseidon::chain(&limbs, Fr::zero())?,
+            poseidon::chain(&limbs, blinding)?,
+            amount_floor,
+        );
+
+        if self.layout.currency(&serialized) != statement.currency {
+            return Err(anyhow!(
+                "Currency of the transaction is not the currency of the amount floor"
+            ));
+        }
+        if self
+            .layout
+            .amount(&serialized)
+            .is_none_or(|amount| amount < statement.amount_floor)
+        {
+            return Err(anyhow!(
+                "Amount of the transaction is below the amount floor"
+            ));
+        }
+
+        let circuit = FingerprintCircuit {
+            layout: self.layout.clone(),
+            serialized: Value::known(serialized.to_vec()),
+            blinding: Value::known(blinding),
+            amount_floor: Value::known(statement.amount_floor),
+        };
+        let instances = statement.instances();
+
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _, _>(
+            &self.params,
+            &self.pk,
+            &[circuit],
+            &[&[instances.as_slice()]],
+            rng,
+            &mut transcript,
+        )?;
+
+        Ok((statement, transcript.finalize()))
+    }
+}
+
+/// Verifier of fingerprint proofs of the schema
+pub struct FingerprintVerifier {
+    params: ParamsKZG<Bn256>,
+    vk: VerifyingKey<G1Affine>,
+}
+
+impl FingerprintVerifier {
+    /// Derive the verifying key, it is the same as the one of the prover with the same parameters and schema
+    pub fn new(params: &SetupParams, schema: &FingerprintSchema) -> Result<Self, Error> {
+        let circuit = FingerprintCircuit::empty(Layout::new(schema)?);
+
+        Ok(Self {
+            params: params.0.clone(),
+            vk: keygen_vk(&params.0, &circuit)?,
+        })
+    }
+
+    ///
+    /// Verify the `proof` of the `statement`. The verifier builds the statement from the values it expects
+    /// (e.g. the fingerprint it received and the floor it asked for), not from the values of the prover.
+    pub fn verify(&self, statement: &FingerprintStatement, proof: &[u8]) -> Result<(), Error> {
+        let instances = statement.instances();
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+
+        verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+            &self.params,
+            &self.vk,
+            SingleStrategy::new(&self.params),
+            &[&[instances.as_slice()]],
+            &mut transcript,
+        )
+        .map_err(|e| anyhow!("Fingerprint proof is invalid: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fingerprinting_core::schema::SchemaComponent;
+    use fingerprinting_core::HashSqueeze;
+    use fingerprinting_types::currencies::Currency;
+    use fingerprinting_types::test_utils::sample_transaction;
+    use halo2_axiom::dev::MockProver;
+    use rand_core::OsRng;
+
+    fn money(amount: &str, currency: &str) -> Result<Money, Error> {
+        let currency = Currency::try_from(currency).map_err(|_| anyhow!("Unknown currency"))?;
+
+        Money::parse(amount, currency)
+    }
+
+    fn transaction() -> Result<TransactionFingerprintData<Fr>, Error> {
+        let tx = sample_transaction()
+            .amount(money("1250.50", "EUR")?)
+            .build()?;
+
+        tx.try_into()
+    }
+
+    #[test]
+    fn test_circuit_matches_fingerprint() -> Result<(), Error> {
+        let tx = transaction()?;
+        let date_time = Fr::from(123456789);
+        let blinding = Fr::from(42);
+
+        let serialized = tx.serialize_with_date_time(date_time)?;
+        assert_eq!(
+            poseidon::chain(&limbs(&serialized), Fr::zero())?,
+            tx.fingerprint_with_date_time(date_time)?
+        );
+        assert_eq!(
+            poseidon::chain(&limbs(&serialized), Fr::zero())?,
+            serialized.squeeze()?
+        );
+
+        let layout = Layout::new(tx.schema())?;
+        let statement = FingerprintStatement::new(
+            tx.fingerprint_with_date_time(date_time)?,
+            commit(&tx, date_time, blinding)?,
+            &money("1000", "EUR")?,
+        );
+        let circuit = |floor: u128| FingerprintCircuit {
+            layout: layout.clone(),
+            serialized: Value::known(serialized.to_vec()),
+            blinding: Value::known(blinding),
+            amount_floor: Value::known(floor),
+        };
+
+        let prover = MockProver::run(
+            K,
+            &circuit(statement.amount_floor),
+            vec![statement.instances()],
+        )?;
+        assert_eq!(prover.verify(), Ok(()));
+
+        // Amount below the floor
+        let above = FingerprintStatement {
+            amount_floor: statement.amount_floor * 2,
+            ..statement
+        };
+        let prover = MockProver::run(K, &circuit(above.amount_floor), vec![above.instances()])?;
+        assert!(prover.verify().is_err());
+
+        // Another currency
+        let dollars = FingerprintStatement {
+            currency: 840,
+            ..statement
+        };
+        let prover = MockProver::run(K, &circuit(dollars.amount_floor), vec![dollars.instances()])?;
+        assert!(prover.verify().is_err());
+
+        // Schema with all components fits into the circuit
+        let schema = FingerprintSchema::builder(2)
+            .component(SchemaComponent::BankIdentifier)
+            .component(SchemaComponent::Iban)
+            .component(SchemaComponent::Reference)
+            .component(SchemaComponent::Direction)
+            .component(SchemaComponent::Amount)
+            .component(SchemaComponent::Currency)
+            .component(SchemaComponent::DateTime)
+            .build()?;
+        let tx = transaction()?.with_schema(schema);
+        let statement = FingerprintStatement::new(
+            tx.fingerprint_with_date_time(date_time)?,
+            commit(&tx, date_time, blinding)?,
+            &money("1250.50", "EUR")?,
+        );
+        let circuit = FingerprintCircuit {
+            layout: Layout::new(tx.schema())?,
+            serialized: Value::known(tx.serialize_with_date_time(date_time)?.to_vec()),
+            blinding: Value::known(blinding),
+            amount_floor: Value::known(statement.amount_floor),
+        };
+        let prover = MockProver::run(K, &circuit, vec![statement.instances()])?;
+        assert_eq!(prover.verify(), Ok(()));
+
+        // Schema without the amount can't be proven
+        assert!(Layout::new(
+            &FingerprintSchema::builder(3)
+                .component(SchemaComponent::DateTime)
+                .build()?
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prove_and_verify() -> Result<(), Error> {
+        let tx = transaction()?;
+        let date_time = Fr::from(123456789);
+        let blinding = Fr::from(42);
+
+        let params = SetupParams::generate(OsRng);
+        let prover = FingerprintProver::new(&params, tx.schema())?;
+        let verifier = FingerprintVerifier::new(&params, tx.schema())?;
+
+        let floor = money("1000", "EUR")?;
+        let (statement, proof) = prover.prove(&tx, date_time, blinding, &floor, OsRng)?;
+        assert_eq!(
+            statement.fingerprint,
+            tx.fingerprint_with_date_time(date_time)?
+        );
+        assert_eq!(statement.commitment, commit(&tx, date_time, blinding)?);
+
+        verifier.verify(&statement, &proof)?;
+
+        // The proof is bound to the statement
+        let other = FingerprintStatement {
+            fingerprint: statement.fingerprint + Fr::one(),
+            ..statement
+        };
+        assert!(verifier.verify(&other, &proof).is_err());
+        let lower = FingerprintStatement::new(
+            statement.fingerprint,
+            statement.commitment,
+            &money("500", "EUR")?,
+        );
+        assert!(verifier.verify(&lower, &proof).is_err());
+
+        // The prover refuses statements that don't hold
+        assert!(prover
+            .prove(&tx, date_time, blinding, &money("5000", "EUR")?, OsRng)
+            .is_err());
+        assert!(prover
+            .prove(&tx, date_time, blinding, &money("10", "USD")?, OsRng)
+            .is_err());
+
+        // Parameters survive serialization
+        let mut buffer = Vec::new();
+        params.write(&mut buffer)?;
+        let read = SetupParams::read(&mut buffer.as_slice())?;
+        FingerprintVerifier::new(&read, tx.schema())?.verify(&statement, &proof)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+//!
+//! Poseidon permutation of width 3 compatible with circom (and `pso_poseidon`), one row per round.
+//!
+//! The state before the round is in the current row, the state after it in the next one. Full rounds
+//! apply the S-box `x^5` to the whole state, partial rounds to the first element only. Squares of the S-box
+//! inputs are witnessed in the round row, which keeps the round gates within degree 4.
+use anyhow::Error;
+use halo2_axiom::circuit::{Region, Value};
+use halo2_axiom::halo2curves::bn256::Fr;
+use halo2_axiom::plonk::{self, Advice, Column, ConstraintSystem, Expression, Fixed, Selector};
+use halo2_axiom::poly::Rotation;
+use pso_poseidon::params::get_poseidon_parameters;
+use pso_poseidon::{Poseidon, PoseidonHasher, PoseidonParameters};
+
+use crate::circuit::Variable;
+
+/// Domain tag followed by two inputs
+pub(crate) const WIDTH: usize = 3;
+
+#[derive(Debug, Clone)]
+pub(crate) struct PoseidonConfig {
+    state: [Column<Advice>; WIDTH],
+    squares: [Column<Advice>; WIDTH],
+    round_constants: [Column<Fixed>; WIDTH],
+    full_round: Selector,
+    partial_round: Selector,
+}
+
+pub(crate) struct PoseidonChip {
+    config: PoseidonConfig,
+    params: PoseidonParameters<Fr>,
+}
+
+fn parameters() -> PoseidonParameters<Fr> {
+    get_poseidon_parameters::<Fr>(WIDTH as u8).expect("Poseidon parameters of width 3 are defined")
+}
+
+impl PoseidonChip {
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<Fr>,
+        state: [Column<Advice>; WIDTH],
+    ) -> PoseidonConfig {
+        let params = parameters();
+        let squares = [(); WIDTH].map(|_| meta.advice_column());
+        let round_constants = [(); WIDTH].map(|_| meta.fixed_column());
+        let full_round = meta.selector();
+        let partial_round = meta.selector();
+
+        for column in state {
+            meta.enable_equality(column);
+        }
+
+        for (name, selector, full) in [
+            ("poseidon full round", full_round, true),
+            ("poseidon partial round", partial_round, false),
+        ] {
+            let mds = params.mds.clone();
+
+            meta.create_gate(name, |meta| {
+                let q = meta.query_selector(selector);
+                let current = state.map(|column| meta.query_advice(column, Rotation::cur()));
+                let next = state.map(|column| meta.query_advice(column, Rotation::next()));
+                let squared = squares.map(|column| meta.query_advice(column, Rotation::cur()));
+                let constants =
+                    round_constants.map(|column| meta.query_fixed(column, Rotation::cur()));
+
+                let mut constraints = Vec::new();
+                let mut sbox = Vec::with_capacity(WIDTH);
+                for i in 0..WIDTH {
+                    let x = current[i].clone() + constants[i].clone();
+
+                    if full || i == 0 {
+                        constraints.push(q.clone() * (squared[i].clone() - x.clone() * x.clone()));
+                        sbox.push(squared[i].clone() * squared[i].clone() * x);
+                    } else {
+                        sbox.push(x);
+                    }
+                }
+
+                for i in 0..WIDTH {
+                    let mixed = (0..WIDTH).fold(Expression::Constant(Fr::zero()), |acc, j| {
+                        acc + sbox[j].clone() * Expression::Constant(mds[i][j])
+                    });
+
+                    constraints.push(q.clone() * (next[i].clone() - mixed));
+                }
+
+                constraints
+            });
+        }
+
+        PoseidonConfig {
+            state,
+            squares,
+            round_constants,
+            full_round,
+            partial_round,
+        }
+    }
+
+    pub(crate) fn construct(config: PoseidonConfig) -> Self {
+        Self {
+            config,
+            params: parameters(),
+        }
+    }
+
+    fn rounds(&self) -> usize {
+        self.params.full_rounds + self.params.partial_rounds
+    }
+
+    fn is_full_round(&self, round: usize) -> bool {
+        let half = self.params.full_rounds / 2;
+
+        round < half || round >= half + self.params.partial_rounds
+    }
+
+    fn round(&self, round: usize, state: [Fr; WIDTH]) -> [Fr; WIDTH] {
+        let sbox: [Fr; WIDTH] = std::array::from_fn(|i| {
+            let x = state[i] + self.params.ark[round * WIDTH + i];
+            if self.is_full_round(round) || i == 0 {
+                x.square().square() * x
+            } else {
+                x
+            }
+        });
+
+        std::array::from_fn(|i| {
+            (0..WIDTH).fold(Fr::zero(), |acc, j| acc + sbox[j] * self.params.mds[i][j])
+        })
+    }
+
+    ///
+    /// `Poseidon(left, right)` as computed by `Poseidon::new_circom(2)`, the domain tag is zero.
+    /// The permutation takes the rows of the `region` from the `offset`, the offset is moved past them.
+    pub(crate) fn hash(
+        &self,
+        region: &mut Region<'_, Fr>,
+        offset: &mut usize,
+        left: &Variable,
+        right: &Variable,
+    ) -> Result<Variable, plonk::Error> {
+        let config = &self.config;
+        let start = *offset;
+
+        let mut state = left
+            .value
+            .zip(right.value)
+            .map(|(left, right)| [Fr::zero(), left, right]);
+
+        let tag = region.assign_advice(config.state[0], start, Value::known(Fr::zero()));
+        region.constrain_constant(tag.cell(), Fr::zero())?;
+        for (column, input) in config.state[1..].iter().zip([left, right]) {
+            let cell = region.assign_advice(*column, start, input.value);
+            region.constrain_equal(cell.cell(), input.cell);
+        }
+
+        let mut output = None;
+        for round in 0..self.rounds() {
+            let row = start + round;
+            let full = self.is_full_round(round);
+            if full {
+                config.full_round.enable(region, row)?;
+            } else {
+                config.partial_round.enable(region, row)?;
+            }
+            for (i, column) in config.round_constants.iter().enumerate() {
+                let constant = self.params.ark[round * WIDTH + i];
+                region.assign_fixed(*column, row, constant);
+
+                if full || i == 0 {
+                    let square = state.map(|state| (state[i] + constant).square());
+                    region.assign_advice(config.squares[i], row, square);
+                }
+            }
+
+            state = state.map(|state| self.round(round, state));
+            for (i, column) in config.state.iter().enumerate() {
+                let cell = region.assign_advice(*column, row + 1, state.map(|state| state[i]));
+                if i == 0 {
+                    output = Some(cell.cell());
+                }
+            }
+        }
+        *offset = start + self.rounds() + 1;
+
+        // The output is the first element of the state after the last round
+        Ok(Variable {
+            cell: output.ok_or(plonk::Error::Synthesis)?,
+            value: state.map(|state| state[0]),
+        })
+    }
+}
+
+/// `Poseidon(limb, last_hash)` chained over the `limbs` starting from `initial`
+pub(crate) fn chain(limbs: &[Fr], initial: Fr) -> Result<Fr, Error> {
+    let mut poseidon = Poseidon::<Fr>::new_circom(2)?;
+
+    let mut last_hash = initial;
+    for limb in limbs {
+        last_hash = poseidon.hash(&[*limb, last_hash])?;
+    }
+
+    Ok(last_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_axiom::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_axiom::dev::MockProver;
+    use halo2_axiom::plonk::{Circuit, Instance};
+
+    #[derive(Default)]
+    struct HashCircuit {
+        left: Value<Fr>,
+        right: Value<Fr>,
+    }
+
+    impl Circuit<Fr> for HashCircuit {
+        type Config = (PoseidonConfig, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let state = [(); WIDTH].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            meta.enable_constant(constant);
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (PoseidonChip::configure(meta, state), state[0], instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), plonk::Error> {
+            let hash = layouter.assign_region(
+                || "poseidon",
+                |mut region| {
+                    let left = Variable {
+                        cell: region.assign_advice(input, 0, self.left).cell(),
+                        value: self.left,
+                    };
+                    let right = Variable {
+                        cell: region.assign_advice(input, 1, self.right).cell(),
+                        value: self.right,
+                    };
+
+                    PoseidonChip::construct(config).hash(&mut region, &mut 2, &left, &right)
+                },
+            )?;
+            layouter.constrain_instance(hash.cell, instance, 0);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_poseidon_chip() -> Result<(), Error> {
+        let (left, right) = (Fr::from(42), Fr::from(7));
+        let expected = Poseidon::<Fr>::new_circom(2)?.hash(&[left, right])?;
+        let circuit = HashCircuit {
+            left: Value::known(left),
+            right: Value::known(right),
+        };
+
+        let prover = MockProver::run(8, &circuit, vec![vec![expected]])?;
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(8, &circuit, vec![vec![expected + Fr::one()]])?;
+        assert!(prover.verify().is_err());
+
+        Ok(())
+    }
+}
@@ -46,20 +46,20 @@ impl Health for HealthRegistry {
             .filter(|s| s.name() == service_name)
             .map(|s| {
                 if s.is_serving() {
-                    Ok(Response::new(HealthCheckResponse {
+                    Response::new(HealthCheckResponse {
                         status: ServingStatus::SERVING,
                         _unknown_fields: Default::default(),
-                    }))
+                    })
                 } else {
-                    Ok(Response::new(HealthCheckResponse {
+                    Response::new(HealthCheckResponse {
                         status: ServingStatus::NOT_SERVING,
                         _unknown_fields: Default::default(),
-                    }))
+                    })
                 }
             })
             .next_back();
 
-        result.ok_or(Status::not_found("Service not found"))?
+        result.ok_or(Status::not_found("Service not found"))
     }
 
     async fn watch(